@@ -0,0 +1,241 @@
+//! Python bindings of the `lcov` crate.
+//!
+//! The extension module is named `lcov`, and provides the `Report` and `Stats` classes. See
+//! `README.md` for an example.
+use lcov::filter::{MinCount, SectionFilter};
+use lcov::report::summary;
+use lcov::report::ParseOptions;
+use lcov::Reader;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Coverage information accumulated from LCOV tracefiles.
+#[pyclass(name = "Report", module = "lcov")]
+#[derive(Debug, Clone, Default)]
+struct PyReport {
+    inner: lcov::Report,
+}
+
+#[pymethods]
+impl PyReport {
+    /// Creates an empty report.
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a tracefile.
+    ///
+    /// Raises `ValueError` if the tracefile is malformed, or contains unknown records with
+    /// `strict=True`.
+    #[staticmethod]
+    #[pyo3(signature = (path, strict = false))]
+    fn from_file(path: PathBuf, strict: bool) -> PyResult<Self> {
+        let reader = Reader::open_file(path)?.lossless_paths(true);
+        Self::read(reader, strict)
+    }
+
+    /// Parses the content of a tracefile.
+    #[staticmethod]
+    #[pyo3(signature = (data, strict = false))]
+    fn parse(data: &[u8], strict: bool) -> PyResult<Self> {
+        Self::read(Reader::new(data).lossless_paths(true), strict)
+    }
+
+    /// Merges another report into this report.
+    ///
+    /// Raises `ValueError` if function start lines, line checksums or source file revisions
+    /// conflict. This report may be partially merged in that case.
+    fn merge(&mut self, other: &Self) -> PyResult<()> {
+        self.inner.merge(other.inner.clone()).map_err(value_error)
+    }
+
+    /// Merges another report into this report, keeping the values of `other` on conflicts.
+    fn merge_lossy(&mut self, other: &Self) {
+        self.inner.merge_lossy(other.inner.clone());
+    }
+
+    /// Keeps only the source files for which `predicate(path)` is true.
+    fn retain_files(&mut self, predicate: &Bound<'_, PyAny>) -> PyResult<()> {
+        let mut error = None;
+        self.inner
+            .sections
+            .retain_paths(|path| call_predicate(predicate, &path.to_string_lossy(), &mut error));
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Keeps only the functions for which `predicate(name)` is true.
+    fn retain_functions(&mut self, predicate: &Bound<'_, PyAny>) -> PyResult<()> {
+        let mut error = None;
+        self.inner.retain_functions(|_, function, _| {
+            call_predicate(predicate, function.name.as_str(), &mut error)
+        });
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Removes the lines executed fewer than `min` times, and the branches on them.
+    ///
+    /// Functions, and branches on lines without line data, are kept.
+    fn min_count(&mut self, min: u64) {
+        MinCount::new(min).filter_sections(&mut self.inner.sections);
+    }
+
+    /// Returns the execution count of a line, summed across all tests.
+    fn line_count(&self, path: PathBuf, line: u32) -> Option<u64> {
+        self.inner.line_count(path, line)
+    }
+
+    /// Returns the numbers of found and hit functions, branches and lines of the whole report.
+    fn summary(&self) -> PyStats {
+        self.inner.summary().total.into()
+    }
+
+    /// Returns the numbers of found and hit functions, branches and lines of each source file.
+    fn file_summaries(&self) -> HashMap<String, PyStats> {
+        self.inner
+            .summary()
+            .files
+            .into_iter()
+            .map(|(path, stats)| (path.to_string_lossy().into_owned(), stats.into()))
+            .collect()
+    }
+
+    /// Writes the report as a tracefile.
+    fn write(&self, path: PathBuf) -> PyResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn __str__(&self) -> String {
+        let mut output = vec![];
+        self.write_to(&mut output)
+            .expect("writing to a `Vec` never fails");
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl PyReport {
+    fn read<B>(reader: Reader<B>, strict: bool) -> PyResult<Self>
+    where
+        B: io::BufRead,
+    {
+        let options = ParseOptions::new().strict(strict);
+        let inner = lcov::Report::from_reader_with(reader, &options).map_err(value_error)?;
+        Ok(PyReport { inner })
+    }
+
+    fn write_to<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        for rec in self.inner.clone().into_records() {
+            rec.write_to(writer)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Numbers of found and hit functions, branches and lines.
+#[pyclass(name = "Stats", module = "lcov", frozen, get_all)]
+#[derive(Debug, Clone, Copy)]
+struct PyStats {
+    functions_found: u32,
+    functions_hit: u32,
+    branches_found: u32,
+    branches_hit: u32,
+    lines_found: u32,
+    lines_hit: u32,
+}
+
+#[pymethods]
+impl PyStats {
+    /// Ratio of functions hit, or `None` if no functions are found.
+    #[getter]
+    fn function_rate(&self) -> Option<f64> {
+        summary::Counter::new(self.functions_found, self.functions_hit).rate()
+    }
+
+    /// Ratio of branches hit, or `None` if no branches are found.
+    #[getter]
+    fn branch_rate(&self) -> Option<f64> {
+        summary::Counter::new(self.branches_found, self.branches_hit).rate()
+    }
+
+    /// Ratio of lines hit, or `None` if no lines are found.
+    #[getter]
+    fn line_rate(&self) -> Option<f64> {
+        summary::Counter::new(self.lines_found, self.lines_hit).rate()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Stats(functions={}/{}, branches={}/{}, lines={}/{})",
+            self.functions_hit,
+            self.functions_found,
+            self.branches_hit,
+            self.branches_found,
+            self.lines_hit,
+            self.lines_found
+        )
+    }
+}
+
+impl From<summary::Stats> for PyStats {
+    fn from(stats: summary::Stats) -> Self {
+        PyStats {
+            functions_found: stats.functions.found,
+            functions_hit: stats.functions.hit,
+            branches_found: stats.branches.found,
+            branches_hit: stats.branches.hit,
+            lines_found: stats.lines.found,
+            lines_hit: stats.lines.hit,
+        }
+    }
+}
+
+// Calls a Python predicate from a Rust one. Once the predicate raises an exception, the
+// exception is kept in `error` and everything else is kept.
+fn call_predicate(predicate: &Bound<'_, PyAny>, arg: &str, error: &mut Option<PyErr>) -> bool {
+    if error.is_some() {
+        return true;
+    }
+    match predicate.call1((arg,)).and_then(|res| res.is_truthy()) {
+        Ok(keep) => keep,
+        Err(e) => {
+            *error = Some(e);
+            true
+        }
+    }
+}
+
+fn value_error<E>(e: E) -> PyErr
+where
+    E: fmt::Display,
+{
+    PyValueError::new_err(e.to_string())
+}
+
+/// LCOV tracefile parser/merger/filter.
+#[pymodule]
+#[pyo3(name = "lcov")]
+fn lcov_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyReport>()?;
+    m.add_class::<PyStats>()?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Processing of LCOV tracefiles in memory.
+//!
+//! The functions in this module take tracefiles as byte slices and return plain values, without
+//! touching the file system. They are meant to be wrapped for environments such as WebAssembly
+//! modules called from JavaScript, where tracefiles are fetched or uploaded as buffers.
+//!
+//! This module is available only if the `bytes` feature is enabled.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+//! use lcov::bytes;
+//!
+//! let input1 = b"TN:\nSF:foo.c\nDA:1,1\nDA:2,0\nend_of_record\n";
+//! let input2 = b"TN:\nSF:foo.c\nDA:2,3\nend_of_record\n";
+//!
+//! let merged = bytes::merge([&input1[..], &input2[..]])?;
+//! let summary = bytes::summary(&merged)?;
+//! assert_eq!(summary.total.lines.hit, 2);
+//! # Ok(())
+//! # }
+//! # fn main() {
+//! # try_main().expect("failed to run test");
+//! # }
+//! ```
+use crate::report::summary::Summary;
+use crate::report::{MergeError, ParseError};
+use crate::{Reader, Report};
+
+/// All possible errors that can occur when processing tracefiles in memory.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An error indicating that a tracefile cannot be parsed.
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// An error indicating that tracefiles cannot be merged.
+    #[error(transparent)]
+    Merge(#[from] MergeError),
+}
+
+/// Parses a tracefile.
+///
+/// Non-UTF-8 source file paths are kept as they are on Unix, as [`Reader::lossless_paths`] does.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// let report = lcov::bytes::parse(b"TN:\nSF:foo.c\nDA:1,1\nend_of_record\n")?;
+/// assert_eq!(report.line_count("foo.c", 1), Some(1));
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+///
+/// [`Reader::lossless_paths`]: ../reader/struct.Reader.html#method.lossless_paths
+pub fn parse(input: &[u8]) -> Result<Report, ParseError> {
+    Report::from_reader(Reader::new(input).lossless_paths(true))
+}
+
+/// Merges tracefiles, and returns the merged tracefile.
+///
+/// See the [module-level documentation](index.html#examples) for an example.
+pub fn merge<I>(inputs: I) -> Result<Vec<u8>, Error>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut report = Report::new();
+    for input in inputs {
+        report.merge(parse(input.as_ref())?)?;
+    }
+    Ok(to_vec(report))
+}
+
+/// Computes the summary of a tracefile.
+///
+/// See the [module-level documentation](index.html#examples) for an example.
+pub fn summary(input: &[u8]) -> Result<Summary, ParseError> {
+    Ok(parse(input)?.summary())
+}
+
+/// Converts a report into a tracefile.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "foo.c").add_line(1, 1);
+/// let output = lcov::bytes::to_vec(report);
+/// assert!(output.starts_with(b"TN:\nSF:foo.c\nDA:1,1\n"));
+/// ```
+pub fn to_vec(report: Report) -> Vec<u8> {
+    let mut output = vec![];
+    for rec in report.into_records() {
+        rec.write_to(&mut output)
+            .expect("writing to a `Vec` never fails");
+        output.push(b'\n');
+    }
+    output
+}
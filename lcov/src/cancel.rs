@@ -0,0 +1,73 @@
+//! Cancellation of long-running operations.
+//!
+//! Parsing and merging large tracefiles may take minutes. Applications which run them in the
+//! background, such as GUIs and daemons, can abort them by a [`CancellationToken`] shared with
+//! the operation.
+//!
+//! [`CancellationToken`]: struct.CancellationToken.html
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag shared between an operation and its callers to request cancellation of the operation.
+///
+/// Clones of a token share the same flag, so cancelling one of them cancels all of them.
+/// Operations check the flag periodically (e.g. for each section), and return an error once
+/// the flag is set.
+///
+/// # Examples
+///
+/// ```rust
+/// # use matches::assert_matches;
+/// # fn main() {
+/// use lcov::{Reader, Report};
+/// use lcov::cancel::CancellationToken;
+/// use lcov::report::{ParseError, ParseOptions};
+///
+/// let token = CancellationToken::new();
+/// let options = ParseOptions::new().cancellation(token.clone());
+///
+/// let input = "TN:\nSF:foo.c\nDA:1,1\nend_of_record\n";
+/// assert!(Report::from_reader_with(Reader::new(input.as_bytes()), &options).is_ok());
+///
+/// token.cancel();
+/// assert_matches!(Report::from_reader_with(Reader::new(input.as_bytes()), &options),
+///                 Err(ParseError::Cancelled));
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of the operations sharing this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if cancellation is requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn shared_between_threads() {
+        let token = CancellationToken::new();
+        assert!(!token.is_cancelled());
+
+        let other = token.clone();
+        thread::spawn(move || other.cancel()).join().unwrap();
+        assert!(token.is_cancelled());
+    }
+}
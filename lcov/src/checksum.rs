@@ -0,0 +1,135 @@
+//! Checksums of source lines, computed in the same way as `geninfo --checksum`.
+//!
+//! The checksum of a line is the MD5 digest of the line without the line terminator, encoded in
+//! Base64 without padding. Checksums are stored in [`line::Value::checksum`] and used to detect
+//! tracefiles measured against other versions of the source files.
+//!
+//! This module is available only if the `checksum` feature is enabled.
+//!
+//! [`line::Value::checksum`]: ../report/section/line/struct.Value.html#structfield.checksum
+use crate::Report;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A line whose checksum differs from the one computed from the source file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mismatch {
+    /// Path of the source file.
+    pub source_file: PathBuf,
+    /// Line number.
+    pub line: u32,
+    /// Checksum in the report.
+    pub expected: String,
+    /// Checksum of the line in the source file, or `None` if the file is shorter.
+    pub actual: Option<String>,
+}
+
+/// Computes the checksum of a line without the line terminator.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::checksum;
+///
+/// assert_eq!(checksum::line(b""), "1B2M2Y8AsgTpgAmY7PhCfg");
+/// assert_eq!(checksum::line(b"int main() {"), checksum::line(b"int main() {"));
+/// ```
+pub fn line(line: &[u8]) -> String {
+    STANDARD_NO_PAD.encode(Md5::digest(line))
+}
+
+/// Fills in missing checksums of lines and verifies the existing ones against the source files.
+///
+/// Relative paths of source files are resolved against `source_dir`. Returns the lines whose
+/// checksums don't match, which are left as they are.
+pub fn apply(report: &mut Report, source_dir: &Path) -> io::Result<Vec<Mismatch>> {
+    let mut cache = HashMap::<PathBuf, Vec<String>>::new();
+    let mut mismatches = vec![];
+    for (key, value) in &mut report.sections {
+        if value.lines.is_empty() {
+            continue;
+        }
+        if !cache.contains_key(&key.source_file) {
+            let path = source_dir.join(&key.source_file);
+            let content = fs::read(&path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("failed to read {}: {}", path.display(), e),
+                )
+            })?;
+            let checksums = content
+                .split(|&b| b == b'\n')
+                .map(|l| line(l.strip_suffix(b"\r").unwrap_or(l)))
+                .collect();
+            let _ = cache.insert(key.source_file.clone(), checksums);
+        }
+        let checksums = &cache[&key.source_file];
+        for (lkey, lvalue) in &mut value.lines {
+            let actual = lkey
+                .line
+                .checked_sub(1)
+                .and_then(|i| checksums.get(i as usize));
+            match (&lvalue.checksum, actual) {
+                (None, Some(actual)) => lvalue.checksum = Some(actual.clone()),
+                (None, None) => {}
+                (Some(expected), actual) if actual != Some(expected) => mismatches.push(Mismatch {
+                    source_file: key.source_file.clone(),
+                    line: lkey.line,
+                    expected: expected.clone(),
+                    actual: actual.cloned(),
+                }),
+                (Some(_), _) => {}
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, line};
+    use crate::Report;
+
+    #[test]
+    fn digest() {
+        assert_eq!(line(b""), "1B2M2Y8AsgTpgAmY7PhCfg");
+        assert_eq!(line(b"abc"), "kAFQmDzST7DWlj99KOF/cg");
+    }
+
+    #[test]
+    fn fill_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("main.c"),
+            "int main() {\r\n  return 0;\n}\n",
+        )
+        .unwrap();
+
+        let mut report = Report::new();
+        let _ = report.section("", "main.c").add_line(1, 1).add_line(2, 1);
+        let _ = report.section("t", "main.c").add_line(5, 0);
+        let mismatches = apply(&mut report, dir.path()).unwrap();
+        assert!(mismatches.is_empty());
+        let (_, value) = report.sections.iter().next().unwrap();
+        let checksums = value
+            .lines
+            .values()
+            .map(|line| line.checksum.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            checksums,
+            [Some(line(b"int main() {")), Some(line(b"  return 0;"))]
+        );
+
+        std::fs::write(dir.path().join("main.c"), "int main() {\n  return 1;\n}\n").unwrap();
+        let mismatches = apply(&mut report, dir.path()).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].line, 2);
+        assert_eq!(mismatches[0].actual, Some(line(b"  return 1;")));
+    }
+}
@@ -0,0 +1,70 @@
+//! A [`SectionFilter`] that keeps only the functions with the matching names.
+//!
+//! See [`FunctionName`] documentation for more.
+//!
+//! [`SectionFilter`]: ../trait.SectionFilter.html
+//! [`FunctionName`]: struct.FunctionName.html
+use super::SectionFilter;
+use crate::report::section;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// A [`SectionFilter`] that keeps only the functions whose name satisfies the predicate.
+///
+/// Only the function records (`FN` and `FNDA`) are filtered. Lines and branches in the functions
+/// are kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{FunctionName, SectionFilter};
+///
+/// let mut report = Report::new();
+/// let _ = report
+///     .section("", "src/main.rs")
+///     .add_function("main", 1, 1)
+///     .add_function("__cxx_global_var_init", 10, 1);
+///
+/// let filter = FunctionName::new(|name| !name.starts_with("__"));
+/// filter.filter_sections(&mut report.sections);
+/// assert_eq!(report.function_count("src/main.rs", "main"), Some(1));
+/// assert_eq!(report.function_count("src/main.rs", "__cxx_global_var_init"), None);
+/// ```
+///
+/// [`SectionFilter`]: ../trait.SectionFilter.html
+#[derive(Clone, Copy)]
+pub struct FunctionName<F> {
+    predicate: F,
+}
+
+impl<F> fmt::Debug for FunctionName<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FunctionName {{ .. }}")
+    }
+}
+
+impl<F> FunctionName<F>
+where
+    F: Fn(&str) -> bool,
+{
+    /// Creates a filter which keeps the functions whose name satisfies `predicate`.
+    pub fn new(predicate: F) -> Self {
+        FunctionName { predicate }
+    }
+}
+
+impl<F> SectionFilter for FunctionName<F>
+where
+    F: Fn(&str) -> bool,
+{
+    fn keeps_function(
+        &self,
+        _key: &section::Key,
+        _value: &section::Value,
+        name: &str,
+        _lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        (self.predicate)(name)
+    }
+}
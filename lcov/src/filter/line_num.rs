@@ -4,12 +4,14 @@
 //!
 //! [`Section`]: ../../report/section/index.html
 //! [`LineNum`]: struct.LineNum.html
-use super::FilterMap;
+use super::{FilterMap, SectionFilter};
 use crate::report::section;
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, Bound};
+use std::collections::{BTreeMap, Bound, HashMap, HashSet};
 use std::iter::{self, Extend, FromIterator};
-use std::{mem, ops};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::{fmt, mem, ops};
 
 /// A [`Section`] filter that extracts only the records related to the specified line numbers.
 ///
@@ -88,6 +90,74 @@ impl LineNum {
         self.extend(iter::once(range));
     }
 
+    /// Returns `true` if the filter contains no lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::filter::LineNum;
+    ///
+    /// let mut filter = LineNum::new();
+    /// assert!(filter.is_empty());
+    /// filter.insert(3..4);
+    /// assert!(!filter.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.start2end.is_empty()
+    }
+
+    /// Returns an iterator over the disjoint ranges of lines, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::filter::LineNum;
+    ///
+    /// let filter = LineNum::from_iter([10..20, 1..3, 3..5]);
+    /// assert_eq!(filter.ranges().collect::<Vec<_>>(), [1..=4, 10..=19]);
+    /// ```
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<u32>> + '_ {
+        self.start2end.iter().map(|(&start, &end)| start..=end)
+    }
+
+    /// Joins the ranges separated by at most `gap` lines.
+    ///
+    /// This is useful for displaying lines as hunks, ignoring the lines between them such as
+    /// blank lines or comments. Note that the gaps are filled regardless of their contents, so
+    /// the ranges must not be separated by lines which should be kept out of them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::filter::LineNum;
+    ///
+    /// let mut filter = LineNum::from_iter([1..3, 5..6, 10..11]);
+    /// filter.fill_gaps(2);
+    /// assert_eq!(filter.to_string(), "1-5, 10");
+    /// ```
+    pub fn fill_gaps(&mut self, gap: u32) {
+        let mut joined = BTreeMap::new();
+        let mut cur = None::<(u32, u32)>;
+        for (start, end) in mem::take(&mut self.start2end) {
+            cur = match cur {
+                Some((cur_start, cur_end))
+                    if u64::from(start) <= u64::from(cur_end) + u64::from(gap) + 1 =>
+                {
+                    Some((cur_start, u32::max(cur_end, end)))
+                }
+                Some((cur_start, cur_end)) => {
+                    let _ = joined.insert(cur_start, cur_end);
+                    Some((start, end))
+                }
+                None => Some((start, end)),
+            };
+        }
+        if let Some((start, end)) = cur {
+            let _ = joined.insert(start, end);
+        }
+        self.start2end = joined;
+    }
+
     /// Applies the filter to `section`.
     /// # Examples
     ///
@@ -121,32 +191,14 @@ impl LineNum {
     /// # fn main() {}
     /// ```
     pub fn apply(&self, section: &mut section::Value) {
-        let mut functions = mem::take(&mut section.functions)
-            .into_iter()
-            .filter_map(|(key, value)| {
-                value
-                    .start_line
-                    .map(|start_line| (start_line, 0, key, value))
-            })
-            .collect::<Vec<_>>();
-        functions.sort_by_key(|&(start_line, _, _, _)| start_line);
-        {
-            let mut end = u32::MAX;
-            for data in functions.iter_mut().rev() {
-                data.1 = end;
-                end = u32::saturating_sub(data.0, 1);
-            }
-        }
-        let functions = functions
-            .into_iter()
-            .filter_map(|(start_line, end_line, key, value)| {
-                if self.contains(Range::new(start_line, end_line)) {
-                    Some((key, value))
-                } else {
-                    None
-                }
-            });
-        section.functions.extend(functions);
+        // Functions without start lines are removed, as they have no lines.
+        let kept = section
+            .function_regions()
+            .iter()
+            .filter(|(_, lines)| self.contains(Range::new(*lines.start(), *lines.end())))
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<_>>();
+        section.functions.retain(|key, _| kept.contains(key));
 
         section.branches.filter_map(|(key, value)| {
             if self.contains(Range::from_line(key.line)) {
@@ -164,7 +216,7 @@ impl LineNum {
         });
     }
 
-    fn contains<R>(&self, range: R) -> bool
+    pub(crate) fn contains<R>(&self, range: R) -> bool
     where
         R: Into<Range>,
     {
@@ -235,6 +287,99 @@ where
     }
 }
 
+/// Formats the ranges of lines, e.g. `1-5, 10, 20-`.
+impl fmt::Display for LineNum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (&start, &end)) in self.start2end.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match end {
+                _ if end == start => write!(f, "{}", start)?,
+                u32::MAX => write!(f, "{}-", start)?,
+                _ => write!(f, "{}-{}", start, end)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies the filter to the lines of all sections, as [`LineNum::apply`] does.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{LineNum, SectionFilter};
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(5, 1);
+///
+/// let filter = LineNum::from_iter([0..3]);
+/// filter.filter_sections(&mut report.sections);
+/// assert_eq!(report.line_count("src/main.rs", 1), Some(1));
+/// assert_eq!(report.line_count("src/main.rs", 5), None);
+/// ```
+///
+/// [`LineNum::apply`]: struct.LineNum.html#method.apply
+impl SectionFilter for LineNum {
+    fn keeps_function(
+        &self,
+        _key: &section::Key,
+        _value: &section::Value,
+        _name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        lines.is_some_and(|lines| self.contains(Range::new(*lines.start(), *lines.end())))
+    }
+
+    fn keeps_line(&self, _key: &section::Key, _value: &section::Value, line: u32) -> bool {
+        self.contains(Range::from_line(line))
+    }
+}
+
+/// Applies the filter of each source file, and removes the sections of the other source files.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{LineNum, SectionFilter};
+/// use std::collections::HashMap;
+/// use std::path::PathBuf;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(5, 1);
+/// let _ = report.section("", "src/lib.rs").add_line(1, 1);
+///
+/// let mut filter = HashMap::new();
+/// let _ = filter.insert(PathBuf::from("src/main.rs"), LineNum::from_iter([0..3]));
+/// filter.filter_sections(&mut report.sections);
+/// assert_eq!(report.sections.len(), 1);
+/// assert_eq!(report.line_count("src/main.rs", 5), None);
+/// ```
+impl SectionFilter for HashMap<PathBuf, LineNum> {
+    fn keeps_section(&self, key: &section::Key, _value: &section::Value) -> bool {
+        self.contains_key(&key.source_file)
+    }
+
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        self.get(&key.source_file)
+            .is_some_and(|filter| filter.keeps_function(key, value, name, lines))
+    }
+
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        self.get(&key.source_file)
+            .is_some_and(|filter| filter.keeps_line(key, value, line))
+    }
+}
+
 /// A range of lines.
 #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
 pub struct Range {
@@ -267,7 +412,7 @@ impl From<ops::RangeFull> for Range {
 }
 
 impl Range {
-    fn new(start: u32, end: u32) -> Self {
+    pub(crate) fn new(start: u32, end: u32) -> Self {
         Range { start, end }
     }
 
@@ -305,6 +450,8 @@ impl Range {
 #[cfg(test)]
 mod tests {
     use super::{LineNum, Range};
+    use crate::filter::SectionFilter;
+    use crate::Report;
 
     #[test]
     fn join() {
@@ -345,6 +492,29 @@ mod tests {
         check(&file, &[(10, 40), (50, 100)]);
     }
 
+    #[test]
+    fn fill_gaps() {
+        fn check(gap: u32, ranges: &[(u32, u32)], expect: &[(u32, u32)]) {
+            let mut file = LineNum::default();
+            for &(start, end) in ranges {
+                file.insert(Range::new(start, end));
+            }
+            file.fill_gaps(gap);
+            assert_eq!(file.start2end, expect.iter().cloned().collect());
+        }
+        let max = u32::MAX;
+        check(0, &[(1, 2), (4, 5)], &[(1, 2), (4, 5)]);
+        check(1, &[(1, 2), (4, 5)], &[(1, 5)]);
+        check(1, &[(1, 2), (5, 6)], &[(1, 2), (5, 6)]);
+        check(
+            3,
+            &[(1, 2), (5, 6), (10, 10), (15, 15)],
+            &[(1, 10), (15, 15)],
+        );
+        check(max, &[(0, 0), (max, max)], &[(0, max)]);
+        check(2, &[], &[]);
+    }
+
     #[test]
     fn contains() {
         fn gen_file(i: u32, n: u32) -> (LineNum, Vec<bool>) {
@@ -373,4 +543,35 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn function_end_lines() {
+        let input = "TN:\nSF:a.c\nFN:1,3,foo\nFN:10,bar\nFNDA:1,foo\nFNDA:1,bar\n\
+                     DA:2,1\nDA:6,1\nDA:11,1\nend_of_record\n";
+        let names = |report: &Report| {
+            let (_, section) = report.sections.iter().next().unwrap();
+            section
+                .functions
+                .keys()
+                .map(|key| key.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Line 6 is out of `foo`, which ends at line 3.
+        let filter = LineNum::from_iter([Range::new(5, 7)]);
+        let mut report = input.parse::<Report>().unwrap();
+        filter.apply(report.sections.values_mut().next().unwrap());
+        assert!(names(&report).is_empty());
+        let mut report = input.parse::<Report>().unwrap();
+        filter.filter_sections(&mut report.sections);
+        assert!(names(&report).is_empty());
+
+        let filter = LineNum::from_iter([Range::new(3, 7)]);
+        let mut report = input.parse::<Report>().unwrap();
+        filter.apply(report.sections.values_mut().next().unwrap());
+        assert_eq!(names(&report), ["foo"]);
+        let mut report = input.parse::<Report>().unwrap();
+        filter.filter_sections(&mut report.sections);
+        assert_eq!(names(&report), ["foo"]);
+    }
 }
@@ -0,0 +1,55 @@
+//! A [`SectionFilter`] that removes the lines executed fewer times than a threshold.
+//!
+//! See [`MinCount`] documentation for more.
+//!
+//! [`SectionFilter`]: ../trait.SectionFilter.html
+//! [`MinCount`]: struct.MinCount.html
+use super::SectionFilter;
+use crate::report::section::{self, line};
+
+/// A [`SectionFilter`] that removes the lines, and the branches on them, executed fewer times
+/// than a threshold.
+///
+/// This filter is useful for isolating hot paths, or ignoring lines only touched by setup code
+/// of a test harness. Branches on lines without line data (`DA` records) are kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{MinCount, SectionFilter};
+///
+/// let mut report = Report::new();
+/// let _ = report
+///     .section("", "src/main.rs")
+///     .add_line(1, 1)
+///     .add_line(2, 100)
+///     .add_branch(1, 0, 0, Some(1));
+///
+/// MinCount::new(10).filter_sections(&mut report.sections);
+/// assert_eq!(report.line_count("src/main.rs", 1), None);
+/// assert_eq!(report.line_count("src/main.rs", 2), Some(100));
+/// assert!(report.branches_at("src/main.rs", 1).is_empty());
+/// ```
+///
+/// [`SectionFilter`]: ../trait.SectionFilter.html
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct MinCount {
+    min: u64,
+}
+
+impl MinCount {
+    /// Creates a filter which keeps the lines executed at least `min` times.
+    pub fn new(min: u64) -> Self {
+        MinCount { min }
+    }
+}
+
+impl SectionFilter for MinCount {
+    fn keeps_line(&self, _key: &section::Key, value: &section::Value, line: u32) -> bool {
+        value
+            .lines
+            .get(&line::Key { line })
+            .map_or(true, |value| value.count >= self.min)
+    }
+}
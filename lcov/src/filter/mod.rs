@@ -1,10 +1,22 @@
 //! Filters for a LCOV report.
+use crate::report::section::{self, Sections};
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::iter::{Extend, IntoIterator};
 use std::mem;
+use std::ops::RangeInclusive;
 
+pub mod function_name;
 pub mod line_num;
+pub mod min_count;
+pub mod source_file;
 
+pub use self::function_name::FunctionName;
 pub use self::line_num::LineNum;
+pub use self::min_count::MinCount;
+pub use self::source_file::SourceFile;
 
 /// Filters elements of the collection in-place.
 ///
@@ -62,3 +74,460 @@ where
         self.extend(iter);
     }
 }
+
+/// Filters elements of the collection in-place, in parallel.
+///
+/// This is the parallel counterpart of [`FilterMap`], useful for expensive filters.
+/// This trait is available only if the `rayon` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::ParFilterMap;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1);
+/// let _ = report.section("", "tests/test.rs").add_line(1, 1);
+/// report.sections.par_filter_map(|(key, value)| {
+///     if key.source_file.starts_with("src") {
+///         Some((key, value))
+///     } else {
+///         None
+///     }
+/// });
+/// assert_eq!(report.sections.len(), 1);
+/// ```
+///
+/// [`FilterMap`]: trait.FilterMap.html
+#[cfg(feature = "rayon")]
+pub trait ParFilterMap {
+    /// The type of the elements.
+    type Item;
+
+    /// Filters elements of `self` in-place, in parallel.
+    fn par_filter_map<F>(&mut self, f: F)
+    where
+        F: Fn(Self::Item) -> Option<Self::Item> + Sync + Send;
+}
+
+#[cfg(feature = "rayon")]
+impl<T, I> ParFilterMap for T
+where
+    T: Default + ParallelExtend<I> + IntoParallelIterator<Item = I>,
+    I: Send,
+{
+    type Item = I;
+
+    fn par_filter_map<F>(&mut self, f: F)
+    where
+        F: Fn(Self::Item) -> Option<Self::Item> + Sync + Send,
+    {
+        let iter = mem::take(self).into_par_iter().filter_map(f);
+        self.par_extend(iter);
+    }
+}
+
+/// A filter which decides which coverage information of a section is kept.
+///
+/// A filter consists of predicates on a section, its functions and its lines. All predicates
+/// keep everything by default, and [`filter`] applies them to a section. Branches are kept or
+/// removed together with the line they are on.
+///
+/// Filters can be applied in sequence by [`FilterChain`].
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{SectionFilter, SourceFile};
+/// use lcov::report::section;
+///
+/// // Keeps only the lines with an even line number.
+/// struct EvenLines;
+///
+/// impl SectionFilter for EvenLines {
+///     fn keeps_line(&self, _key: &section::Key, _value: &section::Value, line: u32) -> bool {
+///         line % 2 == 0
+///     }
+/// }
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 1);
+/// let _ = report.section("", "tests/test.rs").add_line(1, 1).add_line(2, 1);
+///
+/// EvenLines.filter_sections(&mut report.sections);
+/// SourceFile::new(|path| path.starts_with("src")).filter_sections(&mut report.sections);
+///
+/// assert_eq!(report.sections.len(), 1);
+/// assert_eq!(report.line_count("src/main.rs", 1), None);
+/// assert_eq!(report.line_count("src/main.rs", 2), Some(1));
+/// ```
+///
+/// [`filter`]: #method.filter
+/// [`FilterChain`]: struct.FilterChain.html
+pub trait SectionFilter {
+    /// Returns `true` if the section should be kept.
+    fn keeps_section(&self, key: &section::Key, value: &section::Value) -> bool {
+        let _ = (key, value);
+        true
+    }
+
+    /// Returns `true` if the function `name` in the section should be kept.
+    ///
+    /// `lines` is the range of lines which the function is considered to span, as computed by
+    /// [`function::Regions`]. `None` if the start line of the function is unknown.
+    ///
+    /// [`function::Regions`]: ../report/section/function/struct.Regions.html
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        let _ = (key, value, name, lines);
+        true
+    }
+
+    /// Returns `true` if the line in the section, and the branches on it, should be kept.
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        let _ = (key, value, line);
+        true
+    }
+
+    /// Applies the filter to the section.
+    ///
+    /// All predicates are evaluated against the section before filtering.
+    /// Returns `false` if the section should be removed, i.e. it is rejected by
+    /// [`keeps_section`] or has no coverage information after filtering.
+    ///
+    /// [`keeps_section`]: #method.keeps_section
+    fn filter(&self, key: &section::Key, value: &mut section::Value) -> bool {
+        if !self.keeps_section(key, value) {
+            return false;
+        }
+
+        let lines = value.function_regions().iter().collect::<HashMap<_, _>>();
+        let functions = value
+            .functions
+            .keys()
+            .filter(|fkey| {
+                let lines = lines.get(fkey).cloned();
+                !self.keeps_function(key, value, &fkey.name, lines)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let lines = value
+            .lines
+            .keys()
+            .map(|lkey| lkey.line)
+            .chain(value.branches.keys().map(|bkey| bkey.line))
+            .filter(|&line| !self.keeps_line(key, value, line))
+            .collect::<BTreeSet<_>>();
+
+        for fkey in &functions {
+            let _ = value.functions.remove(fkey);
+        }
+        value.branches.retain(|bkey, _| !lines.contains(&bkey.line));
+        value.lines.retain(|lkey, _| !lines.contains(&lkey.line));
+        !value.is_empty()
+    }
+
+    /// Applies the filter to all sections, and removes the sections [`filter`] rejects.
+    ///
+    /// [`filter`]: #method.filter
+    fn filter_sections(&self, sections: &mut Sections) {
+        sections.retain(|key, value| self.filter(key, value));
+    }
+
+    /// Returns a filter which keeps what both `self` and `other` keep.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::filter::{LineNum, SectionFilter, SourceFile};
+    /// use std::collections::HashMap;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 1);
+    /// let _ = report.section("", "src/generated.rs").add_line(1, 1);
+    /// let _ = report.section("", "src/critical/mod.rs").add_line(1, 1).add_line(2, 1);
+    ///
+    /// // Lines changed by a commit.
+    /// let mut diff = HashMap::new();
+    /// let _ = diff.insert(PathBuf::from("src/main.rs"), LineNum::from_iter([1..2]));
+    /// let _ = diff.insert(PathBuf::from("src/generated.rs"), LineNum::from_iter([1..2]));
+    /// let generated = SourceFile::new(|path| path.ends_with("generated.rs"));
+    /// let critical = SourceFile::new(|path| path.starts_with("src/critical"));
+    ///
+    /// // Lines in the diff and not in generated files, or in the critical module.
+    /// let filter = diff.and(generated.not()).or(critical);
+    /// filter.filter_sections(&mut report.sections);
+    ///
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(1));
+    /// assert_eq!(report.line_count("src/main.rs", 2), None);
+    /// assert_eq!(report.line_count("src/generated.rs", 1), None);
+    /// assert_eq!(report.line_count("src/critical/mod.rs", 2), Some(1));
+    /// ```
+    fn and<F>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+        F: SectionFilter,
+    {
+        And(self, other)
+    }
+
+    /// Returns a filter which keeps what either `self` or `other` keeps.
+    ///
+    /// See [`and`] for an example.
+    ///
+    /// [`and`]: #method.and
+    fn or<F>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+        F: SectionFilter,
+    {
+        Or(self, other)
+    }
+
+    /// Returns a filter which keeps what `self` removes.
+    ///
+    /// Sections are never removed by the returned filter itself, but by having no coverage
+    /// information left.
+    ///
+    /// See [`and`] for an example.
+    ///
+    /// [`and`]: #method.and
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<T> SectionFilter for Box<T>
+where
+    T: SectionFilter + ?Sized,
+{
+    fn keeps_section(&self, key: &section::Key, value: &section::Value) -> bool {
+        (**self).keeps_section(key, value)
+    }
+
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        (**self).keeps_function(key, value, name, lines)
+    }
+
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        (**self).keeps_line(key, value, line)
+    }
+
+    fn filter(&self, key: &section::Key, value: &mut section::Value) -> bool {
+        (**self).filter(key, value)
+    }
+}
+
+/// A [`SectionFilter`] which applies several filters in sequence.
+///
+/// The filters are chosen at runtime, e.g. from a configuration file.
+/// Each filter sees the section filtered by the preceding ones.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{FilterChain, FunctionName, SectionFilter, SourceFile};
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_function("main", 1, 1).add_function("helper", 5, 0);
+/// let _ = report.section("", "build/generated.rs").add_line(1, 1);
+///
+/// let mut chain = FilterChain::new();
+/// chain.push(SourceFile::new(|path| !path.starts_with("build")));
+/// chain.push(FunctionName::new(|name| name != "helper"));
+/// chain.filter_sections(&mut report.sections);
+///
+/// assert_eq!(report.sections.len(), 1);
+/// assert_eq!(report.function_count("src/main.rs", "main"), Some(1));
+/// assert_eq!(report.function_count("src/main.rs", "helper"), None);
+/// ```
+///
+/// [`SectionFilter`]: trait.SectionFilter.html
+#[derive(Default)]
+pub struct FilterChain {
+    filters: Vec<Box<dyn SectionFilter>>,
+}
+
+impl fmt::Debug for FilterChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FilterChain {{ {} filter(s) }}", self.filters.len())
+    }
+}
+
+impl FilterChain {
+    /// Creates an empty chain, which keeps everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a filter to the chain.
+    pub fn push<F>(&mut self, filter: F)
+    where
+        F: SectionFilter + 'static,
+    {
+        self.filters.push(Box::new(filter));
+    }
+
+    /// Returns the number of filters in the chain.
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns `true` if the chain has no filter.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+impl FromIterator<Box<dyn SectionFilter>> for FilterChain {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = Box<dyn SectionFilter>>,
+    {
+        FilterChain {
+            filters: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl SectionFilter for FilterChain {
+    fn keeps_section(&self, key: &section::Key, value: &section::Value) -> bool {
+        self.filters.iter().all(|f| f.keeps_section(key, value))
+    }
+
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        self.filters
+            .iter()
+            .all(|f| f.keeps_function(key, value, name, lines.clone()))
+    }
+
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        self.filters.iter().all(|f| f.keeps_line(key, value, line))
+    }
+
+    fn filter(&self, key: &section::Key, value: &mut section::Value) -> bool {
+        self.filters.iter().all(|f| f.filter(key, value))
+    }
+}
+
+// A function or a line is kept by a filter if both the section and the item are kept, so
+// combinators compose the predicates in that sense.
+
+/// A filter which keeps what both filters keep.
+///
+/// This struct is created by [`SectionFilter::and`]. See its documentation for more.
+///
+/// [`SectionFilter::and`]: trait.SectionFilter.html#method.and
+#[derive(Debug, Clone, Copy, Default)]
+pub struct And<A, B>(A, B);
+
+impl<A, B> SectionFilter for And<A, B>
+where
+    A: SectionFilter,
+    B: SectionFilter,
+{
+    fn keeps_section(&self, key: &section::Key, value: &section::Value) -> bool {
+        self.0.keeps_section(key, value) && self.1.keeps_section(key, value)
+    }
+
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        self.0.keeps_function(key, value, name, lines.clone())
+            && self.1.keeps_function(key, value, name, lines)
+    }
+
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        self.0.keeps_line(key, value, line) && self.1.keeps_line(key, value, line)
+    }
+}
+
+/// A filter which keeps what either filter keeps.
+///
+/// This struct is created by [`SectionFilter::or`]. See its documentation for more.
+///
+/// [`SectionFilter::or`]: trait.SectionFilter.html#method.or
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Or<A, B>(A, B);
+
+impl<A, B> SectionFilter for Or<A, B>
+where
+    A: SectionFilter,
+    B: SectionFilter,
+{
+    fn keeps_section(&self, key: &section::Key, value: &section::Value) -> bool {
+        self.0.keeps_section(key, value) || self.1.keeps_section(key, value)
+    }
+
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        (self.0.keeps_section(key, value) && self.0.keeps_function(key, value, name, lines.clone()))
+            || (self.1.keeps_section(key, value) && self.1.keeps_function(key, value, name, lines))
+    }
+
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        (self.0.keeps_section(key, value) && self.0.keeps_line(key, value, line))
+            || (self.1.keeps_section(key, value) && self.1.keeps_line(key, value, line))
+    }
+}
+
+/// A filter which keeps what the filter removes.
+///
+/// This struct is created by [`SectionFilter::not`]. See its documentation for more.
+///
+/// [`SectionFilter::not`]: trait.SectionFilter.html#method.not
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Not<A>(A);
+
+impl<A> SectionFilter for Not<A>
+where
+    A: SectionFilter,
+{
+    fn keeps_function(
+        &self,
+        key: &section::Key,
+        value: &section::Value,
+        name: &str,
+        lines: Option<RangeInclusive<u32>>,
+    ) -> bool {
+        !(self.0.keeps_section(key, value) && self.0.keeps_function(key, value, name, lines))
+    }
+
+    fn keeps_line(&self, key: &section::Key, value: &section::Value, line: u32) -> bool {
+        !(self.0.keeps_section(key, value) && self.0.keeps_line(key, value, line))
+    }
+}
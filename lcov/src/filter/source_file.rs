@@ -0,0 +1,58 @@
+//! A [`SectionFilter`] that keeps only the sections of the matching source files.
+//!
+//! See [`SourceFile`] documentation for more.
+//!
+//! [`SectionFilter`]: ../trait.SectionFilter.html
+//! [`SourceFile`]: struct.SourceFile.html
+use super::SectionFilter;
+use crate::report::section;
+use std::fmt;
+use std::path::Path;
+
+/// A [`SectionFilter`] that keeps only the sections whose source file satisfies the predicate.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::filter::{SectionFilter, SourceFile};
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1);
+/// let _ = report.section("", "/usr/include/stdio.h").add_line(1, 1);
+///
+/// let filter = SourceFile::new(|path| !path.starts_with("/usr"));
+/// filter.filter_sections(&mut report.sections);
+/// assert_eq!(report.sections.len(), 1);
+/// ```
+///
+/// [`SectionFilter`]: ../trait.SectionFilter.html
+#[derive(Clone, Copy)]
+pub struct SourceFile<F> {
+    predicate: F,
+}
+
+impl<F> fmt::Debug for SourceFile<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SourceFile {{ .. }}")
+    }
+}
+
+impl<F> SourceFile<F>
+where
+    F: Fn(&Path) -> bool,
+{
+    /// Creates a filter which keeps the sections whose source file satisfies `predicate`.
+    pub fn new(predicate: F) -> Self {
+        SourceFile { predicate }
+    }
+}
+
+impl<F> SectionFilter for SourceFile<F>
+where
+    F: Fn(&Path) -> bool,
+{
+    fn keeps_section(&self, key: &section::Key, _value: &section::Value) -> bool {
+        (self.predicate)(&key.source_file)
+    }
+}
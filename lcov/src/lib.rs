@@ -12,7 +12,7 @@
 //!
 //! ```toml
 //! [dependencies]
-//! lcov = "0.8.1"
+//! lcov = "0.9.0"
 //! ```
 //!
 //! # Performance
@@ -139,13 +139,21 @@
 #![warn(unused_import_braces)]
 #![warn(unused_qualifications)]
 #![warn(unused_results)]
-#![doc(html_root_url = "https://docs.rs/lcov/0.8.1")]
+#![doc(html_root_url = "https://docs.rs/lcov/0.9.0")]
 
 pub use reader::Reader;
 pub use record::{Record, RecordKind};
 pub use report::Report;
+pub use writer::RecordWriter;
 
+#[cfg(feature = "bytes")]
+pub mod bytes;
+pub mod cancel;
+#[cfg(feature = "checksum")]
+pub mod checksum;
 pub mod filter;
 pub mod reader;
 pub mod record;
 pub mod report;
+pub mod stream;
+pub mod writer;
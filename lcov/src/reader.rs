@@ -8,15 +8,73 @@
 //! [`Reader`]: struct.Reader.html
 //! [`open_file`]: ../fn.open_file.html
 use super::record::{ParseRecordError, Record};
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Lines};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
 /// Reading an LCOV records from a buffered reader.
+///
+/// Lines may be terminated by `\n`, `\r\n` or a lone `\r`, and the terminators can be mixed in
+/// an input. A UTF-8 byte order mark at the start of the input is skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::{Reader, Record};
+///
+/// let input = "\u{feff}TN:\r\nSF:foo.c\rDA:1,1\nend_of_record\r\n";
+/// let records = Reader::new(input.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(records, [
+///     Record::TestName { name: "".into() },
+///     Record::SourceFile { path: "foo.c".into() },
+///     Record::LineData { line: 1, count: 1, checksum: None },
+///     Record::EndOfRecord,
+/// ]);
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
 #[derive(Debug)]
 pub struct Reader<B> {
-    lines: Lines<B>,
+    buf: B,
+    name: Option<String>,
     line: u32,
+    offset: u64,
+    line_start: u64,
+    // Set after a `\r`, so that a `\n` directly following it is read as the same terminator.
+    after_cr: bool,
+    on_error: Option<ErrorHandler>,
+    lossless_paths: bool,
+    passthrough: bool,
+}
+
+/// An action to take on a malformed line, returned by the callback of [`Reader::on_error`].
+///
+/// [`Reader::on_error`]: struct.Reader.html#method.on_error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recovery {
+    /// Skips the line and continues reading.
+    Skip,
+    /// Returns the error as [`Error::ParseRecord`], as the reader does without the callback.
+    ///
+    /// [`Error::ParseRecord`]: enum.Error.html#variant.ParseRecord
+    Abort,
+    /// Returns the record instead of the line.
+    Replace(Record),
+}
+
+type ErrorCallback = dyn FnMut(u32, &str, &ParseRecordError) -> Recovery + Send + Sync;
+
+struct ErrorHandler(Box<ErrorCallback>);
+
+impl fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ErrorHandler(..)")
+    }
 }
 
 impl<B> Reader<B> {
@@ -51,8 +109,257 @@ impl<B> Reader<B> {
         B: BufRead,
     {
         Reader {
-            lines: buf.lines(),
+            buf,
+            name: None,
             line: 0,
+            offset: 0,
+            line_start: 0,
+            after_cr: false,
+            on_error: None,
+            lossless_paths: false,
+            passthrough: false,
+        }
+    }
+
+    /// Sets a label of the input, such as a file name or a URL.
+    ///
+    /// Errors of a labeled reader are returned as [`Error::Labeled`], which has the label and the
+    /// byte offset of the line where the error occurs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Reader;
+    ///
+    /// let input = "TN:\nSF:foo.c\nDA:1\n";
+    /// let mut reader = Reader::new(input.as_bytes()).name("merged.info");
+    /// let err = reader.find_map(Result::err).unwrap();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "merged.info (byte 13): invalid record syntax at line 3: field `count` not found"
+    /// );
+    /// ```
+    ///
+    /// [`Error::Labeled`]: enum.Error.html#variant.Labeled
+    pub fn name<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Returns the label of the input set by [`name`].
+    ///
+    /// [`name`]: #method.name
+    pub fn input_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the number of lines read so far.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns the number of bytes read so far, i.e. the byte offset of the next line.
+    ///
+    /// The `\n` of a `\r\n` terminator is read with the next line, so that reading a line never
+    /// waits for more input than the line itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Reader;
+    ///
+    /// let mut reader = Reader::new("TN:\nSF:foo.c\n".as_bytes());
+    /// assert_eq!(reader.offset(), 0);
+    /// let _ = reader.next();
+    /// assert_eq!(reader.offset(), 4);
+    /// ```
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Specifies whether paths of `SF` records which are not valid UTF-8 are read as they are.
+    ///
+    /// By default, lines which are not valid UTF-8 are errors. If enabled, the paths of `SF`
+    /// records are read from the raw bytes on Unix, and written back by [`Record::write_to`]
+    /// without loss. On other platforms, where paths are not arbitrary bytes, invalid sequences
+    /// are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Record};
+    ///
+    /// let input = b"TN:\nSF:caf\xe9.c\nend_of_record\n";
+    /// assert!(Reader::new(&input[..]).any(|rec| rec.is_err()));
+    ///
+    /// let records = Reader::new(&input[..])
+    ///     .lossless_paths(true)
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// let mut output = vec![];
+    /// for rec in &records {
+    ///     rec.write_to(&mut output)?;
+    ///     output.push(b'\n');
+    /// }
+    /// # #[cfg(unix)]
+    /// assert_eq!(output, input);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`Record::write_to`]: enum.Record.html#method.write_to
+    pub fn lossless_paths(mut self, lossless_paths: bool) -> Self {
+        self.lossless_paths = lossless_paths;
+        self
+    }
+
+    /// Enables or disables the passthrough mode.
+    ///
+    /// In the passthrough mode, lines of unknown record kinds, such as extension records of other
+    /// tools, are returned as [`Record::Unknown`] instead of errors. [`Report`] keeps them in the
+    /// section they appear in, and outputs them again, so they survive a parse, filter and output
+    /// pipeline. Other malformed lines are still errors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Record, Report};
+    ///
+    /// let input = "TN:\nSF:foo.c\nXCOV:1,hot\nDA:1,1\nend_of_record\n";
+    /// let records = Reader::new(input.as_bytes())
+    ///     .passthrough(true)
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(records[2], Record::Unknown { text: "XCOV:1,hot".into() });
+    ///
+    /// let report = Report::from_reader(Reader::new(input.as_bytes()).passthrough(true))?;
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.unknown_records, ["XCOV:1,hot"]);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`Record::Unknown`]: enum.Record.html#variant.Unknown
+    /// [`Report`]: report/struct.Report.html
+    pub fn passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Sets a callback which decides how to treat malformed lines.
+    ///
+    /// The callback receives the line number, the text of the line without its terminator, and
+    /// the parse error, and returns the action to take. Lines which are not valid UTF-8 are
+    /// passed with [`ParseRecordError::InvalidUtf8`], and invalid sequences in the text are
+    /// replaced with `U+FFFD REPLACEMENT CHARACTER`. Errors other than parse errors, such as I/O
+    /// errors, are returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Record};
+    /// use lcov::reader::Recovery;
+    ///
+    /// let input = "\
+    /// SF:foo.c
+    /// DA:1,1
+    /// DA:2,-1
+    /// XX:unknown
+    /// end_of_record
+    /// ";
+    /// let reader = Reader::new(input.as_bytes()).on_error(|line, text, error| {
+    ///     if text.starts_with("DA:") {
+    ///         // Keeps the malformed line as a comment.
+    ///         return Recovery::Replace(Record::Comment { text: text.into() });
+    ///     }
+    ///     eprintln!("skipping line {}: {}", line, error);
+    ///     Recovery::Skip
+    /// });
+    /// let records = reader.collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(records.len(), 4);
+    /// assert_eq!(records[2], Record::Comment { text: "DA:2,-1".into() });
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn on_error<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u32, &str, &ParseRecordError) -> Recovery + Send + Sync + 'static,
+    {
+        self.on_error = Some(ErrorHandler(Box::new(callback)));
+        self
+    }
+
+    // Handles a malformed line by the callback. Returns `None` if the line is skipped.
+    fn recover(&mut self, text: &str, error: ParseRecordError) -> Option<Result<Record, Error>> {
+        let recovery = match &mut self.on_error {
+            Some(ErrorHandler(callback)) => callback(self.line, text, &error),
+            None => Recovery::Abort,
+        };
+        match recovery {
+            Recovery::Skip => None,
+            Recovery::Abort => Some(Err(Error::ParseRecord(self.line, error))),
+            Recovery::Replace(rec) => Some(Ok(rec)),
+        }
+    }
+}
+
+impl<B> Reader<B>
+where
+    B: BufRead,
+{
+    // Reads a line without its terminator. Returns `None` at the end of the input.
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut line = vec![];
+        let mut partial = false;
+        loop {
+            let available = match self.buf.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                return Ok(partial.then_some(line));
+            }
+            if self.after_cr {
+                if available[0] == b'\n' {
+                    self.buf.consume(1);
+                    self.offset += 1;
+                }
+                self.after_cr = false;
+                continue;
+            }
+            if !partial {
+                self.line_start = self.offset;
+            }
+            match available.iter().position(|&b| b == b'\n' || b == b'\r') {
+                Some(i) => {
+                    line.extend_from_slice(&available[..i]);
+                    self.after_cr = available[i] == b'\r';
+                    self.buf.consume(i + 1);
+                    self.offset += i as u64 + 1;
+                    return Ok(Some(line));
+                }
+                None => {
+                    let len = available.len();
+                    line.extend_from_slice(available);
+                    self.buf.consume(len);
+                    self.offset += len as u64;
+                    partial = true;
+                }
+            }
         }
     }
 }
@@ -79,6 +386,20 @@ impl Reader<BufReader<File>> {
     }
 }
 
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes).into()
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
 /// All possible errors that can occur when reading LCOV tracefile.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -104,6 +425,34 @@ pub enum Error {
     /// ```
     #[error("invalid record syntax at line {}: {}", _0, _1)]
     ParseRecord(u32, #[source] ParseRecordError),
+
+    /// An error of a reader labeled by [`Reader::name`].
+    ///
+    /// See [`Reader::name`] for an example.
+    ///
+    /// [`Reader::name`]: struct.Reader.html#method.name
+    #[error("{} (byte {}): {}", name, offset, error)]
+    Labeled {
+        /// The label of the input.
+        name: String,
+        /// The byte offset of the line where the error occurs.
+        offset: u64,
+        /// The error.
+        #[source]
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns the error without the label added by [`Reader::name`].
+    ///
+    /// [`Reader::name`]: struct.Reader.html#method.name
+    pub fn unlabeled(&self) -> &Error {
+        match self {
+            Error::Labeled { error, .. } => error.unlabeled(),
+            _ => self,
+        }
+    }
 }
 
 impl<B> Iterator for Reader<B>
@@ -113,11 +462,60 @@ where
     type Item = Result<Record, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.lines.next().map(|line| {
-            line.map_err(Error::Io).and_then(|line| {
-                self.line += 1;
-                line.parse().map_err(|e| Error::ParseRecord(self.line, e))
+        self.next_record().map(|res| {
+            res.map_err(|error| match &self.name {
+                Some(name) => Error::Labeled {
+                    name: name.clone(),
+                    offset: self.line_start,
+                    error: Box::new(error),
+                },
+                None => error,
             })
         })
     }
 }
+
+impl<B> Reader<B>
+where
+    B: BufRead,
+{
+    fn next_record(&mut self) -> Option<Result<Record, Error>> {
+        loop {
+            let mut line = match self.read_line() {
+                Ok(line) => line?,
+                Err(e) => return Some(Err(Error::Io(e))),
+            };
+            self.line += 1;
+            if self.line == 1 && line.starts_with(UTF8_BOM) {
+                let _ = line.drain(..UTF8_BOM.len());
+            }
+            let line = match String::from_utf8(line) {
+                Ok(line) => line,
+                Err(e) if self.lossless_paths && e.as_bytes().starts_with(b"SF:") => {
+                    let mut path = e.into_bytes();
+                    let _ = path.drain(..3);
+                    return Some(Ok(Record::SourceFile {
+                        path: path_from_bytes(path),
+                    }));
+                }
+                Err(e) => {
+                    let text = String::from_utf8_lossy(e.as_bytes()).into_owned();
+                    match self.recover(&text, ParseRecordError::InvalidUtf8(e.utf8_error())) {
+                        Some(res) => return Some(res),
+                        None => continue,
+                    }
+                }
+            };
+            let error = match line.parse() {
+                Ok(rec) => return Some(Ok(rec)),
+                Err(e) => e,
+            };
+            if self.passthrough && error == ParseRecordError::UnknownRecord {
+                return Some(Ok(Record::Unknown { text: line }));
+            }
+            if let Some(res) = self.recover(&line, error) {
+                return Some(res);
+            }
+        }
+    }
+}
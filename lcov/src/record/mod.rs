@@ -4,7 +4,7 @@
 //!
 //! [`Record`]: enum.Record.html
 pub use self::parse::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 mod display;
 mod parse;
@@ -20,7 +20,7 @@ mod tests;
 ///
 /// [`parse`]: enum.Record.html#method.parse
 /// [`to_string`]: enum.Record.html#method.to_string
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Record {
     /// Represents a `TN` record.
     ///
@@ -47,21 +47,47 @@ pub enum Record {
         /// Absolute path to the source file.
         path: PathBuf,
     },
+    /// Represents a `VER` record.
+    ///
+    /// Some geninfo configurations emit the revision of the source file (e.g. a checksum or
+    /// a VCS revision) after `SF` record.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert_eq!("VER:2a7f3c1".parse(), Ok(Record::Version { version: "2a7f3c1".into() }));
+    /// ```
+    Version {
+        /// Revision of the source file.
+        version: String,
+    },
 
     /// Represents a `FN` record.
     ///
+    /// LCOV 2.x emits the line number of function end as well (`FN:<start>,<end>,<name>`).
+    /// Since function names may contain commas, the end line is read only if the name following
+    /// it does not start with a digit, and is not written otherwise. So `FN:1,3,5` is a function
+    /// named `3,5` without the end line.
+    ///
     /// # Examples
     ///
     /// ```rust
     /// use lcov::Record;
     /// assert_eq!("FN:10,main".parse(),
-    ///            Ok(Record::FunctionName { name: "main".into(), start_line: 10 }));
+    ///            Ok(Record::FunctionName { name: "main".into(), start_line: 10, end_line: None }));
+    /// assert_eq!("FN:10,20,main".parse(),
+    ///            Ok(Record::FunctionName { name: "main".into(), start_line: 10, end_line: Some(20) }));
+    /// assert_eq!("FN:1,3,5".parse(),
+    ///            Ok(Record::FunctionName { name: "3,5".into(), start_line: 1, end_line: None }));
     /// ```
     FunctionName {
         /// Function name.
         name: String,
         /// Line number of function start.
         start_line: u32,
+        /// Line number of function end.
+        end_line: Option<u32>,
     },
     /// Represents a `FNDA` record.
     ///
@@ -78,6 +104,45 @@ pub enum Record {
         /// Execution count.
         count: u64,
     },
+    /// Represents a `FNL` record.
+    ///
+    /// LCOV 2.x groups functions sharing the same location (e.g. template instantiations),
+    /// and emits one `FNL` record for each group and `FNA` records for each function in the group.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert_eq!("FNL:0,10,20".parse(),
+    ///            Ok(Record::FunctionLeader { index: 0, start_line: 10, end_line: Some(20) }));
+    /// assert_eq!("FNL:0,10".parse(),
+    ///            Ok(Record::FunctionLeader { index: 0, start_line: 10, end_line: None }));
+    /// ```
+    FunctionLeader {
+        /// Index of the function group.
+        index: u32,
+        /// Line number of function start.
+        start_line: u32,
+        /// Line number of function end.
+        end_line: Option<u32>,
+    },
+    /// Represents a `FNA` record.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert_eq!("FNA:0,3,foo<int>".parse(),
+    ///            Ok(Record::FunctionAlias { index: 0, count: 3, name: "foo<int>".into() }));
+    /// ```
+    FunctionAlias {
+        /// Index of the function group.
+        index: u32,
+        /// Execution count.
+        count: u64,
+        /// Function name.
+        name: String,
+    },
     /// Represents a `FNF` record.
     ///
     /// # Examples
@@ -106,21 +171,26 @@ pub enum Record {
     /// Represents a `BRDA` record.
     ///
     /// `block` and `branch` are gcc internal IDs for the branch.
+    /// Branches taken only when an exception is thrown have the block number prefixed by `e`.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use lcov::Record;
     /// assert_eq!("BRDA:10,30,40,-".parse(),
-    ///            Ok(Record::BranchData { line: 10, block: 30, branch: 40, taken: None }));
+    ///            Ok(Record::BranchData { line: 10, block: 30, exception: false, branch: 40, taken: None }));
     /// assert_eq!("BRDA:10,30,40,3".parse(),
-    ///            Ok(Record::BranchData { line: 10, block: 30, branch: 40, taken: Some(3) }));
+    ///            Ok(Record::BranchData { line: 10, block: 30, exception: false, branch: 40, taken: Some(3) }));
+    /// assert_eq!("BRDA:10,e0,1,5".parse(),
+    ///            Ok(Record::BranchData { line: 10, block: 0, exception: true, branch: 1, taken: Some(5) }));
     /// ```
     BranchData {
         /// Line number.
         line: u32,
         /// Block number.
         block: u32,
+        /// Whether the branch is an exception branch.
+        exception: bool,
         /// Branch number.
         branch: u32,
         /// A number indicating how often that branch was taken.
@@ -203,19 +273,68 @@ pub enum Record {
     /// assert_eq!("end_of_record".parse(), Ok(Record::EndOfRecord));
     /// ```
     EndOfRecord,
+
+    /// Represents a comment line, which starts with `#`.
+    ///
+    /// Comments are ignored by [`Report`], unless [`ParseOptions::preserve_order`] is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert_eq!("# generated by geninfo".parse(),
+    ///            Ok(Record::Comment { text: " generated by geninfo".into() }));
+    /// ```
+    ///
+    /// [`Report`]: ../report/struct.Report.html
+    /// [`ParseOptions::preserve_order`]: ../report/struct.ParseOptions.html#method.preserve_order
+    Comment {
+        /// Text after `#`.
+        text: String,
+    },
+
+    /// Represents a record of an unknown kind, such as an extension record of other tools.
+    ///
+    /// Unknown records are not created by [`parse`], which returns
+    /// [`ParseRecordError::UnknownRecord`] instead, but by [`Reader`] with
+    /// [`Reader::passthrough`] enabled. [`Report`] keeps them in the section they appear in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// let rec = Record::Unknown { text: "XFN:1,main,vendor-data".into() };
+    /// assert_eq!(rec.to_string(), "XFN:1,main,vendor-data");
+    /// ```
+    ///
+    /// [`parse`]: enum.Record.html#method.parse
+    /// [`ParseRecordError::UnknownRecord`]: enum.ParseRecordError.html#variant.UnknownRecord
+    /// [`Reader`]: ../struct.Reader.html
+    /// [`Reader::passthrough`]: ../struct.Reader.html#method.passthrough
+    /// [`Report`]: ../report/struct.Report.html
+    Unknown {
+        /// The whole line, without its terminator.
+        text: String,
+    },
 }
 
 /// Represents all LCOV record kinds.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum RecordKind {
     /// Represents a `TN` record.
     TestName,
     /// Represents a `SF` record.
     SourceFile,
+    /// Represents a `VER` record.
+    Version,
     /// Represents a `FN` record.
     FunctionName,
     /// Represents a `FNDA` record.
     FunctionData,
+    /// Represents a `FNL` record.
+    FunctionLeader,
+    /// Represents a `FNA` record.
+    FunctionAlias,
     /// Represents a `FNF` record.
     FunctionsFound,
     /// Represents a `FNH` record.
@@ -234,6 +353,10 @@ pub enum RecordKind {
     LinesHit,
     /// Represents a `end_of_record` record.
     EndOfRecord,
+    /// Represents a comment line.
+    Comment,
+    /// Represents a record of an unknown kind.
+    Unknown,
 }
 
 macro_rules! kind_impl {
@@ -257,18 +380,249 @@ impl Record {
     pub fn kind(&self) -> RecordKind {
         kind_impl! {
             *self;
-            TestName, SourceFile,
-            FunctionName, FunctionData, FunctionsFound, FunctionsHit,
+            TestName, SourceFile, Version,
+            FunctionName, FunctionData, FunctionLeader, FunctionAlias,
+            FunctionsFound, FunctionsHit,
             BranchData, BranchesFound, BranchesHit,
             LineData, LinesFound, LinesHit,
-            EndOfRecord
+            EndOfRecord, Comment, Unknown
+        }
+    }
+
+    /// Returns `true` if the record starts a section (`SF`).
+    ///
+    /// `TN` records are not section starts, since a `TN` record applies to all the following
+    /// sections until the next `TN` record.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert!(Record::SourceFile { path: "src/main.rs".into() }.is_section_start());
+    /// assert!(!Record::TestName { name: "test".into() }.is_section_start());
+    /// ```
+    pub fn is_section_start(&self) -> bool {
+        matches!(*self, Record::SourceFile { .. })
+    }
+
+    /// Returns `true` if the record ends a section (`end_of_record`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert!(Record::EndOfRecord.is_section_end());
+    /// assert!(!Record::LinesHit { hit: 1 }.is_section_end());
+    /// ```
+    pub fn is_section_end(&self) -> bool {
+        matches!(*self, Record::EndOfRecord)
+    }
+
+    /// Returns `true` if the record is a summary of a section
+    /// (`FNF`, `FNH`, `BRF`, `BRH`, `LF` or `LH`).
+    ///
+    /// See [`RecordKind::is_per_section_summary`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert!(Record::LinesFound { found: 1 }.is_summary());
+    /// assert!(!Record::LineData { line: 1, count: 1, checksum: None }.is_summary());
+    /// ```
+    ///
+    /// [`RecordKind::is_per_section_summary`]: enum.RecordKind.html#method.is_per_section_summary
+    pub fn is_summary(&self) -> bool {
+        self.kind().is_per_section_summary()
+    }
+
+    /// Returns `true` if the record holds coverage information of a function, a branch or
+    /// a line (`FN`, `FNDA`, `FNL`, `FNA`, `BRDA` or `DA`).
+    ///
+    /// See [`RecordKind::is_detail`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// assert!(Record::LineData { line: 1, count: 1, checksum: None }.is_detail());
+    /// assert!(!Record::LinesFound { found: 1 }.is_detail());
+    /// ```
+    ///
+    /// [`RecordKind::is_detail`]: enum.RecordKind.html#method.is_detail
+    pub fn is_detail(&self) -> bool {
+        self.kind().is_detail()
+    }
+
+    /// Returns the line number which the record refers to.
+    ///
+    /// This is the start line for `FN` and `FNL` records.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// let rec = Record::LineData { line: 3, count: 1, checksum: None };
+    /// assert_eq!(rec.line(), Some(3));
+    /// let rec = Record::FunctionName { name: "main".into(), start_line: 1, end_line: None };
+    /// assert_eq!(rec.line(), Some(1));
+    /// assert_eq!(Record::EndOfRecord.line(), None);
+    /// ```
+    pub fn line(&self) -> Option<u32> {
+        match *self {
+            Record::FunctionName { start_line, .. } | Record::FunctionLeader { start_line, .. } => {
+                Some(start_line)
+            }
+            Record::BranchData { line, .. } | Record::LineData { line, .. } => Some(line),
+            _ => None,
+        }
+    }
+
+    /// Returns the execution count of the record.
+    ///
+    /// This is the number of times the branch was taken for `BRDA` records, and `None` if the
+    /// branch was never evaluated (`-`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// let rec = Record::FunctionData { name: "main".into(), count: 2 };
+    /// assert_eq!(rec.count(), Some(2));
+    /// let rec = Record::BranchData { line: 1, block: 0, exception: false, branch: 0, taken: None };
+    /// assert_eq!(rec.count(), None);
+    /// assert_eq!(Record::LinesHit { hit: 3 }.count(), None);
+    /// ```
+    pub fn count(&self) -> Option<u64> {
+        match *self {
+            Record::FunctionData { count, .. }
+            | Record::FunctionAlias { count, .. }
+            | Record::LineData { count, .. } => Some(count),
+            Record::BranchData { taken, .. } => taken,
+            _ => None,
+        }
+    }
+
+    /// Returns the test name of `TN` records, or the function name of `FN`, `FNDA` and `FNA`
+    /// records.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// let rec = Record::FunctionAlias { index: 0, count: 1, name: "foo<int>".into() };
+    /// assert_eq!(rec.name(), Some("foo<int>"));
+    /// assert_eq!(Record::TestName { name: "test".into() }.name(), Some("test"));
+    /// assert_eq!(Record::EndOfRecord.name(), None);
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        match *self {
+            Record::TestName { ref name }
+            | Record::FunctionName { ref name, .. }
+            | Record::FunctionData { ref name, .. }
+            | Record::FunctionAlias { ref name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the source file of `SF` records.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Record;
+    /// use std::path::Path;
+    /// let rec = Record::SourceFile { path: "src/main.rs".into() };
+    /// assert_eq!(rec.path(), Some(Path::new("src/main.rs")));
+    /// assert_eq!(Record::EndOfRecord.path(), None);
+    /// ```
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            Record::SourceFile { ref path } => Some(path),
+            _ => None,
         }
     }
 }
 
 impl RecordKind {
+    /// Returns all record kinds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::RecordKind;
+    /// assert!(RecordKind::all().contains(&RecordKind::LineData));
+    /// for kind in RecordKind::all() {
+    ///     println!("{}", kind);
+    /// }
+    /// ```
+    pub fn all() -> &'static [RecordKind] {
+        use RecordKind::*;
+
+        &[
+            TestName,
+            SourceFile,
+            Version,
+            FunctionName,
+            FunctionData,
+            FunctionLeader,
+            FunctionAlias,
+            FunctionsFound,
+            FunctionsHit,
+            BranchData,
+            BranchesFound,
+            BranchesHit,
+            LineData,
+            LinesFound,
+            LinesHit,
+            EndOfRecord,
+            Comment,
+            Unknown,
+        ]
+    }
+
+    /// Returns `true` if the record kind is a summary of a section
+    /// (`FNF`, `FNH`, `BRF`, `BRH`, `LF` or `LH`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::RecordKind;
+    /// assert!(RecordKind::LinesHit.is_per_section_summary());
+    /// assert!(!RecordKind::LineData.is_per_section_summary());
+    /// ```
+    pub fn is_per_section_summary(&self) -> bool {
+        use RecordKind::*;
+
+        matches!(
+            *self,
+            FunctionsFound | FunctionsHit | BranchesFound | BranchesHit | LinesFound | LinesHit
+        )
+    }
+
+    /// Returns `true` if the record kind holds coverage information of a function, a branch or
+    /// a line (`FN`, `FNDA`, `FNL`, `FNA`, `BRDA` or `DA`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::RecordKind;
+    /// assert!(RecordKind::BranchData.is_detail());
+    /// assert!(!RecordKind::BranchesFound.is_detail());
+    /// ```
+    pub fn is_detail(&self) -> bool {
+        use RecordKind::*;
+
+        matches!(
+            *self,
+            FunctionName | FunctionData | FunctionLeader | FunctionAlias | BranchData | LineData
+        )
+    }
+
     /// Returns the corresponding `&str` for the record kind.
     ///
+    /// `RecordKind::Unknown` has no fixed representation, so an empty string is returned.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -281,8 +635,11 @@ impl RecordKind {
         match *self {
             TestName => "TN",
             SourceFile => "SF",
+            Version => "VER",
             FunctionName => "FN",
             FunctionData => "FNDA",
+            FunctionLeader => "FNL",
+            FunctionAlias => "FNA",
             FunctionsFound => "FNF",
             FunctionsHit => "FNH",
             BranchData => "BRDA",
@@ -292,6 +649,8 @@ impl RecordKind {
             LinesFound => "LF",
             LinesHit => "LH",
             EndOfRecord => "end_of_record",
+            Comment => "#",
+            Unknown => "",
         }
     }
 }
@@ -1,7 +1,7 @@
 use super::{Record, RecordKind};
 use std::num::ParseIntError;
 use std::path::PathBuf;
-use std::str::FromStr;
+use std::str::{FromStr, Utf8Error};
 
 /// All possible errors that can occur when parsing LCOV record kind.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -15,8 +15,11 @@ impl FromStr for RecordKind {
         let kind = match s {
             "TN" => TestName,
             "SF" => SourceFile,
+            "VER" => Version,
             "FN" => FunctionName,
             "FNDA" => FunctionData,
+            "FNL" => FunctionLeader,
+            "FNA" => FunctionAlias,
             "FNF" => FunctionsFound,
             "FNH" => FunctionsHit,
             "BRDA" => BranchData,
@@ -26,6 +29,7 @@ impl FromStr for RecordKind {
             "LF" => LinesFound,
             "LH" => LinesHit,
             "end_of_record" => EndOfRecord,
+            "#" => Comment,
             _ => return Err(ParseRecordKindError),
         };
 
@@ -86,56 +90,121 @@ pub enum ParseRecordError {
     /// ```
     #[error("unknown record")]
     UnknownRecord,
-}
 
-macro_rules! replace_expr {
-    ($_id:ident $sub:expr) => {
-        $sub
-    };
-}
-macro_rules! count_idents {
-    ($($id:ident)*) => { 0 $(+ replace_expr!($id 1))* }
+    /// An error indicating that the line is not valid UTF-8.
+    ///
+    /// This error is returned by [`Reader`], which passes the line to the callback of
+    /// [`Reader::on_error`] with invalid sequences replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn main() {
+    /// use lcov::Reader;
+    /// use lcov::reader::Error as ReadError;
+    /// use lcov::record::ParseRecordError;
+    /// let mut reader = Reader::new(&b"TN:caf\xe9"[..]);
+    /// assert_matches!(reader.next(), Some(Err(ReadError::ParseRecord(1, ParseRecordError::InvalidUtf8(_)))));
+    /// # }
+    /// ```
+    ///
+    /// [`Reader`]: ../struct.Reader.html
+    /// [`Reader::on_error`]: ../struct.Reader.html#method.on_error
+    #[error("{}", _0)]
+    InvalidUtf8(#[source] Utf8Error),
 }
+
 macro_rules! parse_record {
     ($input:expr => $rec:ident { $($field:ident,)* .. $last: ident}) => {{
-        let mut sp = $input.splitn(count_idents!($($field)*) + 1, ',');
+        let mut fields = Fields::new($input);
         let rec = $rec {
-            $($field: ParseField::parse_iter_next(&mut sp, stringify!($field))?,)*
-            $last: ParseField::parse_iter_next(&mut sp, stringify!($last))?
+            $($field: fields.next_field(stringify!($field))?,)*
+            $last: fields.rest_field(stringify!($last))?
         };
-        debug_assert!(sp.next().is_none());
         Ok(rec)
     }};
     ($input:expr => $rec:ident { $($field:ident,)* .. ?$last: ident}) => {{
-        let mut sp = $input.splitn(count_idents!($($field)*) + 1, ',');
+        let mut fields = Fields::new($input);
         let rec = $rec {
-            $($field: ParseField::parse_iter_next(&mut sp, stringify!($field))?,)*
-            $last: if let Some(s) = sp.next() {
-                ParseField::parse_field(s, stringify!($last))?
-            } else {
-                None
+            $($field: fields.next_field(stringify!($field))?,)*
+            $last: match fields.rest() {
+                Some(s) => ParseField::parse_field(s, stringify!($last))?,
+                None => None,
             }
         };
-        debug_assert!(sp.next().is_none());
         Ok(rec)
     }};
     ($input:expr => $rec:ident { $($field:ident),* $(,?$opt_field:ident),* }) => {{
-        let mut sp = $input.split(',');
+        let mut fields = Fields::new($input);
         let rec = $rec {
-            $($field: ParseField::parse_iter_next(&mut sp, stringify!($field))?,)*
-            $($opt_field: if let Some(s) = sp.next() {
-                Some(ParseField::parse_field(s, stringify!($opt_field))?)
-            } else {
-                None
+            $($field: fields.next_field(stringify!($field))?,)*
+            $($opt_field: match fields.next() {
+                Some(s) => Some(ParseField::parse_field(s, stringify!($opt_field))?),
+                None => None,
             },)*
         };
-        if sp.next().is_some() {
-            return Err(ParseRecordError::TooManyFields)
-        }
+        fields.finish()?;
         Ok(rec)
     }};
 }
 
+// Comma-separated fields of a record body.
+//
+// Splits the body by scanning bytes directly, which is much faster than `str::split` on the
+// short fields of LCOV records.
+struct Fields<'a> {
+    rest: Option<&'a str>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(body: &'a str) -> Self {
+        Fields { rest: Some(body) }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.rest?;
+        match rest.bytes().position(|b| b == b',') {
+            Some(i) => {
+                self.rest = Some(&rest[i + 1..]);
+                Some(&rest[..i])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+
+    // Returns all the remaining fields, for the last field which may contain commas.
+    fn rest(&mut self) -> Option<&'a str> {
+        self.rest.take()
+    }
+
+    fn next_field<T>(&mut self, name: &'static str) -> Result<T, ParseRecordError>
+    where
+        T: ParseField,
+    {
+        let s = self.next().ok_or(ParseRecordError::FieldNotFound(name))?;
+        T::parse_field(s, name)
+    }
+
+    fn rest_field<T>(&mut self, name: &'static str) -> Result<T, ParseRecordError>
+    where
+        T: ParseField,
+    {
+        let s = self.rest().ok_or(ParseRecordError::FieldNotFound(name))?;
+        T::parse_field(s, name)
+    }
+
+    fn finish(self) -> Result<(), ParseRecordError> {
+        match self.rest {
+            Some(_) => Err(ParseRecordError::TooManyFields),
+            None => Ok(()),
+        }
+    }
+}
+
 impl FromStr for Record {
     type Err = ParseRecordError;
 
@@ -144,43 +213,104 @@ impl FromStr for Record {
         use RecordKind as Kind;
 
         s = s.trim_end_matches::<&[_]>(&['\n', '\r']);
-        let mut sp = s.splitn(2, ':');
-
-        let kind = sp
-            .next()
-            .unwrap()
+        if let Some(text) = s.strip_prefix('#') {
+            return Ok(Comment { text: text.into() });
+        }
+        let (kind, body) = match s.bytes().position(|b| b == b':') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let kind = kind
             .parse::<RecordKind>()
             .map_err(|_e| ParseRecordError::UnknownRecord)?;
-        let body = sp.next().unwrap_or("");
-        debug_assert!(sp.next().is_none());
 
         match kind {
             Kind::TestName => parse_record!(body => TestName { .. name }),
             Kind::SourceFile => parse_record!(body => SourceFile { .. path }),
-            Kind::FunctionName => parse_record!(body => FunctionName { start_line, .. name }),
+            Kind::Version => parse_record!(body => Version { .. version }),
+            Kind::FunctionName => parse_function_name(body),
             Kind::FunctionData => parse_record!(body => FunctionData { count, .. name }),
+            Kind::FunctionLeader => {
+                parse_record!(body => FunctionLeader { index, start_line, ?end_line })
+            }
+            Kind::FunctionAlias => parse_record!(body => FunctionAlias { index, count, .. name }),
             Kind::FunctionsFound => parse_record!(body => FunctionsFound { found }),
             Kind::FunctionsHit => parse_record!(body => FunctionsHit { hit }),
-            Kind::BranchData => parse_record!(body => BranchData { line, block, branch, taken}),
+            Kind::BranchData => parse_branch_data(body),
             Kind::BranchesFound => parse_record!(body => BranchesFound { found }),
             Kind::BranchesHit => parse_record!(body => BranchesHit { hit }),
             Kind::LineData => parse_record!(body => LineData { line, count, .. ?checksum }),
             Kind::LinesFound => parse_record!(body => LinesFound { found }),
             Kind::LinesHit => parse_record!(body => LinesHit { hit }),
             Kind::EndOfRecord => Ok(EndOfRecord),
+            Kind::Comment => unreachable!("comments are parsed above"),
+            Kind::Unknown => unreachable!("unknown kinds are not parsed"),
         }
     }
 }
 
+// `FN:<start>,<name>` or `FN:<start>,<end>,<name>` (LCOV 2.x).
+//
+// Function names may contain commas, so the second field is treated as the end line only if it
+// is a number and followed by a name not starting with a digit. Function names of real languages
+// never start with a digit, and `FN:<start>,<digits>,...` keeps being read as a name as before
+// the end line is supported.
+fn parse_function_name(body: &str) -> Result<Record, ParseRecordError> {
+    let mut fields = Fields::new(body);
+    let start_line = fields.next_field("start_line")?;
+    let rest = fields
+        .rest()
+        .ok_or(ParseRecordError::FieldNotFound("name"))?;
+    let mut fields = Fields::new(rest);
+    let (end_line, name) = match (fields.next(), fields.rest()) {
+        (Some(end_line), Some(name)) if !is_ambiguous_function_name(name) => {
+            match ParseField::parse_field(end_line, "end_line") {
+                Ok(end_line) => (Some(end_line), name),
+                Err(_) => (None, rest),
+            }
+        }
+        _ => (None, rest),
+    };
+    Ok(Record::FunctionName {
+        name: name.into(),
+        start_line,
+        end_line,
+    })
+}
+
+// Returns `true` if the end line of a `FN` record can't be written before `name`, since the
+// record would be read as a function of another name without the end line.
+pub(super) fn is_ambiguous_function_name(name: &str) -> bool {
+    name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit())
+}
+
+// `BRDA:<line>,<block>,<branch>,<taken>`, where `<block>` is prefixed by `e` for exception
+// branches.
+fn parse_branch_data(body: &str) -> Result<Record, ParseRecordError> {
+    let mut fields = Fields::new(body);
+    let line = fields.next_field("line")?;
+    let block = fields
+        .next()
+        .ok_or(ParseRecordError::FieldNotFound("block"))?;
+    let (exception, block) = match block.strip_prefix('e') {
+        Some(block) => (true, block),
+        None => (false, block),
+    };
+    let block = ParseField::parse_field(block, "block")?;
+    let branch = fields.next_field("branch")?;
+    let taken = fields.next_field("taken")?;
+    fields.finish()?;
+    Ok(Record::BranchData {
+        line,
+        block,
+        exception,
+        branch,
+        taken,
+    })
+}
+
 trait ParseField: Sized {
     fn parse_field(s: &str, name: &'static str) -> Result<Self, ParseRecordError>;
-    fn parse_iter_next<'a, I>(it: &mut I, name: &'static str) -> Result<Self, ParseRecordError>
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let s = it.next().ok_or(ParseRecordError::FieldNotFound(name))?;
-        Self::parse_field(s, name)
-    }
 }
 
 impl ParseField for String {
@@ -195,19 +325,56 @@ impl ParseField for PathBuf {
     }
 }
 
-impl ParseField for u32 {
-    fn parse_field(s: &str, name: &'static str) -> Result<Self, ParseRecordError> {
-        s.parse()
-            .map_err(|e| ParseRecordError::ParseIntError(name, e))
+trait Int: FromStr<Err = ParseIntError> + From<u8> {
+    // Number of digits which never overflow.
+    const SAFE_DIGITS: usize;
+    fn mul_add(self, mul: u8, add: u8) -> Self;
+}
+
+impl Int for u32 {
+    const SAFE_DIGITS: usize = 9;
+    fn mul_add(self, mul: u8, add: u8) -> Self {
+        self * u32::from(mul) + u32::from(add)
     }
 }
 
-impl ParseField for u64 {
+impl Int for u64 {
+    const SAFE_DIGITS: usize = 19;
+    fn mul_add(self, mul: u8, add: u8) -> Self {
+        self * u64::from(mul) + u64::from(add)
+    }
+}
+
+// Parses a decimal integer without overflow checks, or returns `None` if `s` is not a short
+// sequence of digits.
+fn parse_int<T>(s: &str) -> Option<T>
+where
+    T: Int,
+{
+    if s.is_empty() || s.len() > T::SAFE_DIGITS {
+        return None;
+    }
+    s.bytes().try_fold(T::from(0), |n, b| {
+        let digit = b.wrapping_sub(b'0');
+        (digit <= 9).then(|| n.mul_add(10, digit))
+    })
+}
+
+impl<T> ParseField for T
+where
+    T: Int,
+{
     fn parse_field(s: &str, name: &'static str) -> Result<Self, ParseRecordError> {
-        s.parse()
-            .map_err(|e| ParseRecordError::ParseIntError(name, e))
+        match parse_int(s) {
+            Some(n) => Ok(n),
+            // Falls back to `str::parse` for long numbers, signs and errors.
+            None => s
+                .parse()
+                .map_err(|e| ParseRecordError::ParseIntError(name, e)),
+        }
     }
 }
+
 impl<T> ParseField for Option<T>
 where
     T: ParseField,
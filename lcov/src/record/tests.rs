@@ -1,11 +1,14 @@
-use super::Record;
 use super::Record::*;
+use super::{Record, RecordKind};
 
 fn check_parse_ok(s: &str, rec: &Record) {
     assert_eq!(s.parse::<Record>().unwrap(), *rec);
     assert_eq!(format!("{}\n", s).parse::<Record>().unwrap(), *rec);
     assert_eq!(format!("{}\r\n", s).parse::<Record>().unwrap(), *rec);
     assert_eq!(rec.to_string(), s);
+    let mut output = vec![];
+    rec.write_to(&mut output).unwrap();
+    assert_eq!(output, s.as_bytes());
 }
 
 #[test]
@@ -28,19 +31,46 @@ fn source_file() {
     check_ok(r"C:\foo\bar\baz");
 }
 
+#[test]
+fn version() {
+    fn check_ok(s: &str) {
+        check_parse_ok(&format!("VER:{}", s), &Version { version: s.into() })
+    }
+    check_ok("1");
+    check_ok("2a7f3c1");
+    check_ok("foo,bar");
+}
+
 #[test]
 fn function_name() {
-    fn check_ok(name: &str, line: u32) {
+    fn check_ok(name: &str, start_line: u32, end_line: Option<u32>) {
+        let s = if let Some(end_line) = end_line {
+            format!("FN:{},{},{}", start_line, end_line, name)
+        } else {
+            format!("FN:{},{}", start_line, name)
+        };
         check_parse_ok(
-            &format!("FN:{},{}", line, name),
+            &s,
             &FunctionName {
                 name: name.into(),
-                start_line: line,
+                start_line,
+                end_line,
             },
         )
     }
-    check_ok("hogehoge", 3);
-    check_ok("3,5", 1);
+    check_ok("hogehoge", 3, None);
+    check_ok("3,5", 1, None);
+    check_ok("hoge,hoge", 1, None);
+    check_ok("hogehoge", 3, Some(10));
+    check_ok("hoge,hoge", 1, Some(2));
+
+    // End lines are not written before names which would be read as a part of them.
+    let rec = FunctionName {
+        name: "5".into(),
+        start_line: 1,
+        end_line: Some(3),
+    };
+    assert_eq!(rec.to_string(), "FN:1,5");
 }
 
 #[test]
@@ -58,6 +88,44 @@ fn function_data() {
     check_ok("hoge,hoge", 98765);
 }
 
+#[test]
+fn function_leader() {
+    fn check_ok(index: u32, start_line: u32, end_line: Option<u32>) {
+        let s = if let Some(end_line) = end_line {
+            format!("FNL:{},{},{}", index, start_line, end_line)
+        } else {
+            format!("FNL:{},{}", index, start_line)
+        };
+        check_parse_ok(
+            &s,
+            &FunctionLeader {
+                index,
+                start_line,
+                end_line,
+            },
+        );
+    }
+    check_ok(0, 3, None);
+    check_ok(1, 3, Some(10));
+    check_ok(u32::MAX, u32::MAX, Some(u32::MAX));
+}
+
+#[test]
+fn function_alias() {
+    fn check_ok(index: u32, count: u64, name: &str) {
+        check_parse_ok(
+            &format!("FNA:{},{},{}", index, count, name),
+            &FunctionAlias {
+                index,
+                count,
+                name: name.into(),
+            },
+        );
+    }
+    check_ok(0, 12345, "hogehoge");
+    check_ok(1, 98765, "hoge<int, int>");
+}
+
 #[test]
 fn functions_found_hit() {
     fn check_ok(n: u32) {
@@ -71,24 +139,28 @@ fn functions_found_hit() {
 
 #[test]
 fn branch_data() {
-    fn check_ok(line: u32, block: u32, branch: u32, taken: Option<u64>) {
+    fn check_ok(line: u32, block: u32, exception: bool, branch: u32, taken: Option<u64>) {
+        let prefix = if exception { "e" } else { "" };
         let s = if let Some(taken) = taken {
-            format!("BRDA:{},{},{},{}", line, block, branch, taken)
+            format!("BRDA:{},{}{},{},{}", line, prefix, block, branch, taken)
         } else {
-            format!("BRDA:{},{},{},-", line, block, branch)
+            format!("BRDA:{},{}{},{},-", line, prefix, block, branch)
         };
         check_parse_ok(
             &s,
             &BranchData {
                 line,
                 block,
+                exception,
                 branch,
                 taken,
             },
         );
     }
-    check_ok(10, 20, 30, Some(40));
-    check_ok(100, 200, 300, None);
+    check_ok(10, 20, false, 30, Some(40));
+    check_ok(100, 200, false, 300, None);
+    check_ok(10, 0, true, 1, Some(5));
+    check_ok(10, 0, true, 1, None);
 }
 
 #[test]
@@ -139,3 +211,98 @@ fn lines_found_hit() {
 fn end_of_record() {
     check_parse_ok("end_of_record", &EndOfRecord);
 }
+
+#[test]
+fn comment() {
+    fn check_ok(s: &str) {
+        check_parse_ok(&format!("#{}", s), &Comment { text: s.into() })
+    }
+    check_ok("");
+    check_ok(" foo");
+    check_ok("DA:1,2");
+}
+
+#[test]
+fn record_kind() {
+    let kinds = RecordKind::all();
+    for (i, kind) in kinds.iter().enumerate() {
+        assert!(!kinds[..i].contains(kind));
+        if *kind != RecordKind::Unknown {
+            assert_eq!(kind.as_str().parse(), Ok(*kind));
+        }
+        assert!(!(kind.is_detail() && kind.is_per_section_summary()));
+    }
+    assert_eq!(
+        kinds.iter().filter(|k| k.is_per_section_summary()).count(),
+        6
+    );
+    assert_eq!(kinds.iter().filter(|k| k.is_detail()).count(), 6);
+}
+
+#[test]
+fn hash() {
+    use std::collections::HashSet;
+
+    let records = [
+        "DA:1,2",
+        "DA:1,2",
+        "DA:1,2,abc",
+        "BRDA:1,e0,1,-",
+        "end_of_record",
+    ]
+    .iter()
+    .map(|s| s.parse::<Record>().unwrap())
+    .collect::<HashSet<_>>();
+    assert_eq!(records.len(), 4);
+    let kinds = records.iter().map(Record::kind).collect::<HashSet<_>>();
+    assert_eq!(kinds.len(), 3);
+}
+
+#[test]
+fn integer_fields() {
+    use super::ParseRecordError::*;
+    use std::num::IntErrorKind;
+
+    fn int_error(s: &str) -> Option<(&'static str, IntErrorKind)> {
+        match s.parse::<Record>() {
+            Err(ParseIntError(name, e)) => Some((name, *e.kind())),
+            _ => None,
+        }
+    }
+
+    assert_eq!(
+        "DA:0001,+5".parse(),
+        Ok(LineData {
+            line: 1,
+            count: 5,
+            checksum: None
+        })
+    );
+    assert_eq!(
+        "DA:4294967295,18446744073709551615".parse::<Record>(),
+        Ok(LineData {
+            line: u32::MAX,
+            count: u64::MAX,
+            checksum: None
+        })
+    );
+    assert_eq!(
+        int_error("DA:4294967296,1"),
+        Some(("line", IntErrorKind::PosOverflow))
+    );
+    assert_eq!(
+        int_error("DA:1,18446744073709551616"),
+        Some(("count", IntErrorKind::PosOverflow))
+    );
+    assert_eq!(int_error("DA:,1"), Some(("line", IntErrorKind::Empty)));
+    assert_eq!(
+        int_error("DA:1,-1"),
+        Some(("count", IntErrorKind::InvalidDigit))
+    );
+    assert_eq!("LF:1,2".parse::<Record>(), Err(TooManyFields));
+    assert_eq!("DA:1".parse::<Record>(), Err(FieldNotFound("count")));
+    assert_eq!(
+        int_error("BRDA:1,e,0,1"),
+        Some(("block", IntErrorKind::Empty))
+    );
+}
@@ -0,0 +1,167 @@
+use super::merge::MergeEntry;
+use super::section::{self, branch, function, line};
+use super::{Report, Resolution};
+use std::collections::btree_map::{BTreeMap, Entry};
+
+/// A builder which adds coverage information to a section of a [`Report`].
+///
+/// This `struct` is created by the [`section`] method on [`Report`].
+/// See its documentation for more.
+///
+/// [`Report`]: struct.Report.html
+/// [`section`]: struct.Report.html#method.section
+#[derive(Debug)]
+pub struct SectionBuilder<'a> {
+    value: &'a mut section::Value,
+}
+
+impl<'a> SectionBuilder<'a> {
+    pub(crate) fn new(report: &'a mut Report, key: section::Key) -> Self {
+        SectionBuilder {
+            value: report.sections.entry(key).or_default(),
+        }
+    }
+
+    /// Adds an execution count of a line.
+    ///
+    /// If the line already exists, the count is added to the existing one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(10, 3).add_line(10, 2);
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.lines.values().next().unwrap().count, 5);
+    /// ```
+    pub fn add_line(self, line: u32, count: u64) -> Self {
+        let value = line::Value {
+            count,
+            checksum: None,
+        };
+        merge_entry(&mut self.value.lines, line::Key { line }, value);
+        self
+    }
+
+    /// Adds a function with its start line and execution count.
+    ///
+    /// If the function already exists, the start line is overwritten and the count is added to
+    /// the existing one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_function("main", 5, 1);
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.functions.values().next().unwrap().start_line, Some(5));
+    /// ```
+    pub fn add_function<S>(self, name: S, start_line: u32, count: u64) -> Self
+    where
+        S: Into<String>,
+    {
+        let key = function::Key { name: name.into() };
+        let value = function::Value {
+            start_line: Some(start_line),
+            end_line: None,
+            count,
+        };
+        merge_entry(&mut self.value.functions, key, value);
+        self
+    }
+
+    /// Adds a branch with the number indicating how often that branch was taken.
+    ///
+    /// `None` means that the block containing the branch was never executed.
+    /// If the branch already exists, the number is added to the existing one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_branch(10, 0, 0, Some(1))
+    ///     .add_branch(10, 0, 1, None);
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.branches.len(), 2);
+    /// ```
+    pub fn add_branch(self, line: u32, block: u32, branch: u32, taken: Option<u64>) -> Self {
+        self.insert_branch(line, block, false, branch, taken)
+    }
+
+    /// Adds an exception branch, i.e. a branch taken when an exception is thrown.
+    ///
+    /// Exception branches are written with the block number prefixed by `e`, as in
+    /// `BRDA:10,e0,1,5`. Otherwise, this is the same as [`add_branch`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_branch(10, 0, 0, Some(1))
+    ///     .add_exception_branch(10, 0, 0, Some(2));
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.branches.len(), 2);
+    /// assert!(section.branches.keys().any(|key| key.exception));
+    /// ```
+    ///
+    /// [`add_branch`]: #method.add_branch
+    pub fn add_exception_branch(
+        self,
+        line: u32,
+        block: u32,
+        branch: u32,
+        taken: Option<u64>,
+    ) -> Self {
+        self.insert_branch(line, block, true, branch, taken)
+    }
+
+    fn insert_branch(
+        self,
+        line: u32,
+        block: u32,
+        exception: bool,
+        branch: u32,
+        taken: Option<u64>,
+    ) -> Self {
+        let key = branch::Key {
+            line,
+            block,
+            exception,
+            branch,
+        };
+        merge_entry(&mut self.value.branches, key, branch::Value { taken });
+        self
+    }
+}
+
+// Merges a single entry in place, keeping the new value on conflicts as `merge_lossy` does.
+fn merge_entry<K, V>(map: &mut BTreeMap<K, V>, key: K, value: V)
+where
+    K: Ord + Clone,
+    V: MergeEntry<K>,
+{
+    match map.entry(key) {
+        Entry::Vacant(entry) => {
+            let _ = entry.insert(value);
+        }
+        Entry::Occupied(mut entry) => {
+            let key = entry.key().clone();
+            let result = entry
+                .get_mut()
+                .merge_entry(&key, value, &mut |_| Resolution::KeepRight);
+            debug_assert!(result.is_ok());
+        }
+    }
+}
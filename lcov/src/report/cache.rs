@@ -0,0 +1,141 @@
+use super::section::Sections;
+use super::Report;
+use bincode::Options;
+use std::io::{self, Read, Write};
+
+// Magic number and format version at the head of a cache.
+// The version must be incremented whenever the serialized structures change.
+const MAGIC: &[u8; 8] = b"LCOVCACH";
+const VERSION: u32 = 2;
+
+/// All possible errors that can occur when reading or writing a cache of [`Report`].
+///
+/// [`Report`]: struct.Report.html
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    /// An error indicating that I/O operation failed.
+    #[error("{}", _0)]
+    Io(#[from] io::Error),
+
+    /// An error indicating that the input is not a cache of [`Report`].
+    ///
+    /// [`Report`]: struct.Report.html
+    #[error("not a cache of LCOV report")]
+    InvalidHeader,
+
+    /// An error indicating that the cache is written in an incompatible version of the format.
+    #[error("unsupported cache format version: {}", _0)]
+    UnsupportedVersion(u32),
+
+    /// An error indicating that the content of the cache cannot be encoded or decoded.
+    #[error("broken cache: {}", _0)]
+    Codec(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<bincode::Error> for CacheError {
+    fn from(e: bincode::Error) -> Self {
+        match *e {
+            bincode::ErrorKind::Io(e) => CacheError::Io(e),
+            e => CacheError::Codec(Box::new(e)),
+        }
+    }
+}
+
+// The encoding of `bincode::serialize_into`, which the format is defined with.
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+}
+
+impl Report {
+    /// Writes the report in the compact binary cache format.
+    ///
+    /// The cache can be loaded much faster than parsing LCOV tracefile by [`from_cache`].
+    /// The record order preserved by [`ParseOptions::preserve_order`] is not written.
+    ///
+    /// This method is available only if the `cache` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 2);
+    ///
+    /// let mut cache = vec![];
+    /// report.to_cache(&mut cache)?;
+    /// assert_eq!(Report::from_cache(&cache[..])?, report);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`from_cache`]: #method.from_cache
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn to_cache<W>(&self, mut writer: W) -> Result<(), CacheError>
+    where
+        W: Write,
+    {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        options().serialize_into(&mut writer, &self.sections)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a report written by [`to_cache`].
+    ///
+    /// This method is available only if the `cache` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn main() {
+    /// use lcov::Report;
+    /// use lcov::report::CacheError;
+    ///
+    /// assert_matches!(Report::from_cache("TN:\n".as_bytes()), Err(CacheError::InvalidHeader));
+    /// # }
+    /// ```
+    ///
+    /// [`to_cache`]: #method.to_cache
+    pub fn from_cache<R>(mut reader: R) -> Result<Self, CacheError>
+    where
+        R: Read,
+    {
+        let mut magic = [0; 8];
+        let mut version = [0; 4];
+        match reader.read_exact(&mut magic) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(CacheError::InvalidHeader)
+            }
+            Err(e) => return Err(e.into()),
+        }
+        if &magic != MAGIC {
+            return Err(CacheError::InvalidHeader);
+        }
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(CacheError::UnsupportedVersion(version));
+        }
+        // Reads the whole content first, so that a broken length prefix is rejected by the length
+        // of the content instead of allocating a huge buffer.
+        let mut content = vec![];
+        let _ = reader.read_to_end(&mut content)?;
+        let sections: Sections = options()
+            .with_limit(content.len() as u64)
+            .deserialize(&content)?;
+        Ok(Report {
+            sections,
+            layout: None,
+        })
+    }
+}
@@ -1,5 +1,7 @@
-use super::RecordKind;
+use super::section;
+use super::{ConflictKind, MergeConflict, RecordKind};
 use crate::reader;
+use std::fmt;
 
 /// All possible errors that can occur when parsing LCOV records.
 #[derive(Debug, thiserror::Error)]
@@ -70,10 +72,41 @@ pub enum ParseError {
     /// ```
     #[error("unexpected end of file")]
     UnexpectedEof,
+
+    /// An error indicating that a summary record does not match the detail records.
+    ///
+    /// This error occurs only in the strict mode. See [`ParseOptions::strict`] for more.
+    ///
+    /// [`ParseOptions::strict`]: struct.ParseOptions.html#method.strict
+    #[error(
+        "`{}:{}` record of `{}` does not match the detail records (expected {})",
+        kind,
+        found,
+        key.source_file.display(),
+        expected
+    )]
+    SummaryMismatch {
+        /// The section which contains the summary record.
+        key: section::Key,
+        /// The kind of the summary record.
+        kind: RecordKind,
+        /// The value of the summary record.
+        found: u32,
+        /// The value computed from the detail records.
+        expected: u32,
+    },
+
+    /// An error indicating that parsing is cancelled.
+    ///
+    /// This error occurs only if a token is given by [`ParseOptions::cancellation`].
+    ///
+    /// [`ParseOptions::cancellation`]: struct.ParseOptions.html#method.cancellation
+    #[error("parsing cancelled")]
+    Cancelled,
 }
 
 /// All possible errors that can occur when merging LCOV records.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, thiserror::Error)]
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
 pub enum MergeError {
     /// An error indicating that start line of functions are not same.
     ///
@@ -101,15 +134,30 @@ pub enum MergeError {
     /// let mut report1 = Report::from_reader(Reader::new(input1.as_bytes()))?;
     /// let report2 = Report::from_reader(Reader::new(input2.as_bytes()))?;
     /// assert_matches!(report1.merge(report2),
-    ///                 Err(MergeError::UnmatchedFunctionLine));
+    ///                 Err(MergeError::UnmatchedFunctionLine { start_line: 3, other_start_line: 4, .. }));
     /// # Ok(())
     /// # }
     /// # fn main() {
     /// # try_main().expect("failed to run test");
     /// # }
     /// ```
-    #[error("unmatched start line of function")]
-    UnmatchedFunctionLine,
+    #[error(
+        "{}: unmatched start line of function `{}`: {} and {}",
+        DisplayKey(key),
+        name,
+        start_line,
+        other_start_line
+    )]
+    UnmatchedFunctionLine {
+        /// The section in which the conflict is found.
+        key: section::Key,
+        /// Function name.
+        name: String,
+        /// Start line of the function in the report merged into.
+        start_line: u32,
+        /// Start line of the function in the report being merged.
+        other_start_line: u32,
+    },
 
     /// An error indicating that checksum of lines are not same.
     ///
@@ -137,13 +185,135 @@ pub enum MergeError {
     /// let mut report1 = Report::from_reader(Reader::new(input1.as_bytes()))?;
     /// let report2 = Report::from_reader(Reader::new(input2.as_bytes()))?;
     /// assert_matches!(report1.merge(report2),
-    ///                 Err(MergeError::UnmatchedChecksum));
+    ///                 Err(MergeError::UnmatchedChecksum { line: 4, .. }));
     /// # Ok(())
     /// # }
     /// # fn main() {
     /// # try_main().expect("failed to run test");
     /// # }
     /// ```
-    #[error("unmatched checksum")]
-    UnmatchedChecksum,
+    #[error(
+        "{}: unmatched checksum of line {}: `{}` and `{}`",
+        DisplayKey(key),
+        line,
+        checksum,
+        other_checksum
+    )]
+    UnmatchedChecksum {
+        /// The section in which the conflict is found.
+        key: section::Key,
+        /// Line number.
+        line: u32,
+        /// Checksum of the line in the report merged into.
+        checksum: String,
+        /// Checksum of the line in the report being merged.
+        other_checksum: String,
+    },
+
+    /// An error indicating that revisions of source files (`VER` records) are not same.
+    ///
+    /// This error occurs when merging LCOV tracefiles generated from different revisions of
+    /// the source file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Report};
+    /// use lcov::report::MergeError;
+    /// let input1 = "\
+    /// TN:test_name
+    /// SF:foo.c
+    /// VER:1
+    /// DA:4,1
+    /// end_of_record
+    /// ";
+    /// let input2 = "\
+    /// TN:test_name
+    /// SF:foo.c
+    /// VER:2
+    /// DA:4,4
+    /// end_of_record
+    /// ";
+    /// let mut report1 = Report::from_reader(Reader::new(input1.as_bytes()))?;
+    /// let report2 = Report::from_reader(Reader::new(input2.as_bytes()))?;
+    /// assert_matches!(report1.merge(report2),
+    ///                 Err(MergeError::UnmatchedVersion { .. }));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    #[error(
+        "{}: unmatched version of source file: `{}` and `{}`",
+        DisplayKey(key),
+        version,
+        other_version
+    )]
+    UnmatchedVersion {
+        /// The section in which the conflict is found.
+        key: section::Key,
+        /// Revision of the source file in the report merged into.
+        version: String,
+        /// Revision of the source file in the report being merged.
+        other_version: String,
+    },
+
+    /// An error indicating that merging is cancelled.
+    ///
+    /// This error occurs only if a token is given by [`Merger::cancellation`].
+    ///
+    /// [`Merger::cancellation`]: struct.Merger.html#method.cancellation
+    #[error("merging cancelled")]
+    Cancelled,
+}
+
+impl From<MergeConflict> for MergeError {
+    fn from(conflict: MergeConflict) -> Self {
+        let MergeConflict { key, kind } = conflict;
+        match kind {
+            ConflictKind::FunctionLine {
+                name,
+                start_line,
+                other_start_line,
+            } => MergeError::UnmatchedFunctionLine {
+                key,
+                name,
+                start_line,
+                other_start_line,
+            },
+            ConflictKind::Checksum {
+                line,
+                checksum,
+                other_checksum,
+            } => MergeError::UnmatchedChecksum {
+                key,
+                line,
+                checksum,
+                other_checksum,
+            },
+            ConflictKind::Version {
+                version,
+                other_version,
+            } => MergeError::UnmatchedVersion {
+                key,
+                version,
+                other_version,
+            },
+        }
+    }
+}
+
+pub(crate) struct DisplayKey<'a>(pub(crate) &'a section::Key);
+
+impl fmt::Display for DisplayKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.source_file.display())?;
+        if !self.0.test_name.is_empty() {
+            write!(f, " (test `{}`)", self.0.test_name)?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,156 @@
+use super::section::Sections;
+use std::path::Path;
+
+// 64-bit FNV-1a, which is simple enough to keep the digest stable across versions and platforms,
+// unlike `std::collections::hash_map::DefaultHasher`.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.bytes(&n.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u64(s.len() as u64);
+        self.bytes(s.as_bytes());
+    }
+
+    // Non-UTF-8 paths are hashed losslessly where they are preserved, so that distinct paths
+    // have distinct fingerprints. UTF-8 paths are hashed the same as strings.
+    #[cfg(unix)]
+    fn path(&mut self, path: &Path) {
+        use std::os::unix::ffi::OsStrExt;
+        let bytes = path.as_os_str().as_bytes();
+        self.u64(bytes.len() as u64);
+        self.bytes(bytes);
+    }
+
+    #[cfg(not(unix))]
+    fn path(&mut self, path: &Path) {
+        self.str(&path.to_string_lossy());
+    }
+
+    fn opt(&mut self, n: Option<u64>) {
+        match n {
+            Some(n) => {
+                self.bytes(&[1]);
+                self.u64(n);
+            }
+            None => self.bytes(&[0]),
+        }
+    }
+}
+
+// Hashes a string, e.g. a test name to anonymize.
+pub(crate) fn hash_str(s: &str) -> u64 {
+    let mut hasher = Fnv(FNV_OFFSET_BASIS);
+    hasher.str(s);
+    hasher.0
+}
+
+// Hashes the content compared by `Report::semantic_eq`. Every field is written with its length
+// or its presence, so different contents are never written as the same byte sequence.
+pub(crate) fn fingerprint(sections: &Sections) -> u64 {
+    let mut hasher = Fnv(FNV_OFFSET_BASIS);
+    hasher.u64(sections.len() as u64);
+    for (key, value) in sections {
+        hasher.str(&key.test_name);
+        hasher.path(&key.source_file);
+
+        hasher.u64(value.functions.len() as u64);
+        for (key, value) in &value.functions {
+            hasher.str(&key.name);
+            hasher.opt(value.start_line.map(u64::from));
+            hasher.opt(value.end_line.map(u64::from));
+            hasher.u64(value.count);
+        }
+
+        hasher.u64(value.branches.len() as u64);
+        for (key, value) in &value.branches {
+            hasher.u64(key.line.into());
+            hasher.u64(key.block.into());
+            hasher.bytes(&[key.exception.into()]);
+            hasher.u64(key.branch.into());
+            hasher.opt(value.taken);
+        }
+
+        hasher.u64(value.lines.len() as u64);
+        for (key, value) in &value.lines {
+            hasher.u64(key.line.into());
+            hasher.u64(value.count);
+        }
+    }
+    hasher.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Report;
+
+    #[test]
+    fn stable() {
+        let mut report = Report::new();
+        assert_eq!(report.fingerprint(), 0xa8c7_f832_281a_39c5);
+
+        let _ = report
+            .section("test", "src/main.rs")
+            .add_function("main", 1, 1)
+            .add_branch(2, 0, 0, Some(1))
+            .add_branch(2, 0, 1, None)
+            .add_line(1, 1)
+            .add_line(2, 0);
+        assert_eq!(report.fingerprint(), 0x9055_3d2b_bee8_fa0e);
+    }
+
+    #[test]
+    fn semantic_content() {
+        let report1 = "TN:\nSF:a.c\nVER:1\nDA:1,1,x\nend_of_record\n"
+            .parse::<Report>()
+            .unwrap();
+        let report2 = "TN:\nSF:a.c\nDA:1,1\nLF:1\nLH:1\nend_of_record\n"
+            .parse::<Report>()
+            .unwrap();
+        assert_eq!(report1.fingerprint(), report2.fingerprint());
+
+        let report3 = "TN:\nSF:a.c\nDA:1,2\nend_of_record\n"
+            .parse::<Report>()
+            .unwrap();
+        assert_ne!(report1.fingerprint(), report3.fingerprint());
+
+        // Fields are not confused with each other.
+        let report4 = "TN:a\nSF:bc\nDA:1,1\nend_of_record\n"
+            .parse::<Report>()
+            .unwrap();
+        let report5 = "TN:ab\nSF:c\nDA:1,1\nend_of_record\n"
+            .parse::<Report>()
+            .unwrap();
+        assert_ne!(report4.fingerprint(), report5.fingerprint());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut report1 = Report::new();
+        let _ = report1
+            .section("", OsStr::from_bytes(b"src/\xff.c"))
+            .add_line(1, 1);
+        let mut report2 = Report::new();
+        let _ = report2
+            .section("", OsStr::from_bytes(b"src/\xfe.c"))
+            .add_line(1, 1);
+        assert_ne!(report1.fingerprint(), report2.fingerprint());
+    }
+}
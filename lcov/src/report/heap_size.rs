@@ -0,0 +1,86 @@
+use super::section::Sections;
+use std::collections::{BTreeMap, HashMap};
+use std::mem::size_of;
+use std::path::PathBuf;
+
+// Approximate heap sizes used by `Report::estimated_heap_size`. Allocator overhead is not
+// counted, and the sizes of maps are computed from their lengths or capacities, since the
+// standard library does not expose the sizes of their nodes or tables.
+
+pub(crate) fn string(s: &String) -> usize {
+    s.capacity()
+}
+
+pub(crate) fn path(p: &PathBuf) -> usize {
+    p.capacity()
+}
+
+pub(crate) fn vec<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+// B-tree nodes hold up to 11 entries and are at least half full except the root, so each entry
+// is counted as one and a half slots.
+pub(crate) fn btree_map<K, V>(map: &BTreeMap<K, V>) -> usize {
+    map.len() * (size_of::<K>() + size_of::<V>()) * 3 / 2
+}
+
+// Hash tables have one control byte for each bucket in addition to the entry.
+pub(crate) fn hash_map<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<K>() + size_of::<V>() + 1)
+}
+
+pub(crate) fn sections(sections: &Sections) -> usize {
+    let mut size = btree_map(sections);
+    for (key, value) in sections {
+        size += string(&key.test_name) + path(&key.source_file);
+        size += value.version.as_ref().map_or(0, string);
+
+        size += btree_map(&value.functions);
+        size += value
+            .functions
+            .keys()
+            .map(|key| string(&key.name))
+            .sum::<usize>();
+
+        // Branch keys and values have no heap storage.
+        size += btree_map(&value.branches);
+
+        size += btree_map(&value.lines);
+        size += value
+            .lines
+            .values()
+            .map(|value| value.checksum.as_ref().map_or(0, string))
+            .sum::<usize>();
+
+        size += vec(&value.unknown_records);
+        size += value.unknown_records.iter().map(string).sum::<usize>();
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::section::function;
+    use crate::Report;
+
+    #[test]
+    fn grows_with_contents() {
+        let empty = sections(&Sections::new());
+        assert_eq!(empty, 0);
+
+        let mut report = Report::new();
+        let _ = report.section("", "src/main.rs").add_line(1, 1);
+        let small = sections(&report.sections);
+        assert!(small > empty);
+
+        // Strings are counted by their capacities, as paths are.
+        let mut name = String::with_capacity(64);
+        name.push_str("main");
+        let _ = report.section("", "src/main.rs").add_function(name, 1, 1);
+        let larger = sections(&report.sections);
+        let entry = (size_of::<function::Key>() + size_of::<function::Value>()) * 3 / 2;
+        assert_eq!(larger, small + entry + 64);
+    }
+}
@@ -0,0 +1,502 @@
+use super::heap_size;
+use super::section::{self, branch, function, line, Sections};
+use super::{Record, RecordKind, SummaryRecords};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::mem;
+
+// The original order of sections and records, recorded by `ParseOptions::preserve_order`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Layout {
+    sections: Vec<SectionLayout>,
+    index: HashMap<section::Key, usize>,
+    current: Vec<Slot>,
+    trailing: Vec<Record>,
+}
+
+#[derive(Debug, Clone)]
+struct SectionLayout {
+    key: section::Key,
+    slots: Vec<Slot>,
+    // Sections without coverage information are not stored in `Sections`, so they are output
+    // from the layout as-is.
+    empty: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Slot {
+    TestName(String),
+    SourceFile,
+    Version,
+    FunctionName(function::Key),
+    FunctionData(function::Key),
+    FunctionLeader {
+        index: u32,
+        start_line: u32,
+        end_line: Option<u32>,
+        // The first function in the group, which the line numbers are taken from.
+        alias: Option<function::Key>,
+    },
+    FunctionAlias(u32, function::Key),
+    BranchData(branch::Key),
+    LineData(line::Key),
+    Summary(RecordKind),
+    EndOfRecord,
+    Comment(String),
+    Unknown(String),
+}
+
+impl Slot {
+    fn new(rec: &Record) -> Self {
+        match *rec {
+            Record::TestName { ref name } => Slot::TestName(name.clone()),
+            Record::SourceFile { .. } => Slot::SourceFile,
+            Record::Version { .. } => Slot::Version,
+            Record::FunctionName { ref name, .. } => {
+                Slot::FunctionName(function::Key { name: name.clone() })
+            }
+            Record::FunctionData { ref name, .. } => {
+                Slot::FunctionData(function::Key { name: name.clone() })
+            }
+            Record::FunctionLeader {
+                index,
+                start_line,
+                end_line,
+            } => Slot::FunctionLeader {
+                index,
+                start_line,
+                end_line,
+                alias: None,
+            },
+            Record::FunctionAlias {
+                index, ref name, ..
+            } => Slot::FunctionAlias(index, function::Key { name: name.clone() }),
+            Record::BranchData {
+                line,
+                block,
+                exception,
+                branch,
+                ..
+            } => Slot::BranchData(branch::Key {
+                line,
+                block,
+                exception,
+                branch,
+            }),
+            Record::LineData { line, .. } => Slot::LineData(line::Key { line }),
+            Record::EndOfRecord => Slot::EndOfRecord,
+            Record::Comment { ref text } => Slot::Comment(text.clone()),
+            Record::Unknown { ref text } => Slot::Unknown(text.clone()),
+            ref rec => Slot::Summary(rec.kind()),
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        match *self {
+            Slot::TestName(ref s) | Slot::Comment(ref s) | Slot::Unknown(ref s) => {
+                heap_size::string(s)
+            }
+            Slot::FunctionName(ref key)
+            | Slot::FunctionData(ref key)
+            | Slot::FunctionAlias(_, ref key)
+            | Slot::FunctionLeader {
+                alias: Some(ref key),
+                ..
+            } => heap_size::string(&key.name),
+            _ => 0,
+        }
+    }
+}
+
+impl Layout {
+    // The records between sections are counted without their strings, which are rare.
+    pub(crate) fn heap_size(&self) -> usize {
+        let mut size = heap_size::vec(&self.sections)
+            + heap_size::hash_map(&self.index)
+            + heap_size::vec(&self.current)
+            + heap_size::vec(&self.trailing);
+        for section in &self.sections {
+            size += heap_size::string(&section.key.test_name)
+                + heap_size::path(&section.key.source_file)
+                + heap_size::vec(&section.slots);
+            size += section.slots.iter().map(Slot::heap_size).sum::<usize>();
+        }
+        for key in self.index.keys() {
+            size += heap_size::string(&key.test_name) + heap_size::path(&key.source_file);
+        }
+        size
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        for section in &mut self.sections {
+            section.slots.shrink_to_fit();
+        }
+        self.sections.shrink_to_fit();
+        self.index.shrink_to_fit();
+        self.current.shrink_to_fit();
+        self.trailing.shrink_to_fit();
+    }
+
+    pub(crate) fn push(&mut self, rec: &Record) {
+        let slot = Slot::new(rec);
+        if let Slot::FunctionAlias(index, ref key) = slot {
+            for leader in &mut self.current {
+                if let Slot::FunctionLeader {
+                    index: leader_index,
+                    alias: ref mut alias @ None,
+                    ..
+                } = *leader
+                {
+                    if leader_index == index {
+                        *alias = Some(key.clone());
+                    }
+                }
+            }
+        }
+        self.current.push(slot);
+    }
+
+    pub(crate) fn push_trailing(&mut self, rec: Record) {
+        self.trailing.push(rec);
+    }
+
+    pub(crate) fn remove_empty_sections(&mut self) {
+        self.sections.retain(|section| !section.empty);
+        self.rebuild_index();
+    }
+
+    // Sections which collapse onto the same key are output at the position of the first one, and
+    // the records only in the others are output at the end of the section.
+    pub(crate) fn map_keys<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key) -> section::Key,
+    {
+        let mut kept = HashSet::new();
+        self.sections.retain_mut(|section| {
+            section.key = f(&section.key);
+            section.empty || kept.insert(section.key.clone())
+        });
+        self.rebuild_index();
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index = self
+            .sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| !section.empty)
+            .map(|(i, section)| (section.key.clone(), i))
+            .collect();
+    }
+
+    pub(crate) fn finish_section(&mut self, key: &section::Key, kept: bool) {
+        let slots = mem::take(&mut self.current);
+        if !kept {
+            self.sections.push(SectionLayout {
+                key: key.clone(),
+                slots,
+                empty: true,
+            });
+            return;
+        }
+        // A section which appears more than once overwrites the previous one.
+        if let Some(&i) = self.index.get(key) {
+            self.sections[i].slots = slots;
+        } else {
+            let _ = self.index.insert(key.clone(), self.sections.len());
+            self.sections.push(SectionLayout {
+                key: key.clone(),
+                slots,
+                empty: false,
+            });
+        }
+    }
+}
+
+pub(crate) fn into_records(
+    layout: Layout,
+    mut sections: Sections,
+    summary: SummaryRecords,
+) -> Box<dyn Iterator<Item = Record>> {
+    let mut records = vec![];
+
+    for SectionLayout { key, slots, empty } in layout.sections {
+        if empty {
+            section_into_records(&mut records, key, section::Value::default(), slots, summary);
+        } else if let Some(value) = sections.remove(&key) {
+            section_into_records(&mut records, key, value, slots, summary);
+        }
+    }
+
+    let rest = section::into_records(sections, summary);
+    Box::new(records.into_iter().chain(rest).chain(layout.trailing))
+}
+
+fn section_into_records(
+    records: &mut Vec<Record>,
+    key: section::Key,
+    value: section::Value,
+    slots: Vec<Slot>,
+    summary: SummaryRecords,
+) {
+    let last_test_name = slots
+        .iter()
+        .rposition(|slot| matches!(slot, Slot::TestName(_)));
+    let mut emitted = Emitted::default();
+    let groups = function_groups(&slots, &value);
+    // A version set after parsing is emitted just after `SF` record.
+    let has_version = slots.iter().any(|slot| matches!(slot, Slot::Version));
+
+    for (i, slot) in slots.into_iter().enumerate() {
+        match slot {
+            Slot::TestName(name) => {
+                let name = if Some(i) == last_test_name {
+                    key.test_name.clone()
+                } else {
+                    name
+                };
+                records.push(Record::TestName { name });
+            }
+            Slot::SourceFile => {
+                records.push(Record::SourceFile {
+                    path: key.source_file.clone(),
+                });
+                if let (false, Some(version)) = (has_version, &value.version) {
+                    records.push(Record::Version {
+                        version: version.clone(),
+                    });
+                }
+            }
+            Slot::Version => {
+                if let Some(version) = &value.version {
+                    records.push(Record::Version {
+                        version: version.clone(),
+                    });
+                }
+            }
+            Slot::FunctionName(fkey) => {
+                if let Some(fvalue) = value.functions.get(&fkey) {
+                    if let Some(start_line) = fvalue.start_line {
+                        if emitted.function_names.insert(fkey.clone()) {
+                            records.push(Record::FunctionName {
+                                name: fkey.name,
+                                start_line,
+                                end_line: fvalue.end_line,
+                            });
+                        }
+                    }
+                }
+            }
+            Slot::FunctionData(fkey) => {
+                if let Some(fvalue) = value.functions.get(&fkey) {
+                    if emitted.function_data.insert(fkey.clone()) {
+                        records.push(Record::FunctionData {
+                            name: fkey.name,
+                            count: fvalue.count,
+                        });
+                    }
+                }
+            }
+            Slot::FunctionLeader {
+                index,
+                start_line,
+                end_line,
+                alias,
+            } => {
+                let (start_line, end_line) = match alias.and_then(|a| value.functions.get(&a)) {
+                    Some(&function::Value {
+                        start_line: Some(start_line),
+                        end_line,
+                        ..
+                    }) => (start_line, end_line),
+                    _ => (start_line, end_line),
+                };
+                records.push(Record::FunctionLeader {
+                    index,
+                    start_line,
+                    end_line,
+                });
+            }
+            Slot::FunctionAlias(index, fkey) => {
+                if let Some(fvalue) = value.functions.get(&fkey) {
+                    if emitted.function_data.insert(fkey.clone()) {
+                        let _ = emitted.function_names.insert(fkey.clone());
+                        records.push(Record::FunctionAlias {
+                            index,
+                            count: fvalue.count,
+                            name: fkey.name,
+                        });
+                    }
+                }
+            }
+            Slot::BranchData(bkey) => {
+                if let Some(bvalue) = value.branches.get(&bkey) {
+                    if emitted.branches.insert(bkey) {
+                        records.push(Record::BranchData {
+                            line: bkey.line,
+                            block: bkey.block,
+                            exception: bkey.exception,
+                            branch: bkey.branch,
+                            taken: bvalue.taken,
+                        });
+                    }
+                }
+            }
+            Slot::LineData(lkey) => {
+                if let Some(lvalue) = value.lines.get(&lkey) {
+                    if emitted.lines.insert(lkey) {
+                        records.push(Record::LineData {
+                            line: lkey.line,
+                            count: lvalue.count,
+                            checksum: lvalue.checksum.clone(),
+                        });
+                    }
+                }
+            }
+            Slot::Summary(kind) => {
+                if summary != SummaryRecords::Never && emitted.summaries.insert(kind) {
+                    let n = match (kind, groups) {
+                        (RecordKind::FunctionsFound, Some((found, _))) => found,
+                        (RecordKind::FunctionsHit, Some((_, hit))) => hit,
+                        _ => value.summary_of(kind),
+                    };
+                    records.push(summary_record(kind, n));
+                }
+            }
+            Slot::EndOfRecord => {
+                // Records added after parsing are emitted at the end of the section.
+                emitted.push_rest(records, &value);
+                if summary == SummaryRecords::Always {
+                    emitted.push_missing_summaries(records, &value);
+                }
+                records.push(Record::EndOfRecord);
+            }
+            Slot::Comment(text) => records.push(Record::Comment { text }),
+            Slot::Unknown(text) => {
+                let index = value
+                    .unknown_records
+                    .iter()
+                    .enumerate()
+                    .position(|(i, t)| *t == text && !emitted.unknown_records.contains(&i));
+                if let Some(i) = index {
+                    let _ = emitted.unknown_records.insert(i);
+                    records.push(Record::Unknown { text });
+                }
+            }
+        }
+    }
+}
+
+// In LCOV 2.x, `FNF` and `FNH` count the function groups (`FNL` records), not the functions.
+// Returns the numbers of found and hit groups if the section has function groups.
+fn function_groups(slots: &[Slot], value: &section::Value) -> Option<(u32, u32)> {
+    let mut groups = BTreeMap::new();
+    for slot in slots {
+        match *slot {
+            Slot::FunctionLeader { index, .. } => {
+                let _ = groups.entry(index).or_insert(false);
+            }
+            Slot::FunctionAlias(index, ref key) => {
+                let hit = value.functions.get(key).is_some_and(|v| v.count > 0);
+                *groups.entry(index).or_insert(false) |= hit;
+            }
+            _ => {}
+        }
+    }
+    if groups.is_empty() {
+        return None;
+    }
+    let found = groups.len() as u32;
+    let hit = groups.values().filter(|&&hit| hit).count() as u32;
+    Some((found, hit))
+}
+
+#[derive(Debug, Default)]
+struct Emitted {
+    function_names: BTreeSet<function::Key>,
+    function_data: BTreeSet<function::Key>,
+    branches: BTreeSet<branch::Key>,
+    lines: BTreeSet<line::Key>,
+    summaries: BTreeSet<RecordKind>,
+    // Indices of `section::Value::unknown_records`.
+    unknown_records: BTreeSet<usize>,
+}
+
+impl Emitted {
+    fn push_rest(&self, records: &mut Vec<Record>, value: &section::Value) {
+        let function_names = value
+            .functions
+            .iter()
+            .filter(|(key, _)| !self.function_names.contains(key))
+            .filter_map(|(key, value)| {
+                value.start_line.map(|start_line| Record::FunctionName {
+                    name: key.name.clone(),
+                    start_line,
+                    end_line: value.end_line,
+                })
+            });
+        let function_data = value
+            .functions
+            .iter()
+            .filter(|(key, _)| !self.function_data.contains(key))
+            .map(|(key, value)| Record::FunctionData {
+                name: key.name.clone(),
+                count: value.count,
+            });
+        let branches = value
+            .branches
+            .iter()
+            .filter(|(key, _)| !self.branches.contains(key))
+            .map(|(key, value)| Record::BranchData {
+                line: key.line,
+                block: key.block,
+                exception: key.exception,
+                branch: key.branch,
+                taken: value.taken,
+            });
+        let lines = value
+            .lines
+            .iter()
+            .filter(|(key, _)| !self.lines.contains(key))
+            .map(|(key, value)| Record::LineData {
+                line: key.line,
+                count: value.count,
+                checksum: value.checksum.clone(),
+            });
+        let unknown_records = value
+            .unknown_records
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.unknown_records.contains(i))
+            .map(|(_, text)| Record::Unknown { text: text.clone() });
+        records.extend(
+            function_names
+                .chain(function_data)
+                .chain(branches)
+                .chain(lines)
+                .chain(unknown_records),
+        );
+    }
+
+    fn push_missing_summaries(&self, records: &mut Vec<Record>, value: &section::Value) {
+        let kinds = RecordKind::all()
+            .iter()
+            .filter(|kind| kind.is_per_section_summary());
+        for &kind in kinds {
+            if !self.summaries.contains(&kind) {
+                records.push(summary_record(kind, value.summary_of(kind)));
+            }
+        }
+    }
+}
+
+fn summary_record(kind: RecordKind, n: u32) -> Record {
+    match kind {
+        RecordKind::FunctionsFound => Record::FunctionsFound { found: n },
+        RecordKind::FunctionsHit => Record::FunctionsHit { hit: n },
+        RecordKind::BranchesFound => Record::BranchesFound { found: n },
+        RecordKind::BranchesHit => Record::BranchesHit { hit: n },
+        RecordKind::LinesFound => Record::LinesFound { found: n },
+        RecordKind::LinesHit => Record::LinesHit { hit: n },
+        _ => unreachable!("{} is not a summary record", kind),
+    }
+}
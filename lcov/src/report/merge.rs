@@ -0,0 +1,491 @@
+use super::error::DisplayKey;
+use super::section::{self, Sections};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::path::PathBuf;
+
+/// A conflict found while merging two sections of the same test and source file.
+///
+/// This struct is passed to the resolver of [`Report::merge_resolve`].
+///
+/// [`Report::merge_resolve`]: struct.Report.html#method.merge_resolve
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MergeConflict {
+    /// The section in which the conflict is found.
+    pub key: section::Key,
+    /// The conflicting values.
+    pub kind: ConflictKind,
+}
+
+/// All kinds of conflicts found while merging reports.
+///
+/// "Left" values are the ones in the report merged into, and "right" (`other_*`) values are the
+/// ones in the report being merged.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConflictKind {
+    /// A function has different start lines.
+    FunctionLine {
+        /// Function name.
+        name: String,
+        /// Start line of the function in the left report.
+        start_line: u32,
+        /// Start line of the function in the right report.
+        other_start_line: u32,
+    },
+    /// A line has different checksums.
+    Checksum {
+        /// Line number.
+        line: u32,
+        /// Checksum of the line in the left report.
+        checksum: String,
+        /// Checksum of the line in the right report.
+        other_checksum: String,
+    },
+    /// The source file has different revisions (`VER` records).
+    Version {
+        /// Revision of the source file in the left report.
+        version: String,
+        /// Revision of the source file in the right report.
+        other_version: String,
+    },
+}
+
+impl ConflictKind {
+    /// Resolves a conflict of function start lines by keeping the smaller one, if they differ by
+    /// at most `tolerance` lines.
+    ///
+    /// Start lines of functions in headers often differ by a line or so between compilers.
+    /// Returns [`Resolution::Error`] for larger differences and other kinds of conflicts, so it
+    /// can be used as a resolver of [`Report::merge_resolve`] as it is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let mut report = "TN:\nSF:foo.h\nFN:11,foo\nFN:20,bar\nend_of_record\n".parse::<Report>()?;
+    /// let other = "TN:\nSF:foo.h\nFN:10,foo\nend_of_record\n".parse()?;
+    /// report.merge_resolve(other, |conflict| conflict.kind.resolve_function_line(1))?;
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// let lines = section.functions.values().map(|v| v.start_line).collect::<Vec<_>>();
+    /// assert_eq!(lines, [Some(20), Some(10)]);
+    ///
+    /// let other = "TN:\nSF:foo.h\nFN:25,bar\nend_of_record\n".parse()?;
+    /// assert!(report.merge_resolve(other, |conflict| conflict.kind.resolve_function_line(1)).is_err());
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`Resolution::Error`]: enum.Resolution.html#variant.Error
+    /// [`Report::merge_resolve`]: struct.Report.html#method.merge_resolve
+    pub fn resolve_function_line(&self, tolerance: u32) -> Resolution {
+        match *self {
+            ConflictKind::FunctionLine {
+                start_line,
+                other_start_line,
+                ..
+            } if start_line.abs_diff(other_start_line) <= tolerance => {
+                if start_line <= other_start_line {
+                    Resolution::KeepLeft
+                } else {
+                    Resolution::KeepRight
+                }
+            }
+            _ => Resolution::Error,
+        }
+    }
+}
+
+/// A decision on a [`MergeConflict`].
+///
+/// Whichever value is kept, the execution counts are summed up as usual.
+///
+/// [`MergeConflict`]: struct.MergeConflict.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Resolution {
+    /// Keeps the value in the report merged into.
+    KeepLeft,
+    /// Keeps the value in the report being merged.
+    KeepRight,
+    /// Aborts the merge with a [`MergeError`].
+    ///
+    /// [`MergeError`]: enum.MergeError.html
+    Error,
+}
+
+/// How execution counts of the same item are combined when merging reports.
+///
+/// The strategy applies to the counts of lines, functions and branches. Conflicting values,
+/// such as function start lines, are resolved in the same way regardless of the strategy.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::report::{MergeStrategy, Resolution};
+/// use lcov::Report;
+///
+/// let mut report = "TN:\nSF:foo.c\nDA:1,5\nDA:2,0\nend_of_record\n".parse::<Report>()?;
+/// let other = "TN:\nSF:foo.c\nDA:1,3\nDA:2,2\nend_of_record\n".parse()?;
+/// report.merge_with_strategy(other, MergeStrategy::Max, |_| Resolution::Error)?;
+/// assert_eq!(report.line_count("foo.c", 1), Some(5));
+/// assert_eq!(report.line_count("foo.c", 2), Some(2));
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum MergeStrategy {
+    /// Sums up the counts, as `lcov --add-tracefile` does.
+    #[default]
+    Sum,
+    /// Takes the maximum of the counts.
+    ///
+    /// The merged count tells how many times the item is executed by a single run at most,
+    /// which is useful to select representative runs, e.g. to minimize a fuzzing corpus.
+    /// A branch taken in either report is taken as many times as the larger count.
+    Max,
+}
+
+// Execution counts which can be reduced so that summing them up yields the maximum.
+pub(crate) trait Excess {
+    // Reduces the counts of `self` by the ones of `left`, saturating at zero.
+    fn excess_over(&mut self, left: &Self);
+}
+
+// Reduces the counts of the entries in `other` by the ones of the same keys in `left`.
+pub(crate) fn excess_over<'a, K, V, F>(other: &mut BTreeMap<K, V>, mut left: F)
+where
+    V: Excess + 'a,
+    F: FnMut(&K) -> Option<&'a V>,
+{
+    for (key, value) in other {
+        if let Some(left) = left(key) {
+            value.excess_over(left);
+        }
+    }
+}
+
+/// A summary of the conflicting values overridden by [`Report::merge_lossy_diagnostics`].
+///
+/// [`Report::merge_lossy_diagnostics`]: struct.Report.html#method.merge_lossy_diagnostics
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LossyMergeDiagnostics {
+    /// Number of function start lines overridden.
+    pub function_lines: usize,
+    /// Number of line checksums overridden.
+    pub checksums: usize,
+    /// Number of source file revisions overridden.
+    pub versions: usize,
+    /// Source files in which any value is overridden.
+    pub files: BTreeSet<PathBuf>,
+}
+
+impl LossyMergeDiagnostics {
+    /// Returns `true` if no value is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Returns the number of all values overridden.
+    pub fn total(&self) -> usize {
+        self.function_lines + self.checksums + self.versions
+    }
+
+    pub(crate) fn add(&mut self, conflict: &MergeConflict) {
+        match conflict.kind {
+            ConflictKind::FunctionLine { .. } => self.function_lines += 1,
+            ConflictKind::Checksum { .. } => self.checksums += 1,
+            ConflictKind::Version { .. } => self.versions += 1,
+        }
+        if !self.files.contains(&conflict.key.source_file) {
+            let _ = self.files.insert(conflict.key.source_file.clone());
+        }
+    }
+}
+
+impl fmt::Display for LossyMergeDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "overrode {} function start line(s), {} checksum(s) and {} revision(s) in {} file(s)",
+            self.function_lines,
+            self.checksums,
+            self.versions,
+            self.files.len()
+        )
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", DisplayKey(&self.key), self.kind)
+    }
+}
+
+impl std::error::Error for ConflictKind {}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConflictKind::FunctionLine {
+                ref name,
+                start_line,
+                other_start_line,
+            } => write!(
+                f,
+                "function `{}` starts at line {} and line {}",
+                name, start_line, other_start_line
+            ),
+            ConflictKind::Checksum {
+                line,
+                ref checksum,
+                ref other_checksum,
+            } => write!(
+                f,
+                "line {} has checksums `{}` and `{}`",
+                line, checksum, other_checksum
+            ),
+            ConflictKind::Version {
+                ref version,
+                ref other_version,
+            } => write!(
+                f,
+                "source file has revisions `{}` and `{}`",
+                version, other_version
+            ),
+        }
+    }
+}
+
+/// A resolver of conflicts passed to [`Merge::merge_with`].
+///
+/// [`Merge::merge_with`]: trait.Merge.html#tymethod.merge_with
+pub type Resolver<'a> = dyn FnMut(&ConflictKind) -> Resolution + 'a;
+
+// Returns `true` if the right value should be kept.
+pub(crate) fn keeps_right(
+    resolver: &mut Resolver<'_>,
+    kind: ConflictKind,
+) -> Result<bool, ConflictKind> {
+    match resolver(&kind) {
+        Resolution::KeepLeft => Ok(false),
+        Resolution::KeepRight => Ok(true),
+        Resolution::Error => Err(kind),
+    }
+}
+
+/// Values which can be merged with coverage information from other sources.
+///
+/// `Merge` is implemented for [`section::Value`] and the maps in it ([`Functions`],
+/// [`Branches`] and [`Lines`]). Execution counts are summed up, and conflicting values, such as
+/// function start lines, line checksums and source file revisions, are resolved by a
+/// [`Resolver`].
+///
+/// Downstream containers of coverage information can implement `Merge` on top of these types to
+/// get the same merge semantics, including the errors on conflicts.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::report::section::{self, line};
+/// use lcov::report::{ConflictKind, Merge, Resolver};
+/// use std::collections::HashMap;
+///
+/// // Coverage information of source files collected by a build.
+/// #[derive(Default)]
+/// struct Build {
+///     files: HashMap<String, section::Value>,
+/// }
+///
+/// impl Merge for Build {
+///     fn merge_with(
+///         &mut self,
+///         other: Self,
+///         resolver: &mut Resolver<'_>,
+///     ) -> Result<(), ConflictKind> {
+///         for (path, value) in other.files {
+///             match self.files.get_mut(&path) {
+///                 Some(left) => left.merge_with(value, resolver)?,
+///                 None => {
+///                     let _ = self.files.insert(path, value);
+///                 }
+///             }
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let mut value = section::Value::default();
+/// let _ = value.lines.insert(line::Key { line: 1 }, line::Value { count: 1, checksum: None });
+///
+/// let mut build = Build::default();
+/// let _ = build.files.insert("src/main.rs".into(), value.clone());
+/// let mut other = Build::default();
+/// let _ = other.files.insert("src/main.rs".into(), value);
+///
+/// build.merge(other)?;
+/// assert_eq!(build.files["src/main.rs"].lines[&line::Key { line: 1 }].count, 2);
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+///
+/// [`section::Value`]: section/struct.Value.html
+/// [`Functions`]: section/function/type.Functions.html
+/// [`Branches`]: section/branch/type.Branches.html
+/// [`Lines`]: section/line/type.Lines.html
+/// [`Resolver`]: type.Resolver.html
+pub trait Merge: Sized {
+    /// Merges `other` into `self`, resolving conflicts by `resolver`.
+    ///
+    /// Returns the conflict for which `resolver` returns [`Resolution::Error`], in which case
+    /// `self` may be partially merged.
+    ///
+    /// [`Resolution::Error`]: enum.Resolution.html#variant.Error
+    fn merge_with(&mut self, other: Self, resolver: &mut Resolver<'_>) -> Result<(), ConflictKind>;
+
+    /// Merges `other` into `self`, failing on the first conflict.
+    fn merge(&mut self, other: Self) -> Result<(), ConflictKind> {
+        self.merge_with(other, &mut |_| Resolution::Error)
+    }
+
+    /// Merges `other` into `self`, keeping the values of `other` on conflicts.
+    fn merge_lossy(&mut self, other: Self) {
+        let result = self.merge_with(other, &mut |_| Resolution::KeepRight);
+        debug_assert!(result.is_ok());
+    }
+}
+
+// A value in a map, which needs its key to describe conflicts.
+pub(crate) trait MergeEntry<K>: Sized {
+    fn merge_entry(
+        &mut self,
+        key: &K,
+        other: Self,
+        resolver: &mut Resolver<'_>,
+    ) -> Result<(), ConflictKind>;
+}
+
+impl<K, V> Merge for BTreeMap<K, V>
+where
+    K: Ord,
+    V: MergeEntry<K>,
+{
+    fn merge_with(&mut self, other: Self, resolver: &mut Resolver<'_>) -> Result<(), ConflictKind> {
+        for (key, value) in other {
+            match self.get_mut(&key) {
+                Some(left) => left.merge_entry(&key, value, resolver)?,
+                None => {
+                    let _ = self.insert(key, value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Merges sorted entries into a hash map, which is used by `Merger` instead of `BTreeMap`.
+pub(crate) fn merge_into_hash_map<K, V>(
+    map: &mut HashMap<K, V>,
+    other: BTreeMap<K, V>,
+    resolver: &mut Resolver<'_>,
+) -> Result<(), ConflictKind>
+where
+    K: Hash + Eq,
+    V: MergeEntry<K>,
+{
+    map.reserve(other.len());
+    for (key, value) in other {
+        match map.get_mut(&key) {
+            Some(left) => left.merge_entry(&key, value, resolver)?,
+            None => {
+                let _ = map.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn merge_version(
+    version: &mut Option<String>,
+    mut other: Option<String>,
+    resolver: &mut Resolver<'_>,
+) -> Result<(), ConflictKind> {
+    if let (Some(left), Some(right)) = (&*version, &other) {
+        if left != right {
+            let kind = ConflictKind::Version {
+                version: left.clone(),
+                other_version: right.clone(),
+            };
+            if !keeps_right(resolver, kind)? {
+                other = None;
+            }
+        }
+    }
+    if other.is_some() {
+        *version = other;
+    }
+    Ok(())
+}
+
+// Records of unknown kinds are kept once, in the order of appearance.
+pub(crate) fn merge_unknown_records(records: &mut Vec<String>, other: Vec<String>) {
+    for text in other {
+        if !records.contains(&text) {
+            records.push(text);
+        }
+    }
+}
+
+// Sections are merged separately from other maps, as conflicts are reported with the section key.
+pub(crate) fn merge_sections(
+    sections: &mut Sections,
+    other: Sections,
+    strategy: MergeStrategy,
+    resolver: &mut dyn FnMut(&MergeConflict) -> Resolution,
+) -> Result<(), MergeConflict> {
+    for (key, value) in other {
+        merge_section(sections, key, value, strategy, resolver)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn merge_section(
+    sections: &mut Sections,
+    key: section::Key,
+    mut value: section::Value,
+    strategy: MergeStrategy,
+    resolver: &mut dyn FnMut(&MergeConflict) -> Resolution,
+) -> Result<(), MergeConflict> {
+    match sections.get_mut(&key) {
+        Some(left) => {
+            if strategy == MergeStrategy::Max {
+                value.excess_over(left);
+            }
+            let mut resolver = |kind: &ConflictKind| {
+                resolver(&MergeConflict {
+                    key: key.clone(),
+                    kind: kind.clone(),
+                })
+            };
+            if let Err(kind) = left.merge_with(value, &mut resolver) {
+                return Err(MergeConflict { key, kind });
+            }
+        }
+        None => {
+            let _ = sections.insert(key, value);
+        }
+    }
+    Ok(())
+}
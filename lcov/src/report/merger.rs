@@ -0,0 +1,301 @@
+use super::merge::{self, ConflictKind, MergeConflict, MergeStrategy, Resolution, Resolver};
+use super::section::{self, branch, function, line, Sections};
+use super::{MergeError, Report};
+use crate::cancel::CancellationToken;
+use std::collections::HashMap;
+
+/// An accumulator which merges many reports faster than [`Report::merge`].
+///
+/// `Report` keeps coverage information in `BTreeMap`s, and merging reports into it costs
+/// a lookup in the ordered maps for every line, function and branch.
+/// `Merger` keeps the information in hash maps while merging, and sorts it only once in
+/// [`finish`]. The result is the same as merging the reports into an empty [`Report`] in the same
+/// order, except that the record order preserved by [`ParseOptions::preserve_order`] is discarded.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::Report;
+/// use lcov::report::Merger;
+///
+/// let input1 = "TN:\nSF:foo.c\nDA:1,1\nDA:2,0\nend_of_record\n";
+/// let input2 = "TN:\nSF:foo.c\nDA:2,3\nend_of_record\nTN:\nSF:bar.c\nDA:1,1\nend_of_record\n";
+///
+/// let mut merger = Merger::new();
+/// merger.merge(input1.parse()?)?;
+/// merger.merge(input2.parse()?)?;
+/// let report = merger.finish();
+///
+/// let mut expected = input1.parse::<Report>()?;
+/// expected.merge(input2.parse()?)?;
+/// assert_eq!(report, expected);
+/// assert_eq!(report.line_count("foo.c", 2), Some(3));
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+///
+/// [`Report::merge`]: struct.Report.html#method.merge
+/// [`Report`]: struct.Report.html
+/// [`finish`]: #method.finish
+/// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+#[derive(Debug, Clone, Default)]
+pub struct Merger {
+    sections: HashMap<section::Key, Value>,
+    strategy: MergeStrategy,
+    cancellation: Option<CancellationToken>,
+}
+
+// The hash-based counterpart of `section::Value`.
+#[derive(Debug, Clone, Default)]
+struct Value {
+    version: Option<String>,
+    functions: HashMap<function::Key, function::Value>,
+    branches: HashMap<branch::Key, branch::Value>,
+    lines: HashMap<line::Key, line::Value>,
+    unknown_records: Vec<String>,
+}
+
+impl From<section::Value> for Value {
+    fn from(value: section::Value) -> Self {
+        Self {
+            version: value.version,
+            functions: value.functions.into_iter().collect(),
+            branches: value.branches.into_iter().collect(),
+            lines: value.lines.into_iter().collect(),
+            unknown_records: value.unknown_records,
+        }
+    }
+}
+
+impl Value {
+    // Reduces the counts of `other` by the ones in `self`, for `MergeStrategy::Max`.
+    fn excess_of(&self, other: &mut section::Value) {
+        merge::excess_over(&mut other.functions, |key| self.functions.get(key));
+        merge::excess_over(&mut other.branches, |key| self.branches.get(key));
+        merge::excess_over(&mut other.lines, |key| self.lines.get(key));
+    }
+
+    fn merge_with(
+        &mut self,
+        other: section::Value,
+        resolver: &mut Resolver<'_>,
+    ) -> Result<(), ConflictKind> {
+        merge::merge_version(&mut self.version, other.version, resolver)?;
+        merge::merge_into_hash_map(&mut self.functions, other.functions, resolver)?;
+        merge::merge_into_hash_map(&mut self.branches, other.branches, resolver)?;
+        merge::merge_into_hash_map(&mut self.lines, other.lines, resolver)?;
+        merge::merge_unknown_records(&mut self.unknown_records, other.unknown_records);
+        Ok(())
+    }
+
+    fn into_sorted(self) -> section::Value {
+        section::Value {
+            version: self.version,
+            functions: self.functions.into_iter().collect(),
+            branches: self.branches.into_iter().collect(),
+            lines: self.lines.into_iter().collect(),
+            unknown_records: self.unknown_records,
+        }
+    }
+}
+
+impl Merger {
+    /// Creates an empty merger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty merger with space for at least `sections` sections.
+    ///
+    /// Pre-sizing the merger avoids rehashing while merging many reports, if the number of
+    /// sections, i.e. pairs of a test name and a source file, is known in advance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::report::Merger;
+    ///
+    /// let mut merger = Merger::with_capacity(1000);
+    /// merger.merge("TN:\nSF:foo.c\nDA:1,1\nend_of_record\n".parse()?)?;
+    /// merger.shrink_to_fit();
+    /// assert_eq!(merger.finish().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn with_capacity(sections: usize) -> Self {
+        Self {
+            sections: HashMap::with_capacity(sections),
+            ..Self::default()
+        }
+    }
+
+    /// Reserves space for at least `additional` more sections.
+    pub fn reserve(&mut self, additional: usize) {
+        self.sections.reserve(additional);
+    }
+
+    /// Shrinks the capacity of the merger as much as possible.
+    ///
+    /// The hash maps of sections, functions, branches and lines grow in advance while merging,
+    /// and keep their spare capacity until [`finish`] is called. Shrinking them is useful if the
+    /// merger is kept for a long time between merges.
+    ///
+    /// [`finish`]: #method.finish
+    pub fn shrink_to_fit(&mut self) {
+        for value in self.sections.values_mut() {
+            value.functions.shrink_to_fit();
+            value.branches.shrink_to_fit();
+            value.lines.shrink_to_fit();
+            value.unknown_records.shrink_to_fit();
+        }
+        self.sections.shrink_to_fit();
+    }
+
+    /// Specifies how execution counts are combined.
+    ///
+    /// Counts are summed up by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::report::{Merger, MergeStrategy};
+    ///
+    /// let mut merger = Merger::new().strategy(MergeStrategy::Max);
+    /// merger.merge("TN:\nSF:foo.c\nDA:1,5\nBRDA:1,0,0,-\nend_of_record\n".parse()?)?;
+    /// merger.merge("TN:\nSF:foo.c\nDA:1,3\nBRDA:1,0,0,2\nend_of_record\n".parse()?)?;
+    /// let report = merger.finish();
+    /// assert_eq!(report.line_count("foo.c", 1), Some(5));
+    /// let branches = report.branches_at("foo.c", 1);
+    /// assert_eq!(branches.values().next().unwrap().taken, Some(2));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Specifies a token to cancel merging.
+    ///
+    /// The token is checked before each section is merged, and [`MergeError::Cancelled`] is
+    /// returned once it is cancelled. The sections merged before the cancellation are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::cancel::CancellationToken;
+    /// use lcov::report::{MergeError, Merger};
+    ///
+    /// let token = CancellationToken::new();
+    /// let mut merger = Merger::new().cancellation(token.clone());
+    /// merger.merge("TN:\nSF:foo.c\nDA:1,1\nend_of_record\n".parse()?)?;
+    ///
+    /// token.cancel();
+    /// let result = merger.merge("TN:\nSF:bar.c\nDA:1,1\nend_of_record\n".parse()?);
+    /// assert_eq!(result, Err(MergeError::Cancelled));
+    /// assert_eq!(merger.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`MergeError::Cancelled`]: enum.MergeError.html#variant.Cancelled
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Merges a report, like [`Report::merge`].
+    ///
+    /// As with [`Report::merge`], the merger may be partially merged if the merge fails.
+    ///
+    /// [`Report::merge`]: struct.Report.html#method.merge
+    pub fn merge(&mut self, report: Report) -> Result<(), MergeError> {
+        self.merge_resolve(report, |_| Resolution::Error)
+    }
+
+    /// Merges a report with ignoring errors, like [`Report::merge_lossy`].
+    ///
+    /// [`Report::merge_lossy`]: struct.Report.html#method.merge_lossy
+    pub fn merge_lossy(&mut self, report: Report) {
+        let result = self.merge_resolve(report, |_| Resolution::KeepRight);
+        debug_assert!(result.is_ok());
+    }
+
+    /// Merges a report, resolving conflicts by `resolver`, like [`Report::merge_resolve`].
+    ///
+    /// [`Report::merge_resolve`]: struct.Report.html#method.merge_resolve
+    pub fn merge_resolve<F>(&mut self, report: Report, mut resolver: F) -> Result<(), MergeError>
+    where
+        F: FnMut(&MergeConflict) -> Resolution,
+    {
+        self.sections.reserve(report.sections.len());
+        for (key, mut value) in report.sections {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                return Err(MergeError::Cancelled);
+            }
+            match self.sections.get_mut(&key) {
+                Some(left) => {
+                    if self.strategy == MergeStrategy::Max {
+                        left.excess_of(&mut value);
+                    }
+                    let mut resolver = |kind: &ConflictKind| {
+                        resolver(&MergeConflict {
+                            key: key.clone(),
+                            kind: kind.clone(),
+                        })
+                    };
+                    if let Err(kind) = left.merge_with(value, &mut resolver) {
+                        return Err(MergeConflict { key, kind }.into());
+                    }
+                }
+                None => {
+                    let _ = self.sections.insert(key, Value::from(value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of sections merged so far.
+    pub fn len(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Returns `true` if no section is merged.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Sorts the merged coverage information and returns it as a report.
+    pub fn finish(self) -> Report {
+        let sections = self
+            .sections
+            .into_iter()
+            .map(|(key, value)| (key, value.into_sorted()))
+            .collect::<Sections>();
+        Report {
+            sections,
+            layout: None,
+        }
+    }
+}
@@ -3,20 +3,50 @@
 //! The [`Report`] structure contains coverage information of every file.
 //!
 //! [`Report`]: struct.Report.html
+pub use self::builder::SectionBuilder;
+#[cfg(feature = "cache")]
+pub use self::cache::CacheError;
+pub(crate) use self::error::DisplayKey;
 pub use self::error::{MergeError, ParseError};
+use self::layout::Layout;
+pub use self::merge::{
+    ConflictKind, LossyMergeDiagnostics, Merge, MergeConflict, MergeStrategy, Resolution, Resolver,
+};
+pub use self::merger::Merger;
+pub use self::options::{AnonymizeOptions, OutputOptions, ParseOptions, SummaryRecords};
 use self::parser::Parser;
 use self::section::Sections;
+use self::summary::{
+    DiffCoverage, FunctionCoverage, Summary, Thresholds, UncoveredFunction, Violation,
+};
+pub use self::validate::{Finding, FindingKind};
+pub use self::visit::{walk_section, ReportVisitor};
+use super::filter::LineNum;
 use super::reader::Error as ReadError;
 use super::{Reader, Record, RecordKind};
-use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
-use std::path::Path;
+use std::mem;
+use std::ops::RangeInclusive;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
 #[macro_use]
 mod parser;
+mod builder;
+#[cfg(feature = "cache")]
+mod cache;
 mod error;
+mod fingerprint;
+mod heap_size;
+mod layout;
+mod merge;
+mod merger;
+mod options;
 pub mod section;
+pub mod summary;
+mod validate;
+mod visit;
 
 /// An accumulated coverage information from some LCOV tracefiles.
 ///
@@ -47,10 +77,197 @@ pub mod section;
 /// # fn main() {}
 /// ```
 ///
-#[derive(Debug, Clone, Default, Eq, PartialEq)]
+/// Two reports are equal if they have the same coverage information.
+/// The record order preserved by [`ParseOptions::preserve_order`] is not compared.
+///
+/// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+#[derive(Debug, Clone, Default)]
 pub struct Report {
     /// Coverage information about every source files.
     pub sections: Sections,
+    layout: Option<Layout>,
+}
+
+impl PartialEq for Report {
+    fn eq(&self, other: &Self) -> bool {
+        self.sections == other.sections
+    }
+}
+
+impl Eq for Report {}
+
+impl FromIterator<Record> for Report {
+    /// Creates a report from LCOV records.
+    ///
+    /// This is an infallible variant of [`from_reader`] for records which are already in memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the records are not a valid sequence of sections, e.g. a section is not
+    /// terminated by `end_of_record`. Collect into [`LossyCollect`] to skip such sections instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::{Record, Report};
+    ///
+    /// let records = vec![
+    ///     Record::TestName { name: "".into() },
+    ///     Record::SourceFile { path: "src/main.rs".into() },
+    ///     Record::LineData { line: 1, count: 2, checksum: None },
+    ///     Record::EndOfRecord,
+    /// ];
+    /// let report = records.into_iter().collect::<Report>();
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(2));
+    /// ```
+    ///
+    /// [`from_reader`]: #method.from_reader
+    /// [`LossyCollect`]: struct.LossyCollect.html
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = Record>,
+    {
+        let records = iter.into_iter().map(Ok::<_, ReadError>);
+        match Self::from_reader(records) {
+            Ok(report) => report,
+            Err(e) => panic!("invalid LCOV records: {}", e),
+        }
+    }
+}
+
+impl Extend<Record> for Report {
+    /// Adds the coverage information of LCOV records to `self`.
+    ///
+    /// The records are merged by [`merge_lossy`], so conflicting information is overwritten.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the records are not a valid sequence of sections. Extend [`LossyCollect`] to skip
+    /// such sections instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::{Record, Report};
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1);
+    /// report.extend(vec![
+    ///     Record::TestName { name: "".into() },
+    ///     Record::SourceFile { path: "src/main.rs".into() },
+    ///     Record::LineData { line: 1, count: 2, checksum: None },
+    ///     Record::EndOfRecord,
+    /// ]);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(3));
+    /// ```
+    ///
+    /// [`merge_lossy`]: #method.merge_lossy
+    /// [`LossyCollect`]: struct.LossyCollect.html
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = Record>,
+    {
+        self.merge_lossy(iter.into_iter().collect());
+    }
+}
+
+/// A report collected from LCOV records, skipping malformed sections instead of panicking.
+///
+/// The records are split at every `end_of_record`, and each part which is not a valid section
+/// is ignored. Valid sections are merged by [`merge_lossy`].
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Record;
+/// use lcov::report::LossyCollect;
+///
+/// let records = vec![
+///     Record::TestName { name: "".into() },
+///     Record::LinesFound { found: 0 },
+///     Record::EndOfRecord,
+///     Record::TestName { name: "".into() },
+///     Record::SourceFile { path: "src/main.rs".into() },
+///     Record::LineData { line: 1, count: 2, checksum: None },
+///     Record::EndOfRecord,
+/// ];
+/// let LossyCollect(report) = records.into_iter().collect();
+/// assert_eq!(report.line_count("src/main.rs", 1), Some(2));
+/// ```
+///
+/// [`merge_lossy`]: struct.Report.html#method.merge_lossy
+#[derive(Debug, Clone, Default)]
+pub struct LossyCollect(pub Report);
+
+impl LossyCollect {
+    fn merge_section(&mut self, records: Vec<Record>) {
+        let records = records.into_iter().map(Ok::<_, ReadError>);
+        if let Ok(report) = Report::from_reader(records) {
+            self.0.merge_lossy(report);
+        }
+    }
+}
+
+impl FromIterator<Record> for LossyCollect {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = Record>,
+    {
+        let mut collect = Self::default();
+        collect.extend(iter);
+        collect
+    }
+}
+
+impl Extend<Record> for LossyCollect {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = Record>,
+    {
+        let mut records = vec![];
+        for record in iter {
+            let end = record == Record::EndOfRecord;
+            records.push(record);
+            if end {
+                self.merge_section(mem::take(&mut records));
+            }
+        }
+        // An unterminated section is always malformed.
+        if !records.is_empty() {
+            self.merge_section(records);
+        }
+    }
+}
+
+impl FromStr for Report {
+    type Err = ParseError;
+
+    /// Creates a report from the content of LCOV tracefile.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let input = "\
+    /// TN:test_name
+    /// SF:/path/to/source/file.rs
+    /// DA:1,2
+    /// DA:3,0
+    /// end_of_record
+    /// ";
+    /// let report = input.parse::<Report>()?;
+    /// assert_eq!(report.line_count("/path/to/source/file.rs", 1), Some(2));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_reader(Reader::new(s.as_bytes()))
+    }
 }
 
 impl Report {
@@ -66,6 +283,38 @@ impl Report {
         Self::default()
     }
 
+    /// Returns `true` if the report contains no sections.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// assert!(report.is_empty());
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1);
+    /// assert!(!report.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Returns the number of sections, i.e. pairs of a test name and a source file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("test1", "src/main.rs").add_line(1, 1);
+    /// let _ = report.section("test2", "src/main.rs").add_line(1, 0);
+    /// assert_eq!(report.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sections.len()
+    }
+
     /// Creates a report from LCOV record reader.
     ///
     /// # Examples
@@ -91,13 +340,52 @@ impl Report {
     /// # fn main() {}
     /// ```
     pub fn from_reader<I, E>(iter: I) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = Result<Record, E>>,
+        E: Into<ReadError>,
+    {
+        Self::from_reader_with(iter, &ParseOptions::default())
+    }
+
+    /// Creates a report from LCOV record reader with the specified options.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::{Report, Reader};
+    /// use lcov::report::ParseOptions;
+    ///
+    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// let input = "\
+    /// TN:test_name
+    /// SF:/path/to/source/file.rs
+    /// DA:1,2
+    /// DA:3,0
+    /// DA:5,6
+    /// LF:3
+    /// LH:2
+    /// end_of_record
+    /// ";
+    /// let reader = Reader::new(input.as_bytes());
+    /// let report = Report::from_reader_with(reader, &ParseOptions::new().strict(true))?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn from_reader_with<I, E>(iter: I, options: &ParseOptions) -> Result<Self, ParseError>
     where
         I: IntoIterator<Item = Result<Record, E>>,
         E: Into<ReadError>,
     {
         let mut parser = Parser::new(iter.into_iter().map(|item| item.map_err(Into::into)));
+        let mut layout = if options.preserve_order {
+            Some(Layout::default())
+        } else {
+            None
+        };
         let report = Report {
-            sections: section::parse(&mut parser)?,
+            sections: section::parse(&mut parser, options, layout.as_mut())?,
+            layout,
         };
         Ok(report)
     }
@@ -116,15 +404,189 @@ impl Report {
     /// # fn main() {}
     /// ```
     pub fn from_file<P>(path: P) -> Result<Self, ParseError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_file_with(path, &ParseOptions::default())
+    }
+
+    /// Creates a report from LCOV tracefile with the specified options.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::ParseOptions;
+    ///
+    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// let report = Report::from_file_with("report.info", &ParseOptions::new().strict(true))?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn from_file_with<P>(path: P, options: &ParseOptions) -> Result<Self, ParseError>
     where
         P: AsRef<Path>,
     {
         let reader = Reader::open_file(path)
-            .map_err(Into::into)
             .map_err(ReadError::Io)
-            .map_err(Into::into)
             .map_err(ParseError::Read)?;
-        Self::from_reader(reader)
+        Self::from_reader_with(reader, options)
+    }
+
+    /// Returns `true` if `self` and `other` have the same coverage information.
+    ///
+    /// Reports are compared section by section with [`section::Value::semantic_eq`], so line
+    /// checksums, source file revisions and unknown records are ignored. As with `==`, the order
+    /// of records and summary records (`LF`, `LH` and so on) in the original tracefiles never
+    /// matter. This is useful for comparing tracefiles produced by different generators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let input1 = "\
+    /// TN:
+    /// SF:foo.c
+    /// DA:1,1,8f3a
+    /// DA:2,0,c0de
+    /// LF:2
+    /// LH:1
+    /// end_of_record
+    /// ";
+    /// let input2 = "\
+    /// TN:
+    /// SF:foo.c
+    /// DA:2,0
+    /// DA:1,1
+    /// end_of_record
+    /// ";
+    /// let report1 = input1.parse::<Report>()?;
+    /// let report2 = input2.parse::<Report>()?;
+    /// assert_ne!(report1, report2);
+    /// assert!(report1.semantic_eq(&report2));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`section::Value::semantic_eq`]: section/struct.Value.html#method.semantic_eq
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        self.sections.len() == other.sections.len()
+            && self.sections.iter().zip(&other.sections).all(
+                |((key, value), (other_key, other_value))| {
+                    key == other_key && value.semantic_eq(other_value)
+                },
+            )
+    }
+
+    /// Computes a digest of the coverage information.
+    ///
+    /// The digest covers the same information as [`semantic_eq`] compares, so reports which are
+    /// semantically equal have the same fingerprint. The digest is stable across versions of this
+    /// crate and platforms, and can be used as a cache key, e.g. to skip uploading coverage which
+    /// has not changed since the previous CI run. It is not a cryptographic hash.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let report1 = "TN:\nSF:foo.c\nDA:1,1,8f3a\nend_of_record\n".parse::<Report>()?;
+    /// let report2 = "TN:\nSF:foo.c\nDA:1,1\nend_of_record\n".parse::<Report>()?;
+    /// assert_eq!(report1.fingerprint(), report2.fingerprint());
+    /// println!("cache key: {:016x}", report1.fingerprint());
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`semantic_eq`]: #method.semantic_eq
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint::fingerprint(&self.sections)
+    }
+
+    /// Returns the approximate number of bytes allocated on the heap by the report.
+    ///
+    /// The estimation walks every section and counts the entries of the maps and the strings in
+    /// them, including the record order preserved by [`ParseOptions::preserve_order`].
+    /// Allocator overhead is not counted, so the actual memory consumption may be somewhat
+    /// larger. This is useful to enforce memory limits while merging many untrusted tracefiles.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// const LIMIT: usize = 64 * 1024 * 1024;
+    ///
+    /// let mut report = Report::new();
+    /// assert_eq!(report.estimated_heap_size(), 0);
+    ///
+    /// for input in ["TN:\nSF:foo.c\nDA:1,1\nend_of_record\n", "TN:\nSF:bar.c\nDA:1,0\nend_of_record\n"] {
+    ///     report.merge(input.parse()?)?;
+    ///     if report.estimated_heap_size() > LIMIT {
+    ///         return Err("too large coverage data".into());
+    ///     }
+    /// }
+    /// assert!(report.estimated_heap_size() > 0);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn estimated_heap_size(&self) -> usize {
+        heap_size::sections(&self.sections) + self.layout.as_ref().map_or(0, Layout::heap_size)
+    }
+
+    /// Shrinks the memory allocated for the report as much as possible.
+    ///
+    /// Spare capacity of the strings and vectors in the sections and the record order preserved
+    /// by [`ParseOptions::preserve_order`] is released. This is useful to keep a merged report
+    /// for a long time. See [`section::Value::shrink_to_fit`] for more.
+    ///
+    /// Sections are stored in `BTreeMap`s, which cannot be pre-sized. To reduce reallocations
+    /// while merging many reports, use [`Merger::with_capacity`] or [`Merger::reserve`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let mut report = "TN:\nSF:foo.c\nDA:1,1\nend_of_record\n".parse::<Report>()?;
+    /// let before = report.estimated_heap_size();
+    /// report.shrink_to_fit();
+    /// assert!(report.estimated_heap_size() <= before);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    /// [`section::Value::shrink_to_fit`]: section/struct.Value.html#method.shrink_to_fit
+    /// [`Merger::with_capacity`]: struct.Merger.html#method.with_capacity
+    /// [`Merger::reserve`]: struct.Merger.html#method.reserve
+    pub fn shrink_to_fit(&mut self) {
+        for value in self.sections.values_mut() {
+            value.shrink_to_fit();
+        }
+        if let Some(layout) = &mut self.layout {
+            layout.shrink_to_fit();
+        }
     }
 
     /// Merges a report into `self`.
@@ -142,7 +604,7 @@ impl Report {
     /// # fn main() {}
     /// ```
     pub fn merge(&mut self, other: Self) -> Result<(), MergeError> {
-        self.sections.merge(other.sections)
+        self.merge_resolve(other, |_| Resolution::Error)
     }
 
     /// Merges a report into `self` with ignoring an Errors.
@@ -160,91 +622,1465 @@ impl Report {
     /// # fn main() {}
     /// ```
     pub fn merge_lossy(&mut self, other: Self) {
-        self.sections.merge_lossy(other.sections)
+        let result = self.merge_resolve(other, |_| Resolution::KeepRight);
+        debug_assert!(result.is_ok());
     }
 
-    /// Creates an iterator which iterates over [LCOV section].
+    /// Merges a report into `self` with ignoring errors, like [`merge_lossy`], and returns
+    /// a summary of the conflicting values overridden.
     ///
     /// # Examples
     ///
     /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     /// use lcov::Report;
     ///
-    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
-    /// let mut report = Report::from_file("report.info")?;
-    /// // ... Manipulate report
-    /// for record in report.into_records() {
-    ///    println!("{}", record);
-    /// }
+    /// let mut report = "TN:\nSF:foo.c\nDA:1,1,a\nDA:2,1,b\nend_of_record\n".parse::<Report>()?;
+    /// let other = "TN:\nSF:foo.c\nDA:1,1,c\nDA:2,1,d\nend_of_record\n".parse()?;
+    /// let diagnostics = report.merge_lossy_diagnostics(other);
+    /// assert_eq!(diagnostics.checksums, 2);
+    /// assert_eq!(
+    ///     diagnostics.to_string(),
+    ///     "overrode 0 function start line(s), 2 checksum(s) and 0 revision(s) in 1 file(s)"
+    /// );
     /// # Ok(())
     /// # }
-    /// # fn main() {}
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
     /// ```
     ///
-    /// [LCOV records]: enum.Record.html
-    pub fn into_records(self) -> IntoRecords {
-        IntoRecords {
-            iter: section::into_records(self.sections),
-        }
+    /// [`merge_lossy`]: #method.merge_lossy
+    pub fn merge_lossy_diagnostics(&mut self, other: Self) -> LossyMergeDiagnostics {
+        let mut diagnostics = LossyMergeDiagnostics::default();
+        let result = self.merge_resolve(other, |conflict| {
+            diagnostics.add(conflict);
+            Resolution::KeepRight
+        });
+        debug_assert!(result.is_ok());
+        diagnostics
     }
-}
 
-/// An iterator which iterates [LCOV records].
-///
-/// This `struct` is created by the [`into_records`] methods on [`Report`].
-/// See its documentation for more.
-///
-/// [`LCOV records`]: ../struct.Record.html
-/// [`into_records`]: struct.Report.html#method.into_records
-/// [`Report`]: struct.Report.html
-pub struct IntoRecords {
-    iter: Box<dyn Iterator<Item = Record>>,
-}
+    /// Merges a report into `self`, resolving conflicts by `resolver`.
+    ///
+    /// `resolver` is called for each conflict of function start lines, line checksums and
+    /// source file revisions, and decides which value is kept or whether the merge fails.
+    /// [`merge`] is equivalent to a resolver always returning [`Resolution::Error`], and
+    /// [`merge_lossy`] to one always returning [`Resolution::KeepRight`].
+    ///
+    /// As with [`merge`], `self` may be partially merged if the merge fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    /// use lcov::report::{ConflictKind, Resolution};
+    ///
+    /// let input1 = "\
+    /// TN:
+    /// SF:foo.c
+    /// FN:3,foo
+    /// DA:4,1,old_checksum
+    /// end_of_record
+    /// ";
+    /// let input2 = "\
+    /// TN:
+    /// SF:foo.c
+    /// FN:4,foo
+    /// DA:4,2,new_checksum
+    /// end_of_record
+    /// ";
+    /// let mut report = input1.parse::<Report>()?;
+    /// report.merge_resolve(input2.parse()?, |conflict| match conflict.kind {
+    ///     // Keeps the earlier start line.
+    ///     ConflictKind::FunctionLine { start_line, other_start_line, .. } => {
+    ///         if start_line <= other_start_line {
+    ///             Resolution::KeepLeft
+    ///         } else {
+    ///             Resolution::KeepRight
+    ///         }
+    ///     }
+    ///     ConflictKind::Checksum { .. } => Resolution::KeepRight,
+    ///     ConflictKind::Version { .. } => Resolution::Error,
+    /// })?;
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.functions.values().next().unwrap().start_line, Some(3));
+    /// assert_eq!(report.line_count("foo.c", 4), Some(3));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    /// [`merge_lossy`]: #method.merge_lossy
+    /// [`Resolution::Error`]: enum.Resolution.html#variant.Error
+    /// [`Resolution::KeepRight`]: enum.Resolution.html#variant.KeepRight
+    pub fn merge_resolve<F>(&mut self, other: Self, resolver: F) -> Result<(), MergeError>
+    where
+        F: FnMut(&MergeConflict) -> Resolution,
+    {
+        self.merge_with_strategy(other, MergeStrategy::Sum, resolver)
+    }
 
-impl fmt::Debug for IntoRecords {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "IntoIter {{ .. }}")
+    /// Merges a report into `self`, combining execution counts by `strategy` and resolving
+    /// conflicts by `resolver`.
+    ///
+    /// [`merge_resolve`] is equivalent to [`MergeStrategy::Sum`]. See [`MergeStrategy`] for an
+    /// example.
+    ///
+    /// [`merge_resolve`]: #method.merge_resolve
+    /// [`MergeStrategy`]: enum.MergeStrategy.html
+    /// [`MergeStrategy::Sum`]: enum.MergeStrategy.html#variant.Sum
+    pub fn merge_with_strategy<F>(
+        &mut self,
+        other: Self,
+        strategy: MergeStrategy,
+        mut resolver: F,
+    ) -> Result<(), MergeError>
+    where
+        F: FnMut(&MergeConflict) -> Resolution,
+    {
+        merge::merge_sections(&mut self.sections, other.sections, strategy, &mut resolver)
+            .map_err(MergeError::from)
     }
-}
 
-impl Iterator for IntoRecords {
-    type Item = Record;
+    /// Merges a report into `self`, collecting all conflicts instead of aborting on the first one.
+    ///
+    /// Conflicting values (function start lines, line checksums and source file revisions) in
+    /// `self` are kept, and everything else is merged.
+    /// Returns the number of sections in `other` merged without conflicts, and the conflicts
+    /// found, in the order of sections.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    /// use lcov::report::ConflictKind;
+    ///
+    /// let input1 = "\
+    /// TN:
+    /// SF:foo.c
+    /// FN:3,foo
+    /// DA:4,1,a
+    /// DA:5,1,b
+    /// end_of_record
+    /// ";
+    /// let input2 = "\
+    /// TN:
+    /// SF:foo.c
+    /// FN:4,foo
+    /// DA:4,1,c
+    /// DA:5,1,b
+    /// end_of_record
+    /// TN:
+    /// SF:bar.c
+    /// DA:1,1
+    /// end_of_record
+    /// ";
+    /// let mut report = input1.parse::<Report>()?;
+    /// let (merged, conflicts) = report.merge_collect(input2.parse()?);
+    /// assert_eq!(merged, 1);
+    /// assert_eq!(conflicts.len(), 2);
+    /// assert!(matches!(conflicts[0].kind, ConflictKind::FunctionLine { .. }));
+    /// assert!(matches!(conflicts[1].kind, ConflictKind::Checksum { line: 4, .. }));
+    /// assert_eq!(report.line_count("foo.c", 5), Some(2));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn merge_collect(&mut self, other: Self) -> (usize, Vec<MergeConflict>) {
+        let sections = other.sections.len();
+        let mut conflicts = Vec::<MergeConflict>::new();
+        let mut conflicting_sections = 0;
+        let result = self.merge_resolve(other, |conflict| {
+            if conflicts.last().map(|last| &last.key) != Some(&conflict.key) {
+                conflicting_sections += 1;
+            }
+            conflicts.push(conflict.clone());
+            Resolution::KeepLeft
+        });
+        debug_assert!(result.is_ok());
+        (sections - conflicting_sections, conflicts)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next()
+    /// Brings the report into the canonical form, e.g. after manual modification of `sections`.
+    ///
+    /// * Paths of source files are normalized lexically: `.` components are removed, and `..`
+    ///   components are folded into the preceding directory. The file system is not accessed.
+    /// * Sections which have the same key after the normalization are merged by
+    ///   [`merge_lossy`].
+    /// * Sections without coverage information are removed, including the ones kept by
+    ///   [`ParseOptions::preserve_order`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::section;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/./main.rs").add_line(1, 1);
+    /// let _ = report.section("", "src/foo/../main.rs").add_line(1, 2);
+    /// let _ = report.sections.insert(
+    ///     section::Key { test_name: "".into(), source_file: "src/lib.rs".into() },
+    ///     section::Value::default(),
+    /// );
+    /// report.normalize();
+    ///
+    /// assert_eq!(report.sections.len(), 1);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(3));
+    /// ```
+    ///
+    /// [`merge_lossy`]: #method.merge_lossy
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn normalize(&mut self) {
+        self.sections.retain(|_, value| !value.is_empty());
+        let result = self.map_paths_resolve(normalize_path, |_| Resolution::KeepRight);
+        debug_assert!(result.is_ok());
+        if let Some(layout) = &mut self.layout {
+            layout.remove_empty_sections();
+        }
     }
-}
 
-trait Merge {
-    fn merge(&mut self, other: Self) -> Result<(), MergeError>;
-    fn merge_lossy(&mut self, other: Self);
-}
+    /// Rewrites the paths of the source files of all sections by `f`.
+    ///
+    /// Sections which collapse onto the same test name and source file are merged as by
+    /// [`merge`]. This is the building block of remapping paths, e.g. between the build
+    /// environment and the source tree. The record order preserved by
+    /// [`ParseOptions::preserve_order`] is kept, and merged sections are output at the position
+    /// of the first one.
+    ///
+    /// Unlike [`merge`], all sections are rewritten even if the merge fails: the conflicting
+    /// values are overridden as by [`merge_lossy`], and the first conflict is returned as an
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "/build/a/src/main.rs").add_line(1, 1);
+    /// let _ = report.section("", "/build/b/src/main.rs").add_line(1, 2);
+    /// let _ = report.section("", "/usr/include/stdio.h").add_line(1, 0);
+    ///
+    /// report.map_paths(|path| {
+    ///     let rest = path.strip_prefix("/build/a").or_else(|_| path.strip_prefix("/build/b"));
+    ///     rest.unwrap_or(path).to_path_buf()
+    /// })?;
+    /// assert_eq!(report.sections.len(), 2);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(3));
+    /// assert_eq!(report.line_count("/usr/include/stdio.h", 1), Some(0));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    /// [`merge_lossy`]: #method.merge_lossy
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn map_paths<F>(&mut self, f: F) -> Result<(), MergeError>
+    where
+        F: FnMut(&Path) -> PathBuf,
+    {
+        self.map_paths_resolve(f, |_| Resolution::Error)
+    }
+
+    /// Rewrites the paths of the source files of all sections by `f`, resolving conflicts of
+    /// the merged sections by `resolver`.
+    ///
+    /// See [`map_paths`] and [`merge_resolve`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    /// use lcov::report::Resolution;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut report = "TN:\nSF:a/foo.c\nDA:1,1,x\nend_of_record\n".parse::<Report>()?;
+    /// report.merge_lossy("TN:\nSF:b/foo.c\nDA:1,1,y\nend_of_record\n".parse()?);
+    ///
+    /// let mut strict = report.clone();
+    /// assert!(strict.map_paths(|_| PathBuf::from("foo.c")).is_err());
+    ///
+    /// report.map_paths_resolve(|_| PathBuf::from("foo.c"), |_| Resolution::KeepLeft)?;
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// let checksums = section.lines.values().map(|v| v.checksum.as_deref()).collect::<Vec<_>>();
+    /// assert_eq!(checksums, [Some("x")]);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`map_paths`]: #method.map_paths
+    /// [`merge_resolve`]: #method.merge_resolve
+    pub fn map_paths_resolve<F, R>(&mut self, mut f: F, resolver: R) -> Result<(), MergeError>
+    where
+        F: FnMut(&Path) -> PathBuf,
+        R: FnMut(&MergeConflict) -> Resolution,
+    {
+        self.map_keys(
+            |key| section::Key {
+                test_name: key.test_name.clone(),
+                source_file: f(&key.source_file),
+            },
+            resolver,
+        )
+    }
+
+    /// Rewrites the test names of all sections by `f`.
+    ///
+    /// Sections which collapse onto the same test name and source file are merged as by
+    /// [`merge`], e.g. to combine the shards of a test suite. The record order preserved by
+    /// [`ParseOptions::preserve_order`] is kept, and merged sections are output at the position
+    /// of the first one. Conflicts are handled as [`map_paths`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("unit_01", "src/main.rs").add_line(1, 1);
+    /// let _ = report.section("unit_02", "src/main.rs").add_line(1, 2);
+    /// let _ = report.section("integration", "src/main.rs").add_line(1, 4);
+    ///
+    /// report.map_test_names(|name| match name.rsplit_once('_') {
+    ///     Some((suite, shard)) if shard.chars().all(|c| c.is_ascii_digit()) => suite.into(),
+    ///     _ => name.into(),
+    /// })?;
+    /// let names = report.sections.keys().map(|key| key.test_name.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(names, ["integration", "unit"]);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(7));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    /// [`map_paths`]: #method.map_paths
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn map_test_names<F>(&mut self, f: F) -> Result<(), MergeError>
+    where
+        F: FnMut(&str) -> String,
+    {
+        self.map_test_names_resolve(f, |_| Resolution::Error)
+    }
+
+    /// Rewrites the test names of all sections by `f`, resolving conflicts of the merged
+    /// sections by `resolver`.
+    ///
+    /// See [`map_test_names`] and [`merge_resolve`] for more.
+    ///
+    /// [`map_test_names`]: #method.map_test_names
+    /// [`merge_resolve`]: #method.merge_resolve
+    pub fn map_test_names_resolve<F, R>(&mut self, mut f: F, resolver: R) -> Result<(), MergeError>
+    where
+        F: FnMut(&str) -> String,
+        R: FnMut(&MergeConflict) -> Resolution,
+    {
+        self.map_keys(
+            |key| section::Key {
+                test_name: f(&key.test_name),
+                source_file: key.source_file.clone(),
+            },
+            resolver,
+        )
+    }
 
-impl<K, V> Merge for BTreeMap<K, V>
-where
-    K: Ord,
-    V: Merge,
-{
-    fn merge(&mut self, other: Self) -> Result<(), MergeError> {
-        for (key, value) in other {
-            match self.entry(key) {
-                Entry::Vacant(e) => {
-                    let _ = e.insert(value);
+    /// Removes information which is not needed to share the coverage with third parties.
+    ///
+    /// Paths of source files are made relative to the project, test names are replaced with their
+    /// hashes, and line checksums are removed, as specified by `options`. Records of unknown
+    /// kinds and the record order preserved by [`ParseOptions::preserve_order`] are always
+    /// discarded, as they may contain arbitrary text. Sections which collapse onto the same test
+    /// name and source file are merged like [`merge_lossy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::AnonymizeOptions;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("acme_secret_feature", "/home/alice/acme/src/main.rs")
+    ///     .add_line(1, 1);
+    /// let _ = report.section("", "/usr/include/stdio.h").add_line(1, 0);
+    ///
+    /// report.anonymize(&AnonymizeOptions::new().strip_prefix("/home/alice/acme"));
+    /// let keys = report.sections.keys().collect::<Vec<_>>();
+    /// assert_eq!(keys[0].test_name, "");
+    /// assert_eq!(keys[0].source_file.to_str(), Some("/usr/include/stdio.h"));
+    /// assert_eq!(keys[1].source_file.to_str(), Some("src/main.rs"));
+    /// assert_ne!(keys[1].test_name, "acme_secret_feature");
+    /// ```
+    ///
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    /// [`merge_lossy`]: #method.merge_lossy
+    pub fn anonymize(&mut self, options: &AnonymizeOptions) {
+        self.layout = None;
+        let result = self.map_keys(
+            |key| {
+                let test_name = if options.hash_test_names && !key.test_name.is_empty() {
+                    format!("{:016x}", fingerprint::hash_str(&key.test_name))
+                } else {
+                    key.test_name.clone()
+                };
+                section::Key {
+                    test_name,
+                    source_file: options.source_file(&key.source_file),
+                }
+            },
+            |_| Resolution::KeepRight,
+        );
+        debug_assert!(result.is_ok());
+        for value in self.sections.values_mut() {
+            value.unknown_records.clear();
+            if options.remove_checksums {
+                for line in value.lines.values_mut() {
+                    line.checksum = None;
                 }
-                Entry::Occupied(mut e) => e.get_mut().merge(value)?,
             }
         }
-        Ok(())
     }
 
-    fn merge_lossy(&mut self, other: Self) {
-        for (key, value) in other {
-            match self.entry(key) {
-                Entry::Vacant(e) => {
-                    let _ = e.insert(value);
-                }
-                Entry::Occupied(mut e) => e.get_mut().merge_lossy(value),
+    /// Clamps every execution count to 0 or 1, producing a "covered or not" report.
+    ///
+    /// This is useful for comparing the coverage between runs with different iteration counts.
+    /// See [`section::Value::binarize`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_function("main", 1, 3)
+    ///     .add_branch(2, 0, 0, Some(4))
+    ///     .add_line(2, 10);
+    /// report.binarize();
+    /// assert_eq!(report.function_count("src/main.rs", "main"), Some(1));
+    /// assert_eq!(report.line_count("src/main.rs", 2), Some(1));
+    /// ```
+    ///
+    /// [`section::Value::binarize`]: section/struct.Value.html#method.binarize
+    pub fn binarize(&mut self) {
+        for value in self.sections.values_mut() {
+            value.binarize();
+        }
+    }
+
+    /// Returns a copy of the report with every execution count reset to zero.
+    ///
+    /// The result keeps all the source files, lines, functions and branches, like the baseline
+    /// captured by `lcov --initial`. Merging run data into the baseline makes sure that source
+    /// files not executed at all still appear in the result.
+    /// See [`section::Value::zero_counts`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut run = Report::new();
+    /// let _ = run.section("", "src/main.rs").add_line(1, 3).add_line(2, 0);
+    /// let _ = run.section("", "src/unused.rs").add_line(1, 0);
+    ///
+    /// let mut report = run.zeroed();
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(0));
+    /// assert_eq!(report.line_count("src/unused.rs", 1), Some(0));
+    ///
+    /// let mut later_run = Report::new();
+    /// let _ = later_run.section("", "src/main.rs").add_line(1, 2);
+    /// report.merge(later_run).unwrap();
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(2));
+    /// assert_eq!(report.line_count("src/unused.rs", 1), Some(0));
+    /// ```
+    ///
+    /// [`section::Value::zero_counts`]: section/struct.Value.html#method.zero_counts
+    pub fn zeroed(&self) -> Report {
+        let mut report = self.clone();
+        for value in report.sections.values_mut() {
+            value.zero_counts();
+        }
+        report
+    }
+
+    /// Multiplies every execution count by `factor`.
+    ///
+    /// This is useful for down-weighting coverage of long-running tests before merging it with
+    /// other coverage. See [`section::Value::scale_counts`] for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is negative or NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 100);
+    /// report.scale_counts(0.5);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(50));
+    /// ```
+    ///
+    /// [`section::Value::scale_counts`]: section/struct.Value.html#method.scale_counts
+    pub fn scale_counts(&mut self, factor: f64) {
+        self.scale_counts_by(|_| factor);
+    }
+
+    /// Multiplies the execution counts of each section by the factor returned by `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` returns a negative factor or NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("unit", "src/main.rs").add_line(1, 10);
+    /// let _ = report.section("soak", "src/main.rs").add_line(1, 1000);
+    /// report.scale_counts_by(|key| if key.test_name == "soak" { 0.01 } else { 1.0 });
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(20));
+    /// ```
+    pub fn scale_counts_by<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key) -> f64,
+    {
+        for (key, value) in &mut self.sections {
+            value.scale_counts(f(key));
+        }
+    }
+
+    /// Subtracts the execution counts of `baseline` from the ones in the report.
+    ///
+    /// The counts of all tests in `baseline` are summed up for each source file, and subtracted
+    /// from the section of every test for the source file, saturating at zero. So a baseline
+    /// captured under another test name, e.g. by a run without tests, removes the counts of
+    /// start-up code from all tests. See [`section::Value::subtract`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("unit", "src/main.rs").add_line(1, 10).add_line(2, 1);
+    /// let _ = report.section("integration", "src/main.rs").add_line(1, 3);
+    /// let _ = report.section("unit", "src/lib.rs").add_line(1, 4);
+    /// let mut baseline = Report::new();
+    /// let _ = baseline.section("", "src/main.rs").add_line(1, 1).add_line(2, 1);
+    ///
+    /// report.subtract(&baseline);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(9 + 2));
+    /// assert_eq!(report.line_count("src/main.rs", 2), Some(0));
+    /// assert_eq!(report.line_count("src/lib.rs", 1), Some(4));
+    /// ```
+    ///
+    /// [`section::Value::subtract`]: section/struct.Value.html#method.subtract
+    pub fn subtract(&mut self, baseline: &Report) {
+        let mut files = BTreeMap::<&Path, section::Value>::new();
+        for (key, value) in &baseline.sections {
+            files
+                .entry(&key.source_file)
+                .or_default()
+                .merge_lossy(value.clone());
+        }
+        for (key, value) in &mut self.sections {
+            if let Some(base) = files.get(key.source_file.as_path()) {
+                value.subtract(base);
             }
         }
     }
+
+    /// Splits the report into reports of each source file.
+    ///
+    /// The sections of all tests for a source file go into the same report. The record order
+    /// preserved by [`ParseOptions::preserve_order`] is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("unit", "src/main.rs").add_line(1, 1);
+    /// let _ = report.section("fuzz", "src/main.rs").add_line(2, 1);
+    /// let _ = report.section("unit", "src/lib.rs").add_line(1, 0);
+    ///
+    /// let reports = report.split_by_file();
+    /// assert_eq!(reports.len(), 2);
+    /// assert_eq!(reports[Path::new("src/main.rs")].len(), 2);
+    /// assert_eq!(reports[Path::new("src/lib.rs")].line_count("src/lib.rs", 1), Some(0));
+    /// ```
+    ///
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn split_by_file(self) -> BTreeMap<PathBuf, Report> {
+        self.split(|path| path.to_path_buf())
+    }
+
+    /// Splits the report into reports of each directory of the source files.
+    ///
+    /// Directories are truncated to the first `depth` components, as
+    /// [`Summary::by_directory`] does, and source files at the top level go into the report of
+    /// `.`. The record order preserved by [`ParseOptions::preserve_order`] is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1);
+    /// let _ = report.section("", "src/report/mod.rs").add_line(1, 1);
+    /// let _ = report.section("", "build.rs").add_line(1, 0);
+    ///
+    /// let reports = report.split_by_directory(Some(1));
+    /// let dirs = reports.keys().map(|dir| dir.to_str().unwrap()).collect::<Vec<_>>();
+    /// assert_eq!(dirs, [".", "src"]);
+    /// assert_eq!(reports[Path::new("src")].len(), 2);
+    /// ```
+    ///
+    /// [`Summary::by_directory`]: summary/struct.Summary.html#method.by_directory
+    /// [`ParseOptions::preserve_order`]: struct.ParseOptions.html#method.preserve_order
+    pub fn split_by_directory(self, depth: Option<usize>) -> BTreeMap<PathBuf, Report> {
+        self.split(|path| summary::directory(path, depth))
+    }
+
+    fn split<F>(self, mut f: F) -> BTreeMap<PathBuf, Report>
+    where
+        F: FnMut(&Path) -> PathBuf,
+    {
+        let mut reports = BTreeMap::<PathBuf, Report>::new();
+        for (key, value) in self.sections {
+            let report = reports.entry(f(&key.source_file)).or_default();
+            let _ = report.sections.insert(key, value);
+        }
+        reports
+    }
+
+    /// Retains only the sections which satisfy the predicate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("unit", "src/main.rs").add_line(1, 1);
+    /// let _ = report.section("fuzz", "src/main.rs").add_line(2, 1);
+    /// report.retain_sections(|key, _| key.test_name != "fuzz");
+    /// assert_eq!(report.len(), 1);
+    /// assert_eq!(report.line_count("src/main.rs", 2), None);
+    /// ```
+    pub fn retain_sections<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key, &mut section::Value) -> bool,
+    {
+        self.sections.retain(|key, value| f(key, value));
+    }
+
+    /// Retains only the lines which satisfy the predicate.
+    ///
+    /// Branches on removed lines are kept; use [`retain_branches`] to remove them.
+    /// Sections left without any coverage information are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    /// let _ = report.section("", "src/lib.rs").add_line(1, 0);
+    /// report.retain_lines(|_, _, value| value.count > 0);
+    /// assert_eq!(report.line_count("src/main.rs", 1), Some(1));
+    /// assert_eq!(report.line_count("src/main.rs", 2), None);
+    /// assert_eq!(report.len(), 1);
+    /// ```
+    ///
+    /// [`retain_branches`]: #method.retain_branches
+    pub fn retain_lines<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key, &section::line::Key, &mut section::line::Value) -> bool,
+    {
+        self.retain_in_sections(|key, value| value.lines.retain(|line, data| f(key, line, data)));
+    }
+
+    /// Retains only the functions which satisfy the predicate.
+    ///
+    /// Sections left without any coverage information are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_function("main", 1, 1)
+    ///     .add_function("__cxx_global_var_init", 10, 1);
+    /// report.retain_functions(|_, function, _| !function.name.starts_with("__"));
+    /// assert_eq!(report.function_count("src/main.rs", "main"), Some(1));
+    /// assert_eq!(report.function_count("src/main.rs", "__cxx_global_var_init"), None);
+    /// ```
+    pub fn retain_functions<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key, &section::function::Key, &mut section::function::Value) -> bool,
+    {
+        self.retain_in_sections(|key, value| {
+            value
+                .functions
+                .retain(|function, data| f(key, function, data))
+        });
+    }
+
+    /// Retains only the branches which satisfy the predicate.
+    ///
+    /// Sections left without any coverage information are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_branch(10, 0, 0, Some(1))
+    ///     .add_branch(10, 0, 1, None);
+    /// report.retain_branches(|_, _, value| value.taken.is_some());
+    /// assert_eq!(report.total_branches_found(), 1);
+    /// ```
+    pub fn retain_branches<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key, &section::branch::Key, &mut section::branch::Value) -> bool,
+    {
+        self.retain_in_sections(|key, value| {
+            value.branches.retain(|branch, data| f(key, branch, data))
+        });
+    }
+
+    // Applies `f` to each section, and removes sections which `f` leaves empty.
+    fn retain_in_sections<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&section::Key, &mut section::Value),
+    {
+        self.sections.retain(|key, value| {
+            let was_empty = value.is_empty();
+            f(key, value);
+            was_empty || !value.is_empty()
+        });
+    }
+
+    // Rewrites the keys of sections, and merges sections which collapse onto the same key.
+    // Unresolved conflicts are overridden, so that no section is lost, and the first one is
+    // returned.
+    fn map_keys<F, R>(&mut self, mut f: F, mut resolver: R) -> Result<(), MergeError>
+    where
+        F: FnMut(&section::Key) -> section::Key,
+        R: FnMut(&MergeConflict) -> Resolution,
+    {
+        let mut error = None;
+        let mut resolver = |conflict: &MergeConflict| match resolver(conflict) {
+            Resolution::Error => {
+                if error.is_none() {
+                    error = Some(conflict.clone());
+                }
+                Resolution::KeepRight
+            }
+            resolution => resolution,
+        };
+        let mut mapped = HashMap::new();
+        let mut sections = Sections::new();
+        for (key, value) in mem::take(&mut self.sections) {
+            let new_key = f(&key);
+            let result = merge::merge_section(
+                &mut sections,
+                new_key.clone(),
+                value,
+                MergeStrategy::Sum,
+                &mut resolver,
+            );
+            debug_assert!(result.is_ok());
+            if self.layout.is_some() {
+                let _ = mapped.insert(key, new_key);
+            }
+        }
+        self.sections = sections;
+        if let Some(layout) = &mut self.layout {
+            // `f` is called only once for each key, and also for the keys of empty sections.
+            layout.map_keys(|key| match mapped.get(key) {
+                Some(new_key) => new_key.clone(),
+                None => f(key),
+            });
+        }
+        error.map_or(Ok(()), |conflict| Err(conflict.into()))
+    }
+
+    /// Returns a builder which adds coverage information to the section specified by the test
+    /// name and the source file path.
+    ///
+    /// The section is created if it does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("test_name", "/path/to/source/file.rs")
+    ///     .add_function("main", 1, 1)
+    ///     .add_branch(2, 0, 0, Some(1))
+    ///     .add_branch(2, 0, 1, Some(0))
+    ///     .add_line(1, 1)
+    ///     .add_line(2, 1);
+    ///
+    /// let output = report.into_records().map(|rec| format!("{}\n", rec)).collect::<String>();
+    /// assert_eq!(output, "\
+    /// TN:test_name
+    /// SF:/path/to/source/file.rs
+    /// FN:1,main
+    /// FNDA:1,main
+    /// FNF:1
+    /// FNH:1
+    /// BRDA:2,0,0,1
+    /// BRDA:2,0,1,0
+    /// BRF:2
+    /// BRH:1
+    /// DA:1,1
+    /// DA:2,1
+    /// LF:2
+    /// LH:2
+    /// end_of_record
+    /// ");
+    /// ```
+    pub fn section<S, P>(&mut self, test_name: S, source_file: P) -> SectionBuilder<'_>
+    where
+        S: Into<String>,
+        P: Into<PathBuf>,
+    {
+        let key = section::Key {
+            test_name: test_name.into(),
+            source_file: source_file.into(),
+        };
+        SectionBuilder::new(self, key)
+    }
+
+    /// Returns the execution count of a line of a source file, summed across all tests.
+    ///
+    /// Returns `None` if the line is not instrumented in any test.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("test1", "src/main.rs").add_line(10, 3);
+    /// let _ = report.section("test2", "src/main.rs").add_line(10, 2);
+    /// assert_eq!(report.line_count("src/main.rs", 10), Some(5));
+    /// assert_eq!(report.line_count("src/main.rs", 11), None);
+    /// ```
+    pub fn line_count<P>(&self, source_file: P, line: u32) -> Option<u64>
+    where
+        P: AsRef<Path>,
+    {
+        let key = section::line::Key { line };
+        self.sections_of(source_file.as_ref())
+            .filter_map(|value| value.lines.get(&key))
+            .map(|value| value.count)
+            .reduce(u64::saturating_add)
+    }
+
+    /// Returns the execution count of a function of a source file, summed across all tests.
+    ///
+    /// Returns `None` if the function is not found in any test.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("test1", "src/main.rs").add_function("main", 1, 1);
+    /// let _ = report.section("test2", "src/main.rs").add_function("main", 1, 0);
+    /// assert_eq!(report.function_count("src/main.rs", "main"), Some(1));
+    /// assert_eq!(report.function_count("src/main.rs", "foo"), None);
+    /// ```
+    pub fn function_count<P>(&self, source_file: P, name: &str) -> Option<u64>
+    where
+        P: AsRef<Path>,
+    {
+        let key = section::function::Key { name: name.into() };
+        self.sections_of(source_file.as_ref())
+            .filter_map(|value| value.functions.get(&key))
+            .map(|value| value.count)
+            .reduce(u64::saturating_add)
+    }
+
+    /// Returns the branches on a line of a source file, merged across all tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("test1", "src/main.rs").add_branch(10, 0, 0, Some(1));
+    /// let _ = report
+    ///     .section("test2", "src/main.rs")
+    ///     .add_branch(10, 0, 0, Some(2))
+    ///     .add_branch(10, 0, 1, None);
+    /// let branches = report.branches_at("src/main.rs", 10);
+    /// let taken = branches.values().map(|value| value.taken).collect::<Vec<_>>();
+    /// assert_eq!(taken, [Some(3), None]);
+    /// ```
+    pub fn branches_at<P>(&self, source_file: P, line: u32) -> section::branch::Branches
+    where
+        P: AsRef<Path>,
+    {
+        let mut branches = section::branch::Branches::new();
+        for value in self.sections_of(source_file.as_ref()) {
+            let on_line = value
+                .branches
+                .iter()
+                .filter(|(key, _)| key.line == line)
+                .map(|(key, value)| (*key, *value))
+                .collect();
+            branches.merge_lossy(on_line);
+        }
+        branches
+    }
+
+    /// Returns the number of lines found in the report.
+    ///
+    /// Lines of the same source file are counted once across tests, as [`summary`] does.
+    /// Each call counts over all sections, so use [`Sections::total_stats`] to get several
+    /// numbers at once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("test1", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    /// let _ = report.section("test2", "src/main.rs").add_line(2, 0).add_line(3, 2);
+    /// assert_eq!(report.total_lines_found(), 3);
+    /// assert_eq!(report.total_lines_hit(), 2);
+    /// ```
+    ///
+    /// [`summary`]: #method.summary
+    /// [`Sections::total_stats`]: section/struct.Sections.html#method.total_stats
+    pub fn total_lines_found(&self) -> u32 {
+        self.sections.total_stats().lines.found
+    }
+
+    /// Returns the number of lines with a non-zero execution count in the report.
+    ///
+    /// See [`total_lines_found`] for an example.
+    ///
+    /// [`total_lines_found`]: #method.total_lines_found
+    pub fn total_lines_hit(&self) -> u32 {
+        self.sections.total_stats().lines.hit
+    }
+
+    /// Returns the number of functions found in the report.
+    ///
+    /// Functions of the same source file are counted once across tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("test1", "src/main.rs").add_function("main", 1, 0);
+    /// let _ = report.section("test2", "src/main.rs").add_function("main", 1, 1);
+    /// assert_eq!(report.total_functions_found(), 1);
+    /// assert_eq!(report.total_functions_hit(), 1);
+    /// ```
+    pub fn total_functions_found(&self) -> u32 {
+        self.sections.total_stats().functions.found
+    }
+
+    /// Returns the number of functions with a non-zero execution count in the report.
+    ///
+    /// See [`total_functions_found`] for an example.
+    ///
+    /// [`total_functions_found`]: #method.total_functions_found
+    pub fn total_functions_hit(&self) -> u32 {
+        self.sections.total_stats().functions.hit
+    }
+
+    /// Returns the number of branches found in the report.
+    ///
+    /// Branches of the same source file are counted once across tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_branch(10, 0, 0, Some(1))
+    ///     .add_branch(10, 0, 1, None);
+    /// assert_eq!(report.total_branches_found(), 2);
+    /// assert_eq!(report.total_branches_hit(), 1);
+    /// ```
+    pub fn total_branches_found(&self) -> u32 {
+        self.sections.total_stats().branches.found
+    }
+
+    /// Returns the number of branches taken at least once in the report.
+    ///
+    /// See [`total_branches_found`] for an example.
+    ///
+    /// [`total_branches_found`]: #method.total_branches_found
+    pub fn total_branches_hit(&self) -> u32 {
+        self.sections.total_stats().branches.hit
+    }
+
+    fn sections_of<'a>(
+        &'a self,
+        source_file: &'a Path,
+    ) -> impl Iterator<Item = &'a section::Value> + 'a {
+        self.sections
+            .iter()
+            .filter(move |(key, _)| key.source_file == source_file)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns a parallel iterator over the sections.
+    ///
+    /// This method is available only if the `rayon` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    /// let _ = report.section("", "src/lib.rs").add_line(1, 1);
+    /// let hit = report
+    ///     .par_sections()
+    ///     .map(|(_, value)| value.lines_hit())
+    ///     .sum::<u32>();
+    /// assert_eq!(hit, 2);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_sections(
+        &self,
+    ) -> impl rayon::iter::ParallelIterator<Item = (&section::Key, &section::Value)> {
+        rayon::iter::IntoParallelRefIterator::par_iter(&self.sections)
+    }
+
+    /// Returns a parallel iterator over the sections, allowing modification of the values.
+    ///
+    /// This method is available only if the `rayon` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_branch(1, 0, 0, Some(1));
+    /// report.par_sections_mut().for_each(|(_, value)| value.branches.clear());
+    /// assert!(report.branches_at("src/main.rs", 1).is_empty());
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_sections_mut(
+        &mut self,
+    ) -> impl rayon::iter::ParallelIterator<Item = (&section::Key, &mut section::Value)> {
+        rayon::iter::IntoParallelRefMutIterator::par_iter_mut(&mut self.sections)
+    }
+
+    /// Passes every section of the report to `visitor`, in the order of [`section::Key`].
+    ///
+    /// See [`ReportVisitor`] for an example.
+    ///
+    /// [`section::Key`]: section/struct.Key.html
+    /// [`ReportVisitor`]: trait.ReportVisitor.html
+    pub fn accept<V>(&self, mut visitor: V)
+    where
+        V: ReportVisitor,
+    {
+        for (key, value) in &self.sections {
+            visitor.visit_section(key, value);
+        }
+    }
+
+    /// Computes the summary of the coverage information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    ///
+    /// let summary = report.summary();
+    /// assert_eq!(summary.total.lines.rate(), Some(0.5));
+    /// println!("{}", summary.markdown());
+    /// ```
+    pub fn summary(&self) -> Summary {
+        Summary::new(&self.sections)
+    }
+
+    /// Computes the coverage of each function, including the lines and branches attributed to it.
+    ///
+    /// Sections of the same source file are merged before counting, as [`summary`] does.
+    /// Functions are ordered by source file, and then by start line. Functions without start
+    /// lines come last in each source file, without lines and branches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_function("main", 1, 1)
+    ///     .add_function("unused", 10, 0)
+    ///     .add_line(2, 1)
+    ///     .add_line(3, 0)
+    ///     .add_line(11, 0);
+    ///
+    /// let mut functions = report.function_coverage();
+    /// assert_eq!(functions[0].name, "main");
+    /// assert_eq!(functions[0].lines.to_string(), "50.00% (1/2)");
+    ///
+    /// // Least covered functions first.
+    /// functions.sort_by(|a, b| a.lines.rate().partial_cmp(&b.lines.rate()).unwrap());
+    /// assert_eq!(functions[0].name, "unused");
+    /// assert_eq!(functions[0].count, 0);
+    /// ```
+    ///
+    /// [`summary`]: #method.summary
+    pub fn function_coverage(&self) -> Vec<FunctionCoverage> {
+        summary::function_coverage(&self.sections)
+    }
+
+    /// Returns the functions not executed by any test.
+    ///
+    /// Execution counts of the same function in different tests are summed up. Functions are
+    /// sorted by the path of the source file, then by the start line. Functions whose start line
+    /// is unknown come last in each source file, sorted by name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("test1", "src/main.rs")
+    ///     .add_function("main", 1, 1)
+    ///     .add_function("unused", 20, 0)
+    ///     .add_function("helper", 10, 0);
+    /// let _ = report.section("test2", "src/main.rs").add_function("helper", 10, 1);
+    /// let _ = report.section("test1", "src/lib.rs").add_function("dead", 5, 0);
+    ///
+    /// let uncovered = report
+    ///     .uncovered_functions()
+    ///     .into_iter()
+    ///     .map(|f| format!("{}:{}: {}", f.source_file.display(), f.start_line.unwrap(), f.name))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(uncovered, ["src/lib.rs:5: dead", "src/main.rs:20: unused"]);
+    /// ```
+    pub fn uncovered_functions(&self) -> Vec<UncoveredFunction> {
+        summary::uncovered_functions(&self.sections)
+    }
+
+    /// Returns the lines not executed by any test, for each source file.
+    ///
+    /// Consecutive line numbers are merged into a range. Source files without such lines are
+    /// omitted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("test1", "src/main.rs")
+    ///     .add_line(1, 1)
+    ///     .add_line(2, 0)
+    ///     .add_line(3, 0)
+    ///     .add_line(5, 0)
+    ///     .add_line(6, 0);
+    /// let _ = report.section("test2", "src/main.rs").add_line(6, 1);
+    /// let _ = report.section("test1", "src/lib.rs").add_line(1, 1);
+    ///
+    /// let uncovered = report.uncovered_lines();
+    /// assert_eq!(uncovered.len(), 1);
+    /// assert_eq!(uncovered[Path::new("src/main.rs")], [2..=3, 5..=5]);
+    /// ```
+    pub fn uncovered_lines(&self) -> BTreeMap<PathBuf, Vec<RangeInclusive<u32>>> {
+        summary::uncovered_lines(&self.sections, 0)
+            .into_iter()
+            .map(|(path, lines)| (path, lines.ranges().collect()))
+            .collect()
+    }
+
+    /// Returns the lines not executed by any test as hunks, for each source file.
+    ///
+    /// Unlike [`uncovered_lines`], ranges separated by at most `gap` lines without coverage
+    /// information (`DA` records), such as blank lines or comments, are joined. Ranges are never
+    /// joined across covered lines. The result is ready for display.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/foo.c")
+    ///     .add_line(120, 0)
+    ///     .add_line(122, 0)
+    ///     .add_line(134, 0)
+    ///     .add_line(200, 0)
+    ///     .add_line(203, 0);
+    ///
+    /// for (path, lines) in report.uncovered_ranges(11) {
+    ///     assert_eq!(format!("{}: {}", path.display(), lines), "src/foo.c: 120-134, 200-203");
+    /// }
+    /// ```
+    ///
+    /// [`uncovered_lines`]: #method.uncovered_lines
+    pub fn uncovered_ranges(&self, gap: u32) -> BTreeMap<PathBuf, LineNum> {
+        summary::uncovered_lines(&self.sections, gap)
+    }
+
+    /// Computes the coverage of changed lines, e.g. the lines added or modified by a pull request.
+    ///
+    /// Only the changed lines with coverage information (`DA` records) are counted, and a line is
+    /// hit if any test executes it. Source files without such lines are omitted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::filter::LineNum;
+    /// use std::collections::HashMap;
+    /// use std::iter::FromIterator;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("test1", "src/main.rs")
+    ///     .add_line(1, 1)
+    ///     .add_line(2, 0)
+    ///     .add_line(3, 0)
+    ///     .add_line(10, 0);
+    /// let _ = report.section("test2", "src/main.rs").add_line(3, 1);
+    /// let _ = report.section("test1", "src/lib.rs").add_line(1, 0);
+    ///
+    /// let mut changes = HashMap::new();
+    /// let _ = changes.insert(PathBuf::from("src/main.rs"), LineNum::from_iter([2..5]));
+    /// let coverage = report.diff_coverage(&changes);
+    /// assert_eq!(coverage.total.found, 2);
+    /// assert_eq!(coverage.total.hit, 1);
+    /// assert_eq!(coverage.total.to_string(), "50.00% (1/2)");
+    /// assert_eq!(coverage.files.len(), 1);
+    /// ```
+    pub fn diff_coverage(&self, changes: &HashMap<PathBuf, LineNum>) -> DiffCoverage {
+        summary::diff_coverage(&self.sections, changes)
+    }
+
+    /// Returns the coverage rates below the minimum specified by `thresholds`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::summary::Thresholds;
+    ///
+    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// let report = Report::from_file("report.info")?;
+    /// let thresholds = Thresholds::new().lines(80.0).branches(50.0).file_lines(30.0);
+    /// for violation in report.check(&thresholds) {
+    ///     eprintln!("{}", violation);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn check(&self, thresholds: &Thresholds) -> Vec<Violation> {
+        self.summary().check(thresholds)
+    }
+
+    /// Checks the consistency of the coverage information.
+    ///
+    /// Returns all problems found in the report, such as functions without start lines,
+    /// branches on lines without line data, and line numbers of zero.
+    /// Conflicts between sections of the same source file, which cause [`merge`] to fail,
+    /// are also reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Report};
+    /// use lcov::report::FindingKind;
+    ///
+    /// let input = "\
+    /// TN:
+    /// SF:/path/to/source/file.rs
+    /// FNDA:1,main
+    /// DA:1,1
+    /// end_of_record
+    /// ";
+    /// let report = Report::from_reader(Reader::new(input.as_bytes()))?;
+    /// let findings = report.validate();
+    /// assert_eq!(findings.len(), 1);
+    /// assert_eq!(findings[0].kind, FindingKind::FunctionWithoutStartLine { name: "main".into() });
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    pub fn validate(&self) -> Vec<Finding> {
+        validate::validate(&self.sections)
+    }
+
+    /// Creates an iterator which iterates over [LCOV section].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut report = Report::from_file("report.info")?;
+    /// // ... Manipulate report
+    /// for record in report.into_records() {
+    ///    println!("{}", record);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [LCOV records]: enum.Record.html
+    pub fn into_records(self) -> IntoRecords {
+        self.into_records_with(&OutputOptions::default())
+    }
+
+    /// Creates an iterator which iterates over [LCOV records] with the specified options.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::{OutputOptions, SummaryRecords};
+    ///
+    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// let report = Report::from_file("report.info")?;
+    /// let options = OutputOptions::new().summary_records(SummaryRecords::Never);
+    /// for record in report.into_records_with(&options) {
+    ///    println!("{}", record);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [LCOV records]: enum.Record.html
+    pub fn into_records_with(self, options: &OutputOptions) -> IntoRecords {
+        let iter = match self.layout {
+            Some(layout) => layout::into_records(layout, self.sections, options.summary),
+            None => section::into_records(self.sections, options.summary),
+        };
+        IntoRecords { iter }
+    }
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                let _ = normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// An iterator which iterates [LCOV records].
+///
+/// This `struct` is created by the [`into_records`] methods on [`Report`].
+/// See its documentation for more.
+///
+/// [`LCOV records`]: ../struct.Record.html
+/// [`into_records`]: struct.Report.html#method.into_records
+/// [`Report`]: struct.Report.html
+pub struct IntoRecords {
+    iter: Box<dyn Iterator<Item = Record>>,
+}
+
+impl fmt::Debug for IntoRecords {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "IntoIter {{ .. }}")
+    }
+}
+
+impl Iterator for IntoRecords {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
 }
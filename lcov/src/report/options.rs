@@ -0,0 +1,352 @@
+use crate::cancel::CancellationToken;
+use std::path::{Path, PathBuf};
+
+/// Options to control how a [`Report`] is created from LCOV records.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::ParseOptions;
+///
+/// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = ParseOptions::new().strict(true);
+/// let report = Report::from_file_with("report.info", &options)?;
+/// # Ok(())
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// [`Report`]: struct.Report.html
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) strict: bool,
+    pub(crate) preserve_order: bool,
+    pub(crate) ignore_checksums: bool,
+    pub(crate) cancellation: Option<CancellationToken>,
+}
+
+impl ParseOptions {
+    /// Creates the default options.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::ParseOptions;
+    /// let options = ParseOptions::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables the strict mode.
+    ///
+    /// In the strict mode, summary records (`FNF`, `FNH`, `BRF`, `BRH`, `LF` and `LH`) are verified
+    /// against the detail records of the same section, and [`ParseError::SummaryMismatch`] is
+    /// returned on discrepancy. Otherwise, summary records are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn main() {
+    /// use lcov::{Reader, RecordKind, Report};
+    /// use lcov::report::{ParseError, ParseOptions};
+    ///
+    /// let input = "\
+    /// TN:
+    /// SF:/path/to/source/file.rs
+    /// DA:1,2
+    /// DA:3,0
+    /// LF:2
+    /// LH:2
+    /// end_of_record
+    /// ";
+    /// let options = ParseOptions::new().strict(true);
+    /// assert_matches!(Report::from_reader_with(Reader::new(input.as_bytes()), &options),
+    ///                 Err(ParseError::SummaryMismatch { kind: RecordKind::LinesHit, found: 2, expected: 1, .. }));
+    /// # }
+    /// ```
+    ///
+    /// [`ParseError::SummaryMismatch`]: enum.ParseError.html#variant.SummaryMismatch
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Enables or disables preserving the original order of sections and records.
+    ///
+    /// By default, [`Report`] sorts sections and records, so the output of [`into_records`]
+    /// may differ textually from the input even if they are semantically equal.
+    /// If this option is enabled, the report remembers the original order, and [`into_records`]
+    /// reproduces the input record-by-record, as long as the input is well-formed
+    /// (e.g. summary records match the detail records).
+    ///
+    /// Records added after parsing are output in the sorted order after the preserved ones.
+    /// Sections without coverage information, which are usually dropped, and comment lines are
+    /// output as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Report};
+    /// use lcov::report::ParseOptions;
+    ///
+    /// let input = "\
+    /// TN:
+    /// SF:/path/to/source/file.rs
+    /// DA:5,6
+    /// DA:1,2
+    /// LH:2
+    /// LF:2
+    /// end_of_record
+    /// ";
+    /// let options = ParseOptions::new().preserve_order(true);
+    /// let report = Report::from_reader_with(Reader::new(input.as_bytes()), &options)?;
+    /// let output = report.into_records().map(|rec| format!("{}\n", rec)).collect::<String>();
+    /// assert_eq!(input, output);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`Report`]: struct.Report.html
+    /// [`into_records`]: struct.Report.html#method.into_records
+    pub fn preserve_order(mut self, preserve_order: bool) -> Self {
+        self.preserve_order = preserve_order;
+        self
+    }
+
+    /// Enables or disables ignoring checksums of lines (`DA` records).
+    ///
+    /// Checksums are dropped while parsing, so reports generated from different revisions of the
+    /// source files can be merged without [`MergeError::UnmatchedChecksum`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Report};
+    /// use lcov::report::ParseOptions;
+    ///
+    /// let input = "SF:src/main.rs\nDA:1,2,Q2hlY2tzdW0\nend_of_record\n";
+    /// let options = ParseOptions::new().ignore_checksums(true);
+    /// let report = Report::from_reader_with(Reader::new(input.as_bytes()), &options)?;
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert!(section.lines.values().all(|line| line.checksum.is_none()));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`MergeError::UnmatchedChecksum`]: enum.MergeError.html#variant.UnmatchedChecksum
+    pub fn ignore_checksums(mut self, ignore_checksums: bool) -> Self {
+        self.ignore_checksums = ignore_checksums;
+        self
+    }
+
+    /// Specifies a token to cancel parsing.
+    ///
+    /// The token is checked before each section is parsed, and [`ParseError::Cancelled`] is
+    /// returned once it is cancelled. See [`CancellationToken`] for an example.
+    ///
+    /// [`ParseError::Cancelled`]: enum.ParseError.html#variant.Cancelled
+    /// [`CancellationToken`]: ../cancel/struct.CancellationToken.html
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+/// Options to control how a [`Report`] is converted into LCOV records.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::{OutputOptions, SummaryRecords};
+///
+/// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let report = Report::from_file("report.info")?;
+/// let options = OutputOptions::new().summary_records(SummaryRecords::Never);
+/// for record in report.into_records_with(&options) {
+///     println!("{}", record);
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// [`Report`]: struct.Report.html
+#[derive(Debug, Copy, Clone, Default)]
+pub struct OutputOptions {
+    pub(crate) summary: SummaryRecords,
+}
+
+impl OutputOptions {
+    /// Creates the default options.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::OutputOptions;
+    /// let options = OutputOptions::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies when summary records (`FNF`, `FNH`, `BRF`, `BRH`, `LF` and `LH`) are emitted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Reader, Report};
+    /// use lcov::report::{OutputOptions, SummaryRecords};
+    ///
+    /// let input = "\
+    /// TN:
+    /// SF:/path/to/source/file.rs
+    /// DA:1,2
+    /// end_of_record
+    /// ";
+    /// let report = Report::from_reader(Reader::new(input.as_bytes()))?;
+    ///
+    /// let options = OutputOptions::new().summary_records(SummaryRecords::Always);
+    /// let output = report.into_records_with(&options).map(|rec| format!("{}\n", rec)).collect::<String>();
+    /// assert_eq!(output, "\
+    /// TN:
+    /// SF:/path/to/source/file.rs
+    /// FNF:0
+    /// FNH:0
+    /// BRF:0
+    /// BRH:0
+    /// DA:1,2
+    /// LF:1
+    /// LH:1
+    /// end_of_record
+    /// ");
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn summary_records(mut self, summary: SummaryRecords) -> Self {
+        self.summary = summary;
+        self
+    }
+}
+
+/// Options to control how [`Report::anonymize`] removes information from a report.
+///
+/// By default, test names are hashed and line checksums are removed. Paths of source files are
+/// kept unless prefixes to strip are specified.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::report::AnonymizeOptions;
+///
+/// let options = AnonymizeOptions::new()
+///     .strip_prefix("/home/alice/work/project")
+///     .remove_checksums(false);
+/// ```
+///
+/// [`Report::anonymize`]: struct.Report.html#method.anonymize
+#[derive(Debug, Clone)]
+pub struct AnonymizeOptions {
+    pub(crate) prefixes: Vec<PathBuf>,
+    pub(crate) hash_test_names: bool,
+    pub(crate) remove_checksums: bool,
+}
+
+impl Default for AnonymizeOptions {
+    fn default() -> Self {
+        AnonymizeOptions {
+            prefixes: vec![],
+            hash_test_names: true,
+            remove_checksums: true,
+        }
+    }
+}
+
+impl AnonymizeOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a prefix stripped from paths of source files, such as the root of the project.
+    ///
+    /// Paths are made relative to the first matching prefix. Paths outside all prefixes, e.g.
+    /// system headers, are kept as they are.
+    pub fn strip_prefix<P>(mut self, prefix: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Enables or disables replacing test names with their hashes.
+    ///
+    /// The hash is not cryptographic, so short or common test names can be guessed.
+    /// Empty test names are kept.
+    pub fn hash_test_names(mut self, hash_test_names: bool) -> Self {
+        self.hash_test_names = hash_test_names;
+        self
+    }
+
+    /// Enables or disables removing checksums of lines.
+    pub fn remove_checksums(mut self, remove_checksums: bool) -> Self {
+        self.remove_checksums = remove_checksums;
+        self
+    }
+
+    pub(crate) fn source_file(&self, path: &Path) -> PathBuf {
+        self.prefixes
+            .iter()
+            .find_map(|prefix| path.strip_prefix(prefix).ok())
+            .unwrap_or(path)
+            .into()
+    }
+}
+
+/// Specifies when summary records are emitted.
+///
+/// See [`OutputOptions::summary_records`] for more.
+///
+/// [`OutputOptions::summary_records`]: struct.OutputOptions.html#method.summary_records
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum SummaryRecords {
+    /// Emits summary records only for non-empty categories.
+    #[default]
+    Auto,
+    /// Never emits summary records.
+    Never,
+    /// Always emits summary records, even for empty categories.
+    Always,
+}
+
+impl SummaryRecords {
+    pub(crate) fn should_emit(self, is_empty: bool) -> bool {
+        match self {
+            SummaryRecords::Auto => !is_empty,
+            SummaryRecords::Never => false,
+            SummaryRecords::Always => true,
+        }
+    }
+}
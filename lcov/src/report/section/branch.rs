@@ -3,8 +3,10 @@
 //! Some coverage information is stored in a [`Branches`] as `BTreeMap` .
 //!
 //! [`Branches`]: ./type.Branches.html
-use super::{Merge, MergeError, Record};
+use super::merge::{ConflictKind, Excess, Merge, MergeEntry, Resolver};
+use super::{Record, SummaryRecords};
 use std::collections::BTreeMap;
+use std::fmt;
 use std::iter;
 
 /// A map of coverage information about branches.
@@ -16,11 +18,14 @@ pub type Branches = BTreeMap<Key, Value>;
 ///
 /// [`Branches`]: ./type.Branches.html
 #[derive(Debug, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Key {
     /// Line number.
     pub line: u32,
     /// Block number.
     pub block: u32,
+    /// Whether the branch is an exception branch.
+    pub exception: bool,
     /// Branch number.
     pub branch: u32,
 }
@@ -30,27 +35,181 @@ pub struct Key {
 /// This struct is used as a value of [`Branches`].
 ///
 /// [`Branches`]: ./type.Branches.html
-#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Value {
     /// A number indicating how often that branch was taken.
     pub taken: Option<u64>,
 }
 
-impl Merge for Value {
-    fn merge(&mut self, other: Self) -> Result<(), MergeError> {
-        self.merge_lossy(other);
-        Ok(())
+impl Value {
+    /// Returns the state of the branch, distinguishing a branch whose block is never executed
+    /// (`-` in `BRDA` records) from one executed but not taken (`0`).
+    ///
+    /// Merging keeps the distinction: a branch is never executed only if its block is executed
+    /// in none of the merged reports.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    /// use lcov::report::section::branch::State;
+    ///
+    /// let mut report = "TN:\nSF:foo.c\nBRDA:1,0,0,-\nBRDA:1,0,1,-\nend_of_record\n".parse::<Report>()?;
+    /// report.merge("TN:\nSF:foo.c\nBRDA:1,0,0,-\nBRDA:1,0,1,0\nend_of_record\n".parse()?)?;
+    ///
+    /// let states = report
+    ///     .branches_at("foo.c", 1)
+    ///     .values()
+    ///     .map(|value| value.state())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(states, [State::NotExecuted, State::NotTaken]);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn state(&self) -> State {
+        match self.taken {
+            None => State::NotExecuted,
+            Some(0) => State::NotTaken,
+            Some(taken) => State::Taken(taken),
+        }
+    }
+
+    /// Returns `true` if the block containing the branch is executed, whether the branch is
+    /// taken or not.
+    pub fn is_executed(&self) -> bool {
+        self.taken.is_some()
+    }
+}
+
+/// The state of a branch, returned by [`Value::state`].
+///
+/// [`Value::state`]: struct.Value.html#method.state
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum State {
+    /// The block containing the branch is never executed (`-`).
+    NotExecuted,
+    /// The block is executed, but the branch is never taken (`0`).
+    NotTaken,
+    /// The branch is taken the number of times.
+    Taken(u64),
+}
+
+/// Branch coverage information about a line, as genhtml displays.
+///
+/// This struct is created by [`by_line`]. See its documentation for more.
+///
+/// [`by_line`]: fn.by_line.html
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct LineBranches {
+    /// Number of branches on the line.
+    pub found: u32,
+    /// Number of branches whose block is executed, i.e. which have a taken count.
+    pub executed: u32,
+    /// Number of branches taken at least once.
+    pub taken: u32,
+}
+
+/// Formats as `taken/found`, e.g. `2/4`.
+impl fmt::Display for LineBranches {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.taken, self.found)
+    }
+}
+
+/// Groups branches by line.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::section::branch;
+///
+/// let mut report = Report::new();
+/// let _ = report
+///     .section("", "src/main.rs")
+///     .add_branch(1, 0, 0, Some(1))
+///     .add_branch(1, 0, 1, Some(0))
+///     .add_branch(1, 1, 0, Some(3))
+///     .add_branch(1, 1, 1, None)
+///     .add_branch(5, 0, 0, None);
+/// let (_, section) = report.sections.iter().next().unwrap();
+///
+/// let lines = branch::by_line(&section.branches);
+/// assert_eq!(lines.len(), 2);
+/// assert_eq!(lines[&1].to_string(), "2/4");
+/// assert_eq!(lines[&1].executed, 3);
+/// assert_eq!(lines[&5].taken, 0);
+/// ```
+pub fn by_line(branches: &Branches) -> BTreeMap<u32, LineBranches> {
+    let mut lines = BTreeMap::<u32, LineBranches>::new();
+    for (key, value) in branches {
+        let line = lines.entry(key.line).or_default();
+        line.found += 1;
+        if let Some(taken) = value.taken {
+            line.executed += 1;
+            if taken > 0 {
+                line.taken += 1;
+            }
+        }
+    }
+    lines
+}
+
+/// Merges branches of the same source file into `branches`.
+///
+/// Execution counts are summed up. Branches never conflict.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::report::section::branch::{self, Branches, Key, Value};
+///
+/// let key = Key { line: 1, block: 0, exception: false, branch: 0 };
+/// let mut branches = Branches::new();
+/// let _ = branches.insert(key, Value { taken: None });
+/// let mut other = Branches::new();
+/// let _ = other.insert(key, Value { taken: Some(2) });
+///
+/// branch::merge_branches(&mut branches, other);
+/// assert_eq!(branches[&key].taken, Some(2));
+/// ```
+pub fn merge_branches(branches: &mut Branches, other: Branches) {
+    branches.merge_lossy(other)
+}
+
+impl Excess for Value {
+    fn excess_over(&mut self, left: &Self) {
+        if let (Some(taken), Some(left)) = (self.taken, left.taken) {
+            self.taken = Some(taken.saturating_sub(left));
+        }
     }
+}
 
-    fn merge_lossy(&mut self, other: Self) {
+impl MergeEntry<Key> for Value {
+    fn merge_entry(
+        &mut self,
+        _key: &Key,
+        other: Self,
+        _resolver: &mut Resolver<'_>,
+    ) -> Result<(), ConflictKind> {
         if let Value { taken: Some(taken) } = other {
             self.taken = Some(self.taken.unwrap_or(0) + taken);
         }
+        Ok(())
     }
 }
 
-pub(crate) fn into_records(branches: Branches) -> Box<dyn Iterator<Item = Record>> {
-    if branches.is_empty() {
+pub(crate) fn into_records(
+    branches: Branches,
+    summary: SummaryRecords,
+) -> Box<dyn Iterator<Item = Record>> {
+    let emit_summary = summary.should_emit(branches.is_empty());
+    if branches.is_empty() && !emit_summary {
         return Box::new(iter::empty());
     }
 
@@ -64,8 +223,8 @@ pub(crate) fn into_records(branches: Branches) -> Box<dyn Iterator<Item = Record
     let iter = branches
         .into_iter()
         .map(Branch::Data)
-        .chain(iter::once(Branch::Found))
-        .chain(iter::once(Branch::Hit(0)))
+        .chain(iter::once(Branch::Found).filter(move |_| emit_summary))
+        .chain(iter::once(Branch::Hit(0)).filter(move |_| emit_summary))
         .scan(0, |hit_count, mut rec| {
             match rec {
                 Branch::Data((_, ref data)) => {
@@ -82,6 +241,7 @@ pub(crate) fn into_records(branches: Branches) -> Box<dyn Iterator<Item = Record
             Branch::Data((key, data)) => Record::BranchData {
                 line: key.line,
                 block: key.block,
+                exception: key.exception,
                 branch: key.branch,
                 taken: data.taken,
             },
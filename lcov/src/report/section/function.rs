@@ -3,9 +3,11 @@
 //! Some coverage information is stored in a [`Functions`] as `BTreeMap` .
 //!
 //! [`Functions`]: ./type.Functions.html
-use super::{Merge, MergeError, Record};
+use super::merge::{self, ConflictKind, Excess, Merge, MergeEntry, Resolver};
+use super::{Record, SummaryRecords};
 use std::collections::BTreeMap;
 use std::iter;
+use std::ops::RangeInclusive;
 
 /// A map of coverage information about functions.
 pub type Functions = BTreeMap<Key, Value>;
@@ -16,6 +18,7 @@ pub type Functions = BTreeMap<Key, Value>;
 ///
 /// [`Functions`]: ./type.Functions.html
 #[derive(Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Key {
     /// Function name.
     pub name: String,
@@ -26,38 +29,184 @@ pub struct Key {
 /// This struct is used as a value of [`Functions`].
 ///
 /// [`Functions`]: ./type.Functions.html
-#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Value {
     /// Line number of function start.
     pub start_line: Option<u32>,
+    /// Line number of function end.
+    pub end_line: Option<u32>,
     /// Execution count.
     pub count: u64,
 }
 
-impl Merge for Value {
-    fn merge(&mut self, other: Self) -> Result<(), MergeError> {
-        if let Some(start_line) = other.start_line.as_ref() {
-            if let Some(my_start_line) = self.start_line.as_ref() {
-                if start_line != my_start_line {
-                    return Err(MergeError::UnmatchedFunctionLine);
+/// Line ranges of functions, which attribute lines to functions.
+///
+/// A function spans from its start line to its end line if the end line is known (`FN` records of
+/// LCOV 2.x), or otherwise to the line before the start line of the next function.
+/// The last function without an end line spans to the end of the file.
+/// Functions without a start line are ignored.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::section::function::Regions;
+///
+/// let mut report = Report::new();
+/// let _ = report
+///     .section("", "src/main.rs")
+///     .add_function("foo", 1, 1)
+///     .add_function("bar", 10, 1)
+///     .add_line(3, 1)
+///     .add_line(12, 0);
+/// let (_, section) = report.sections.iter().next().unwrap();
+///
+/// let regions = Regions::new(&section.functions);
+/// assert_eq!(regions.owner(3).map(|key| &key.name[..]), Some("foo"));
+/// assert_eq!(regions.owner(12).map(|key| &key.name[..]), Some("bar"));
+/// assert_eq!(regions.lines_of("foo"), Some(1..=9));
+/// assert_eq!(regions.lines_of("bar"), Some(10..=u32::MAX));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Regions<'a> {
+    // Sorted by start lines.
+    regions: Vec<(RangeInclusive<u32>, &'a Key)>,
+}
+
+impl<'a> Regions<'a> {
+    /// Computes the line ranges of the functions.
+    pub fn new(functions: &'a Functions) -> Self {
+        let mut starts = functions
+            .iter()
+            .filter_map(|(key, value)| value.start_line.map(|start| (start, value.end_line, key)))
+            .collect::<Vec<_>>();
+        starts.sort();
+
+        let mut regions = Vec::with_capacity(starts.len());
+        let mut next_start = None::<u32>;
+        for &(start, end, key) in starts.iter().rev() {
+            let end = end.unwrap_or_else(|| match next_start {
+                Some(next_start) => u32::max(start, next_start.saturating_sub(1)),
+                None => u32::MAX,
+            });
+            regions.push((start..=end, key));
+            next_start = Some(start);
+        }
+        regions.reverse();
+        Regions { regions }
+    }
+
+    /// Returns the function which the line belongs to.
+    ///
+    /// If the line is in more than one function (e.g. a closure in a function), the function
+    /// which starts last is returned.
+    pub fn owner(&self, line: u32) -> Option<&'a Key> {
+        let end = self
+            .regions
+            .partition_point(|(range, _)| *range.start() <= line);
+        self.regions[..end]
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&line))
+            .map(|&(_, key)| key)
+    }
+
+    /// Returns the range of lines which the function belongs to.
+    pub fn lines_of(&self, name: &str) -> Option<RangeInclusive<u32>> {
+        self.regions
+            .iter()
+            .find(|(_, key)| key.name == name)
+            .map(|(range, _)| range.clone())
+    }
+
+    /// Returns an iterator over the functions and their ranges of lines, in the order of start
+    /// lines.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a Key, RangeInclusive<u32>)> + '_ {
+        self.regions
+            .iter()
+            .map(|(range, key)| (*key, range.clone()))
+    }
+}
+
+/// Merges functions of the same source file into `functions`.
+///
+/// Execution counts are summed up. Returns the first conflict of function start lines, in which
+/// case `functions` may be partially merged.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::report::section::function::{self, Functions, Key, Value};
+///
+/// let key = Key { name: "main".into() };
+/// let mut functions = Functions::new();
+/// let _ = functions.insert(key.clone(), Value { start_line: Some(1), end_line: None, count: 1 });
+/// let mut other = Functions::new();
+/// let _ = other.insert(key.clone(), Value { start_line: Some(1), end_line: None, count: 2 });
+///
+/// function::merge_functions(&mut functions, other).unwrap();
+/// assert_eq!(functions[&key].count, 3);
+/// ```
+pub fn merge_functions(functions: &mut Functions, other: Functions) -> Result<(), ConflictKind> {
+    Merge::merge(functions, other)
+}
+
+/// Merges functions of the same source file into `functions`, keeping the start lines of `other`
+/// on conflicts.
+///
+/// See [`merge_functions`] for an example.
+///
+/// [`merge_functions`]: fn.merge_functions.html
+pub fn merge_functions_lossy(functions: &mut Functions, other: Functions) {
+    functions.merge_lossy(other)
+}
+
+impl Excess for Value {
+    fn excess_over(&mut self, left: &Self) {
+        self.count = self.count.saturating_sub(left.count);
+    }
+}
+
+impl MergeEntry<Key> for Value {
+    fn merge_entry(
+        &mut self,
+        key: &Key,
+        mut other: Self,
+        resolver: &mut Resolver<'_>,
+    ) -> Result<(), ConflictKind> {
+        if let (Some(start_line), Some(other_start_line)) = (self.start_line, other.start_line) {
+            if start_line != other_start_line {
+                let kind = ConflictKind::FunctionLine {
+                    name: key.name.clone(),
+                    start_line,
+                    other_start_line,
+                };
+                if !merge::keeps_right(resolver, kind)? {
+                    other.start_line = None;
+                    other.end_line = None;
                 }
             }
         }
-        // Don't check end_line. The value may differ between tracefiles.
-        self.merge_lossy(other);
-        Ok(())
-    }
 
-    fn merge_lossy(&mut self, other: Self) {
+        // Don't check end_line. The value may differ between tracefiles.
         if other.start_line.is_some() {
             self.start_line = other.start_line;
         }
+        if other.end_line.is_some() {
+            self.end_line = other.end_line;
+        }
         self.count = u64::saturating_add(self.count, other.count);
+        Ok(())
     }
 }
 
-pub(crate) fn into_records(functions: Functions) -> Box<dyn Iterator<Item = Record>> {
-    if functions.is_empty() {
+pub(crate) fn into_records(
+    functions: Functions,
+    summary: SummaryRecords,
+) -> Box<dyn Iterator<Item = Record>> {
+    let emit_summary = summary.should_emit(functions.is_empty());
+    if functions.is_empty() && !emit_summary {
         return Box::new(iter::empty());
     }
 
@@ -66,22 +215,22 @@ pub(crate) fn into_records(functions: Functions) -> Box<dyn Iterator<Item = Reco
     functions.sort_by_key(|(_, data)| data.start_line);
 
     enum Func {
-        Line(String, u32),
+        Line(String, u32, Option<u32>),
         Data(String, u64),
         Found,
         Hit(u32),
     }
     let line = functions.clone().into_iter().filter_map(|(key, data)| {
         data.start_line
-            .map(|start_line| Func::Line(key.name, start_line))
+            .map(|start_line| Func::Line(key.name, start_line, data.end_line))
     });
     let count = functions
         .into_iter()
         .map(|(key, data)| Func::Data(key.name, data.count));
     let iter = line
         .chain(count)
-        .chain(iter::once(Func::Found))
-        .chain(iter::once(Func::Hit(0)))
+        .chain(iter::once(Func::Found).filter(move |_| emit_summary))
+        .chain(iter::once(Func::Hit(0)).filter(move |_| emit_summary))
         .scan(0, |hit_count, mut rec| {
             match rec {
                 Func::Data(_, ref mut count) if *count > 0 => *hit_count += 1,
@@ -91,7 +240,11 @@ pub(crate) fn into_records(functions: Functions) -> Box<dyn Iterator<Item = Reco
             Some(rec)
         })
         .map(move |rec| match rec {
-            Func::Line(name, start_line) => Record::FunctionName { name, start_line },
+            Func::Line(name, start_line, end_line) => Record::FunctionName {
+                name,
+                start_line,
+                end_line,
+            },
             Func::Data(name, count) => Record::FunctionData { name, count },
             Func::Found => Record::FunctionsFound { found },
             Func::Hit(hit) => Record::FunctionsHit { hit },
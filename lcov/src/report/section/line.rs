@@ -3,7 +3,8 @@
 //! Some coverage information is stored in a [`Lines`] as `BTreeMap` .
 //!
 //! [`Lines`]: ./type.Linesh.html
-use super::{Merge, MergeError, Record};
+use super::merge::{self, ConflictKind, Excess, Merge, MergeEntry, Resolver};
+use super::{Record, SummaryRecords};
 use std::collections::BTreeMap;
 use std::iter;
 
@@ -16,6 +17,7 @@ pub type Lines = BTreeMap<Key, Value>;
 ///
 /// [`Lines`]: ./type.Lines.html
 #[derive(Debug, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Key {
     /// Line number.
     pub line: u32,
@@ -26,7 +28,8 @@ pub struct Key {
 /// This struct is used as a value of [`Lines`].
 ///
 /// [`Lines`]: ./type.Lines.html
-#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Value {
     /// Execution count.
     pub count: u64,
@@ -34,30 +37,81 @@ pub struct Value {
     pub checksum: Option<String>,
 }
 
-impl Merge for Value {
-    fn merge(&mut self, other: Self) -> Result<(), MergeError> {
-        if let Some(checksum) = other.checksum.as_ref() {
-            if let Some(my_checksum) = self.checksum.as_ref() {
-                if checksum != my_checksum {
-                    return Err(MergeError::UnmatchedChecksum);
+/// Merges lines of the same source file into `lines`.
+///
+/// Execution counts are summed up. Returns the first conflict of line checksums, in which case
+/// `lines` may be partially merged.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::report::section::line::{self, Key, Lines, Value};
+///
+/// let mut lines = Lines::new();
+/// let _ = lines.insert(Key { line: 1 }, Value { count: 1, checksum: Some("a".into()) });
+/// let mut other = Lines::new();
+/// let _ = other.insert(Key { line: 1 }, Value { count: 1, checksum: Some("b".into()) });
+/// let _ = other.insert(Key { line: 2 }, Value { count: 0, checksum: None });
+///
+/// assert!(line::merge_lines(&mut lines.clone(), other.clone()).is_err());
+/// line::merge_lines_lossy(&mut lines, other);
+/// assert_eq!(lines[&Key { line: 1 }], Value { count: 2, checksum: Some("b".into()) });
+/// assert_eq!(lines.len(), 2);
+/// ```
+pub fn merge_lines(lines: &mut Lines, other: Lines) -> Result<(), ConflictKind> {
+    Merge::merge(lines, other)
+}
+
+/// Merges lines of the same source file into `lines`, keeping the checksums of `other` on
+/// conflicts.
+///
+/// See [`merge_lines`] for an example.
+///
+/// [`merge_lines`]: fn.merge_lines.html
+pub fn merge_lines_lossy(lines: &mut Lines, other: Lines) {
+    lines.merge_lossy(other)
+}
+
+impl Excess for Value {
+    fn excess_over(&mut self, left: &Self) {
+        self.count = self.count.saturating_sub(left.count);
+    }
+}
+
+impl MergeEntry<Key> for Value {
+    fn merge_entry(
+        &mut self,
+        key: &Key,
+        mut other: Self,
+        resolver: &mut Resolver<'_>,
+    ) -> Result<(), ConflictKind> {
+        if let (Some(checksum), Some(other_checksum)) = (&self.checksum, &other.checksum) {
+            if checksum != other_checksum {
+                let kind = ConflictKind::Checksum {
+                    line: key.line,
+                    checksum: checksum.clone(),
+                    other_checksum: other_checksum.clone(),
+                };
+                if !merge::keeps_right(resolver, kind)? {
+                    other.checksum = None;
                 }
             }
         }
 
-        self.merge_lossy(other);
-        Ok(())
-    }
-
-    fn merge_lossy(&mut self, other: Self) {
         if other.checksum.is_some() {
             self.checksum = other.checksum;
         }
         self.count += other.count;
+        Ok(())
     }
 }
 
-pub(crate) fn into_records(lines: Lines) -> Box<dyn Iterator<Item = Record>> {
-    if lines.is_empty() {
+pub(crate) fn into_records(
+    lines: Lines,
+    summary: SummaryRecords,
+) -> Box<dyn Iterator<Item = Record>> {
+    let emit_summary = summary.should_emit(lines.is_empty());
+    if lines.is_empty() && !emit_summary {
         return Box::new(iter::empty());
     }
 
@@ -70,8 +124,8 @@ pub(crate) fn into_records(lines: Lines) -> Box<dyn Iterator<Item = Record>> {
     let iter = lines
         .into_iter()
         .map(Line::Data)
-        .chain(iter::once(Line::Found))
-        .chain(iter::once(Line::Hit(0)))
+        .chain(iter::once(Line::Found).filter(move |_| emit_summary))
+        .chain(iter::once(Line::Hit(0)).filter(move |_| emit_summary))
         .scan(0, |hit_count, mut rec| {
             match rec {
                 Line::Data((_, ref data)) => {
@@ -2,28 +2,241 @@
 //!
 //! Some coverage information is stored in a [`Sections`] as `BTreeMap` .
 //!
-//! [`Sections`]: ./type.Sections.html
+//! [`Sections`]: ./struct.Sections.html
 use self::branch::Branches;
 use self::function::Functions;
 use self::line::Lines;
-use super::{Merge, MergeError, ParseError, Parser, ReadError, Record, RecordKind};
-use std::collections::BTreeMap;
+use super::merge::{self, ConflictKind, Excess, Merge, Resolution, Resolver};
+use super::summary::{self, Stats};
+use super::{
+    Layout, ParseError, ParseOptions, Parser, ReadError, Record, RecordKind, SummaryRecords,
+};
+use std::collections::{btree_map, BTreeMap, HashMap};
 use std::iter;
-use std::path::PathBuf;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 
 pub mod branch;
 pub mod function;
 pub mod line;
 
 /// A map of coverage information about source files.
-pub type Sections = BTreeMap<Key, Value>;
+///
+/// `Sections` dereferences to `BTreeMap<Key, Value>`, so all methods of `BTreeMap` are
+/// available.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("test1", "src/main.rs").add_line(1, 1);
+/// let _ = report.section("test2", "src/main.rs").add_line(2, 0);
+/// let _ = report.section("test1", "src/lib.rs").add_line(1, 0);
+///
+/// assert_eq!(report.sections.len(), 3);
+/// assert_eq!(report.sections.by_path("src/main.rs").count(), 2);
+/// assert_eq!(report.sections.total_stats().lines.hit, 1);
+///
+/// report.sections.retain_paths(|path| path.ends_with("lib.rs"));
+/// assert_eq!(report.sections.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "cache",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Sections {
+    map: BTreeMap<Key, Value>,
+}
+
+impl Sections {
+    /// Creates an empty `Sections`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the sections of the source file, one for each test.
+    ///
+    /// See the [type-level documentation](#examples) for an example.
+    pub fn by_path<P>(&self, source_file: P) -> impl Iterator<Item = (&Key, &Value)>
+    where
+        P: AsRef<Path>,
+    {
+        self.map
+            .iter()
+            .filter(move |(key, _)| key.source_file == source_file.as_ref())
+    }
+
+    /// Merges the sections of each source file across tests.
+    ///
+    /// Conflicting information is overwritten as [`Value::merge_lossy`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("unit", "src/lib.rs").add_line(1, 1);
+    /// let _ = report.section("integration", "src/lib.rs").add_line(1, 2);
+    /// let files = report.sections.merge_by_file();
+    /// assert_eq!(files[Path::new("src/lib.rs")].lines_hit(), 1);
+    /// assert_eq!(files[Path::new("src/lib.rs")].lines.values().next().unwrap().count, 3);
+    /// ```
+    ///
+    /// [`Value::merge_lossy`]: struct.Value.html#method.merge_lossy
+    pub fn merge_by_file(&self) -> BTreeMap<&Path, Value> {
+        let mut merged = BTreeMap::<&Path, Value>::new();
+        for (key, value) in &self.map {
+            merged
+                .entry(&key.source_file)
+                .or_default()
+                .merge_lossy(value.clone());
+        }
+        merged
+    }
+
+    /// Computes the numbers of found and hit functions, branches and lines of all sections.
+    ///
+    /// Sections of the same source file are merged before counting, as [`Summary`] does.
+    ///
+    /// [`Summary`]: ../summary/struct.Summary.html
+    pub fn total_stats(&self) -> Stats {
+        summary::total(self)
+    }
+
+    /// Retains only the sections whose source file satisfies the predicate.
+    pub fn retain_paths<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path) -> bool,
+    {
+        self.map.retain(|key, _| f(&key.source_file));
+    }
+
+    /// Converts into the underlying `BTreeMap`.
+    pub fn into_inner(self) -> BTreeMap<Key, Value> {
+        self.map
+    }
+}
+
+impl Deref for Sections {
+    type Target = BTreeMap<Key, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl DerefMut for Sections {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+
+impl From<BTreeMap<Key, Value>> for Sections {
+    fn from(map: BTreeMap<Key, Value>) -> Self {
+        Sections { map }
+    }
+}
+
+impl FromIterator<(Key, Value)> for Sections {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (Key, Value)>,
+    {
+        Sections {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(Key, Value)> for Sections {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = (Key, Value)>,
+    {
+        self.map.extend(iter)
+    }
+}
+
+impl IntoIterator for Sections {
+    type Item = (Key, Value);
+    type IntoIter = btree_map::IntoIter<Key, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Sections {
+    type Item = (&'a Key, &'a Value);
+    type IntoIter = btree_map::Iter<'a, Key, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Sections {
+    type Item = (&'a Key, &'a mut Value);
+    type IntoIter = btree_map::IterMut<'a, Key, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter_mut()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl rayon::iter::IntoParallelIterator for Sections {
+    type Item = (Key, Value);
+    type Iter = rayon::collections::btree_map::IntoIter<Key, Value>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.map.into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> rayon::iter::IntoParallelIterator for &'a Sections {
+    type Item = (&'a Key, &'a Value);
+    type Iter = rayon::collections::btree_map::Iter<'a, Key, Value>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        (&self.map).into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> rayon::iter::IntoParallelIterator for &'a mut Sections {
+    type Item = (&'a Key, &'a mut Value);
+    type Iter = rayon::collections::btree_map::IterMut<'a, Key, Value>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        (&mut self.map).into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl rayon::iter::ParallelExtend<(Key, Value)> for Sections {
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: rayon::iter::IntoParallelIterator<Item = (Key, Value)>,
+    {
+        self.map.par_extend(par_iter)
+    }
+}
 
 /// A key of a coverage information about a source file.
 ///
 /// This struct is used as a key of [`Sections`].
 ///
-/// [`Sections`]: ./type.Sections.html
+/// [`Sections`]: ./struct.Sections.html
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Key {
     /// Name of the test.
     pub test_name: String,
@@ -35,15 +248,25 @@ pub struct Key {
 ///
 /// This struct is  used as a value of [`Sections`].
 ///
-/// [`Sections`]: ./type.Sections.html
-#[derive(Debug, Clone, Default, Eq, PartialEq)]
+/// [`Sections`]: ./struct.Sections.html
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cache", derive(serde::Serialize, serde::Deserialize))]
 pub struct Value {
+    /// Revision of the source file (`VER` record).
+    pub version: Option<String>,
     /// Function coverage information in the section.
     pub functions: Functions,
     /// Branch coverage information in the section.
     pub branches: Branches,
     /// Line coverage information in the section.
     pub lines: Lines,
+    /// Records of unknown kinds in the section, kept by [`Reader::passthrough`].
+    ///
+    /// Records are output as they are before `end_of_record` record. Records between sections
+    /// belong to the following section.
+    ///
+    /// [`Reader::passthrough`]: ../../struct.Reader.html#method.passthrough
+    pub unknown_records: Vec<String>,
 }
 
 impl Value {
@@ -51,47 +274,576 @@ impl Value {
     pub fn is_empty(&self) -> bool {
         self.functions.is_empty() && self.branches.is_empty() && self.lines.is_empty()
     }
+
+    /// Merges the coverage information of the same source file into `self`.
+    ///
+    /// Execution counts are summed up. Returns the first conflict of function start lines, line
+    /// checksums or source file revisions, in which case `self` may be partially merged.
+    ///
+    /// This is useful for collectors merging coverage information at a finer granularity than
+    /// whole [`Report`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::ConflictKind;
+    /// use lcov::report::section::{line, Value};
+    ///
+    /// let mut value = Value::default();
+    /// let _ = value.lines.insert(line::Key { line: 1 }, line::Value { count: 1, checksum: None });
+    /// let mut other = Value::default();
+    /// let _ = other.lines.insert(line::Key { line: 1 }, line::Value { count: 2, checksum: None });
+    /// value.merge(other).unwrap();
+    /// assert_eq!(value.lines[&line::Key { line: 1 }].count, 3);
+    ///
+    /// let mut other = Value::default();
+    /// other.version = Some("1".into());
+    /// value.version = Some("2".into());
+    /// assert!(matches!(value.merge(other), Err(ConflictKind::Version { .. })));
+    /// ```
+    ///
+    /// [`Report`]: ../struct.Report.html
+    pub fn merge(&mut self, other: Self) -> Result<(), ConflictKind> {
+        self.merge_resolve(other, |_| Resolution::Error)
+    }
+
+    /// Merges the coverage information of the same source file into `self`, keeping the values
+    /// of `other` on conflicts.
+    pub fn merge_lossy(&mut self, other: Self) {
+        Merge::merge_lossy(self, other)
+    }
+
+    /// Merges the coverage information of the same source file into `self`, resolving conflicts
+    /// by `resolver`.
+    ///
+    /// See [`Report::merge_resolve`] for details.
+    ///
+    /// [`Report::merge_resolve`]: ../struct.Report.html#method.merge_resolve
+    pub fn merge_resolve<F>(&mut self, other: Self, mut resolver: F) -> Result<(), ConflictKind>
+    where
+        F: FnMut(&ConflictKind) -> Resolution,
+    {
+        self.merge_with(other, &mut resolver)
+    }
 }
 
-impl Merge for Value {
-    fn merge(&mut self, other: Self) -> Result<(), MergeError> {
-        self.functions.merge(other.functions)?;
-        self.branches.merge(other.branches)?;
-        self.lines.merge(other.lines)?;
-        Ok(())
+impl Excess for Value {
+    fn excess_over(&mut self, left: &Self) {
+        merge::excess_over(&mut self.functions, |key| left.functions.get(key));
+        merge::excess_over(&mut self.branches, |key| left.branches.get(key));
+        merge::excess_over(&mut self.lines, |key| left.lines.get(key));
+    }
+}
+
+impl Value {
+    /// Returns the number of functions found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_function("main", 1, 1).add_function("foo", 5, 0);
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.functions_found(), 2);
+    /// assert_eq!(section.functions_hit(), 1);
+    /// assert_eq!(section.function_rate(), Some(0.5));
+    /// ```
+    pub fn functions_found(&self) -> u32 {
+        self.functions.len() as u32
+    }
+
+    /// Returns the number of functions with a non-zero execution count.
+    pub fn functions_hit(&self) -> u32 {
+        self.functions.values().filter(|v| v.count > 0).count() as u32
+    }
+
+    /// Returns the ratio of functions hit to functions found.
+    ///
+    /// Returns `None` if no functions are found.
+    pub fn function_rate(&self) -> Option<f64> {
+        rate(self.functions_hit(), self.functions_found())
+    }
+
+    /// Returns the number of branches found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_branch(1, 0, 0, Some(1))
+    ///     .add_branch(1, 0, 1, Some(0))
+    ///     .add_branch(1, 0, 2, None)
+    ///     .add_branch(1, 0, 3, Some(2));
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.branches_found(), 4);
+    /// assert_eq!(section.branches_executed(), 3);
+    /// assert_eq!(section.branches_hit(), 2);
+    /// assert_eq!(section.branch_rate(), Some(0.5));
+    /// ```
+    pub fn branches_found(&self) -> u32 {
+        self.branches.len() as u32
+    }
+
+    /// Returns the number of branches whose block is executed, i.e. which are not `-`.
+    pub fn branches_executed(&self) -> u32 {
+        self.branches.values().filter(|v| v.is_executed()).count() as u32
+    }
+
+    /// Returns the number of branches taken at least once.
+    pub fn branches_hit(&self) -> u32 {
+        self.branches
+            .values()
+            .filter(|v| v.taken.unwrap_or(0) > 0)
+            .count() as u32
+    }
+
+    /// Returns the ratio of branches hit to branches found.
+    ///
+    /// Returns `None` if no branches are found.
+    pub fn branch_rate(&self) -> Option<f64> {
+        rate(self.branches_hit(), self.branches_found())
+    }
+
+    /// Returns the line ranges of the functions.
+    ///
+    /// See [`function::Regions`] for more.
+    ///
+    /// [`function::Regions`]: function/struct.Regions.html
+    pub fn function_regions(&self) -> function::Regions<'_> {
+        function::Regions::new(&self.functions)
+    }
+
+    /// Returns the branch coverage information of each line.
+    ///
+    /// See [`branch::by_line`] for more.
+    ///
+    /// [`branch::by_line`]: branch/fn.by_line.html
+    pub fn branches_by_line(&self) -> BTreeMap<u32, branch::LineBranches> {
+        branch::by_line(&self.branches)
+    }
+
+    /// Returns the number of instrumented lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(section.lines_found(), 2);
+    /// assert_eq!(section.lines_hit(), 1);
+    /// assert_eq!(section.line_rate(), Some(0.5));
+    /// ```
+    pub fn lines_found(&self) -> u32 {
+        self.lines.len() as u32
+    }
+
+    /// Returns the number of lines with a non-zero execution count.
+    pub fn lines_hit(&self) -> u32 {
+        self.lines.values().filter(|v| v.count > 0).count() as u32
+    }
+
+    /// Returns the ratio of lines hit to lines found.
+    ///
+    /// Returns `None` if no lines are found.
+    pub fn line_rate(&self) -> Option<f64> {
+        rate(self.lines_hit(), self.lines_found())
+    }
+
+    /// Clamps every execution count to 0 or 1, keeping only whether it is covered.
+    ///
+    /// Branches never evaluated (`taken` is `None`) are kept as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 5).add_line(2, 0);
+    /// let (_, section) = report.sections.iter_mut().next().unwrap();
+    /// section.binarize();
+    /// let counts = section.lines.values().map(|v| v.count).collect::<Vec<_>>();
+    /// assert_eq!(counts, [1, 0]);
+    /// ```
+    pub fn binarize(&mut self) {
+        for value in self.functions.values_mut() {
+            value.count = value.count.min(1);
+        }
+        for value in self.branches.values_mut() {
+            value.taken = value.taken.map(|taken| taken.min(1));
+        }
+        for value in self.lines.values_mut() {
+            value.count = value.count.min(1);
+        }
+    }
+
+    /// Resets every execution count to zero, keeping which lines, functions and branches exist.
+    ///
+    /// Branches are marked as never evaluated (`taken` is `None`), as `lcov --initial` does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report
+    ///     .section("", "src/main.rs")
+    ///     .add_branch(1, 0, 0, Some(3))
+    ///     .add_line(1, 5)
+    ///     .add_line(2, 0);
+    /// let (_, section) = report.sections.iter_mut().next().unwrap();
+    /// section.zero_counts();
+    /// let counts = section.lines.values().map(|v| v.count).collect::<Vec<_>>();
+    /// assert_eq!(counts, [0, 0]);
+    /// assert!(section.branches.values().all(|v| v.taken.is_none()));
+    /// ```
+    pub fn zero_counts(&mut self) {
+        for value in self.functions.values_mut() {
+            value.count = 0;
+        }
+        for value in self.branches.values_mut() {
+            value.taken = None;
+        }
+        for value in self.lines.values_mut() {
+            value.count = 0;
+        }
+    }
+
+    /// Multiplies every execution count by `factor`.
+    ///
+    /// Scaled counts are rounded to the nearest integer and saturate at `u64::MAX`.
+    /// Non-zero counts are kept at least 1, so that scaling does not change what is covered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is negative or NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1000).add_line(2, 3);
+    /// let (_, section) = report.sections.iter_mut().next().unwrap();
+    /// section.scale_counts(0.01);
+    /// let counts = section.lines.values().map(|v| v.count).collect::<Vec<_>>();
+    /// assert_eq!(counts, [10, 1]);
+    /// ```
+    pub fn scale_counts(&mut self, factor: f64) {
+        assert!(factor >= 0.0, "invalid scaling factor: {}", factor);
+        for value in self.functions.values_mut() {
+            value.count = scale(value.count, factor);
+        }
+        for value in self.branches.values_mut() {
+            value.taken = value.taken.map(|taken| scale(taken, factor));
+        }
+        for value in self.lines.values_mut() {
+            value.count = scale(value.count, factor);
+        }
+    }
+
+    /// Subtracts the execution counts of `baseline` from the ones of the same items in `self`.
+    ///
+    /// Counts saturate at zero. Items are kept even if their counts become zero, and items only
+    /// in `baseline` are ignored. A branch taken in `self` but never evaluated in `baseline` keeps
+    /// its count.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 5).add_line(2, 1);
+    /// let mut baseline = Report::new();
+    /// let _ = baseline.section("", "src/main.rs").add_line(1, 2).add_line(2, 3).add_line(3, 1);
+    ///
+    /// let (_, section) = report.sections.iter_mut().next().unwrap();
+    /// let (_, base) = baseline.sections.iter().next().unwrap();
+    /// section.subtract(base);
+    /// let counts = section.lines.values().map(|v| v.count).collect::<Vec<_>>();
+    /// assert_eq!(counts, [3, 0]);
+    /// ```
+    pub fn subtract(&mut self, baseline: &Value) {
+        for (key, value) in &mut self.functions {
+            if let Some(base) = baseline.functions.get(key) {
+                value.count = value.count.saturating_sub(base.count);
+            }
+        }
+        for (key, value) in &mut self.branches {
+            if let (Some(taken), Some(base)) = (
+                value.taken,
+                baseline.branches.get(key).and_then(|base| base.taken),
+            ) {
+                value.taken = Some(taken.saturating_sub(base));
+            }
+        }
+        for (key, value) in &mut self.lines {
+            if let Some(base) = baseline.lines.get(key) {
+                value.count = value.count.saturating_sub(base.count);
+            }
+        }
+    }
+
+    /// Adds `by` to the execution count of a line, adding the line if it does not exist.
+    ///
+    /// The count saturates at `u64::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::section::{line, Value};
+    ///
+    /// let mut section = Value::default();
+    /// section.increment_line(3, 1);
+    /// section.increment_line(3, 2);
+    /// assert_eq!(section.lines[&line::Key { line: 3 }].count, 3);
+    /// ```
+    pub fn increment_line(&mut self, line: u32, by: u64) {
+        let value = self.lines.entry(line::Key { line }).or_default();
+        value.count = value.count.saturating_add(by);
+    }
+
+    /// Adds `by` to the execution count of a function, adding the function if it does not exist.
+    ///
+    /// The start line of a function added by this method is unknown. The count saturates at
+    /// `u64::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::section::{function, Value};
+    ///
+    /// let mut section = Value::default();
+    /// section.increment_function("main", 1);
+    /// let key = function::Key { name: "main".into() };
+    /// assert_eq!(section.functions[&key].count, 1);
+    /// assert_eq!(section.functions[&key].start_line, None);
+    /// ```
+    pub fn increment_function<S>(&mut self, name: S, by: u64)
+    where
+        S: Into<String>,
+    {
+        let key = function::Key { name: name.into() };
+        let value = self.functions.entry(key).or_default();
+        value.count = value.count.saturating_add(by);
+    }
+
+    /// Sets how often a branch was taken, adding the branch if it does not exist.
+    ///
+    /// `None` means that the block containing the branch was never executed. Unlike
+    /// [`SectionBuilder::add_branch`], the existing number is overwritten.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::section::{branch, Value};
+    ///
+    /// let mut section = Value::default();
+    /// let key = branch::Key { line: 10, block: 0, exception: false, branch: 1 };
+    /// section.set_branch_taken(key, Some(4));
+    /// section.set_branch_taken(key, Some(2));
+    /// assert_eq!(section.branches[&key].taken, Some(2));
+    /// ```
+    ///
+    /// [`SectionBuilder::add_branch`]: ../struct.SectionBuilder.html#method.add_branch
+    pub fn set_branch_taken(&mut self, key: branch::Key, taken: Option<u64>) {
+        let _ = self.branches.insert(key, branch::Value { taken });
+    }
+
+    /// Adds the execution counts of lines, e.g. hit counts collected by a profiler.
+    ///
+    /// This is equivalent to calling [`increment_line`] for each pair of a line number and
+    /// a count, and lines may appear more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::section::{line, Value};
+    /// use std::collections::HashMap;
+    ///
+    /// let mut section = Value::default();
+    /// section.increment_line(1, 1);
+    ///
+    /// let hits = HashMap::from([(1, 2), (2, 0)]);
+    /// section.merge_line_map(hits);
+    /// let counts = section.lines.values().map(|v| v.count).collect::<Vec<_>>();
+    /// assert_eq!(counts, [3, 0]);
+    /// ```
+    ///
+    /// [`increment_line`]: #method.increment_line
+    pub fn merge_line_map<I>(&mut self, counts: I)
+    where
+        I: IntoIterator<Item = (u32, u64)>,
+    {
+        for (line, count) in counts {
+            self.increment_line(line, count);
+        }
+    }
+
+    /// Shrinks the capacity of the strings and vectors in the section as much as possible.
+    ///
+    /// The maps allocate their nodes on demand, so they have no spare capacity to shrink.
+    pub fn shrink_to_fit(&mut self) {
+        if let Some(version) = &mut self.version {
+            version.shrink_to_fit();
+        }
+        for value in self.lines.values_mut() {
+            if let Some(checksum) = &mut value.checksum {
+                checksum.shrink_to_fit();
+            }
+        }
+        for rec in &mut self.unknown_records {
+            rec.shrink_to_fit();
+        }
+        self.unknown_records.shrink_to_fit();
+    }
+
+    pub(crate) fn summary_of(&self, kind: RecordKind) -> u32 {
+        match kind {
+            RecordKind::FunctionsFound => self.functions_found(),
+            RecordKind::FunctionsHit => self.functions_hit(),
+            RecordKind::BranchesFound => self.branches_found(),
+            RecordKind::BranchesHit => self.branches_hit(),
+            RecordKind::LinesFound => self.lines_found(),
+            RecordKind::LinesHit => self.lines_hit(),
+            _ => unreachable!("{} is not a summary record", kind),
+        }
+    }
+}
+
+fn scale(count: u64, factor: f64) -> u64 {
+    if count == 0 || factor == 0.0 {
+        return 0;
     }
+    // `as` saturates on overflow.
+    ((count as f64 * factor).round() as u64).max(1)
+}
 
-    fn merge_lossy(&mut self, other: Self) {
-        self.functions.merge_lossy(other.functions);
-        self.branches.merge_lossy(other.branches);
-        self.lines.merge_lossy(other.lines);
+fn rate(hit: u32, found: u32) -> Option<f64> {
+    if found == 0 {
+        None
+    } else {
+        Some(f64::from(hit) / f64::from(found))
     }
 }
 
-pub(crate) fn parse<I>(parser: &mut Parser<I, Record>) -> Result<Sections, ParseError>
+impl Value {
+    /// Returns `true` if `self` and `other` have the same coverage information.
+    ///
+    /// Unlike `==`, line checksums, the revision of the source file and unknown records are
+    /// ignored, as they differ among generators of tracefiles with the same coverage.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::Report;
+    ///
+    /// let report1 = "TN:\nSF:foo.c\nVER:1\nDA:1,1,abc\nend_of_record\n".parse::<Report>()?;
+    /// let report2 = "TN:\nSF:foo.c\nDA:1,1\nend_of_record\n".parse::<Report>()?;
+    /// let (_, value1) = report1.sections.iter().next().unwrap();
+    /// let (_, value2) = report2.sections.iter().next().unwrap();
+    /// assert_ne!(value1, value2);
+    /// assert!(value1.semantic_eq(value2));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        self.functions == other.functions
+            && self.branches == other.branches
+            && self.lines.len() == other.lines.len()
+            && self.lines.iter().zip(&other.lines).all(
+                |((key, value), (other_key, other_value))| {
+                    key == other_key && value.count == other_value.count
+                },
+            )
+    }
+}
+
+impl Merge for Value {
+    fn merge_with(&mut self, other: Self, resolver: &mut Resolver<'_>) -> Result<(), ConflictKind> {
+        merge::merge_version(&mut self.version, other.version, resolver)?;
+        self.functions.merge_with(other.functions, resolver)?;
+        self.branches.merge_with(other.branches, resolver)?;
+        self.lines.merge_with(other.lines, resolver)?;
+        merge::merge_unknown_records(&mut self.unknown_records, other.unknown_records);
+        Ok(())
+    }
+}
+
+pub(crate) fn parse<I>(
+    parser: &mut Parser<I, Record>,
+    options: &ParseOptions,
+    mut layout: Option<&mut Layout>,
+) -> Result<Sections, ParseError>
 where
     I: Iterator<Item = Result<Record, ReadError>>,
 {
     let mut sections = Sections::new();
 
     while parser.peek().map_err(ParseError::Read)?.is_some() {
+        if options.is_cancelled() {
+            return Err(ParseError::Cancelled);
+        }
         // Sometimes, lcov emits TN: records multiple times, so skip the first TN: record.
         let mut test_name = None;
-        while let Some(tn) = eat_if_matches!(parser, Record::TestName { name } => name) {
-            test_name = Some(tn);
+        let mut unknown_records = vec![];
+        let mut leading = vec![];
+        while let Some(rec) = eat_if_matches!(
+            parser,
+            rec @ (Record::TestName { .. } | Record::Comment { .. } | Record::Unknown { .. }) => rec
+        ) {
+            match rec {
+                Record::TestName { ref name } => test_name = Some(name.clone()),
+                Record::Unknown { ref text } => unknown_records.push(text.clone()),
+                _ => {}
+            }
+            leading.push(rec);
         }
         // Sometimes, lcov emit extra TN: records at the end of the tracefile.
         if parser.peek().map_err(ParseError::Read)?.is_none() {
+            if let Some(layout) = layout.as_mut() {
+                for rec in leading {
+                    layout.push_trailing(rec);
+                }
+            }
             break;
         }
+        if let Some(layout) = layout.as_mut() {
+            for rec in &leading {
+                layout.push(rec);
+            }
+        }
 
         let mut source_file = None;
+        let mut version = None;
         let mut functions = Functions::default();
         let mut branches = Branches::default();
         let mut lines = Lines::default();
+        let mut summary = vec![];
+        let mut leaders = HashMap::new();
 
         loop {
-            match parser.pop()?.ok_or(ParseError::UnexpectedEof)? {
+            let rec = parser.pop()?.ok_or(ParseError::UnexpectedEof)?;
+            if let Some(layout) = layout.as_mut() {
+                layout.push(&rec);
+            }
+            match rec {
                 rec @ Record::TestName { .. } => {
                     return Err(ParseError::UnexpectedRecord {
                         expected: RecordKind::EndOfRecord,
@@ -99,11 +851,17 @@ where
                     })
                 }
                 Record::SourceFile { path } => source_file = Some(path),
-                Record::FunctionName { name, start_line } => {
+                Record::Version { version: v } => version = Some(v),
+                Record::FunctionName {
+                    name,
+                    start_line,
+                    end_line,
+                } => {
                     let _ = functions.insert(
                         function::Key { name },
                         function::Value {
                             start_line: Some(start_line),
+                            end_line,
                             count: 0,
                         },
                     );
@@ -112,11 +870,29 @@ where
                     let data = functions.entry(function::Key { name }).or_default();
                     data.count += count;
                 }
-                Record::FunctionsFound { .. } => {} // ignore
-                Record::FunctionsHit { .. } => {}   // ignore
+                Record::FunctionLeader {
+                    index,
+                    start_line,
+                    end_line,
+                } => {
+                    let _ = leaders.insert(index, (start_line, end_line));
+                }
+                Record::FunctionAlias { index, count, name } => {
+                    let data = functions.entry(function::Key { name }).or_default();
+                    if let Some(&(start_line, end_line)) = leaders.get(&index) {
+                        data.start_line = Some(start_line);
+                        data.end_line = end_line;
+                    }
+                    data.count = data.count.saturating_add(count);
+                }
+                Record::FunctionsFound { found } => {
+                    summary.push((RecordKind::FunctionsFound, found))
+                }
+                Record::FunctionsHit { hit } => summary.push((RecordKind::FunctionsHit, hit)),
                 Record::BranchData {
                     line,
                     block,
+                    exception,
                     branch,
                     taken,
                 } => {
@@ -124,23 +900,27 @@ where
                         branch::Key {
                             line,
                             block,
+                            exception,
                             branch,
                         },
                         branch::Value { taken },
                     );
                 }
-                Record::BranchesFound { .. } => {} // ignore
-                Record::BranchesHit { .. } => {}   // ignore
+                Record::BranchesFound { found } => summary.push((RecordKind::BranchesFound, found)),
+                Record::BranchesHit { hit } => summary.push((RecordKind::BranchesHit, hit)),
                 Record::LineData {
                     line,
                     count,
                     checksum,
                 } => {
+                    let checksum = checksum.filter(|_| !options.ignore_checksums);
                     let _ = lines.insert(line::Key { line }, line::Value { count, checksum });
                 }
-                Record::LinesFound { .. } => {} // ignore
-                Record::LinesHit { .. } => {}   // ignore
+                Record::LinesFound { found } => summary.push((RecordKind::LinesFound, found)),
+                Record::LinesHit { hit } => summary.push((RecordKind::LinesHit, hit)),
                 Record::EndOfRecord => break,
+                Record::Comment { .. } => {}
+                Record::Unknown { text } => unknown_records.push(text),
             }
         }
 
@@ -149,12 +929,30 @@ where
             source_file: source_file.unwrap_or_default(),
         };
         let value = Value {
+            version,
             functions,
             branches,
             lines,
+            unknown_records,
         };
+        if options.strict {
+            for (kind, found) in summary {
+                let expected = value.summary_of(kind);
+                if found != expected {
+                    return Err(ParseError::SummaryMismatch {
+                        key,
+                        kind,
+                        found,
+                        expected,
+                    });
+                }
+            }
+        }
         // If the new section contains no data, ignore it.
         // LCOV merge (`lcov -c -a XXX`) behaves the same way.
+        if let Some(layout) = layout.as_mut() {
+            layout.finish_section(&key, !value.is_empty());
+        }
         if !value.is_empty() {
             let _ = sections.insert(key, value);
         }
@@ -163,19 +961,30 @@ where
     Ok(sections)
 }
 
-pub(crate) fn into_records(sections: Sections) -> Box<dyn Iterator<Item = Record>> {
-    let iter = sections.into_iter().flat_map(|(key, value)| {
+pub(crate) fn into_records(
+    sections: Sections,
+    summary: SummaryRecords,
+) -> Box<dyn Iterator<Item = Record>> {
+    let iter = sections.into_iter().flat_map(move |(key, value)| {
         let test_name = Record::TestName {
             name: key.test_name,
         };
         let source_file = Record::SourceFile {
             path: key.source_file,
         };
+        let version = value.version.map(|version| Record::Version { version });
         iter::once(test_name)
             .chain(iter::once(source_file))
-            .chain(function::into_records(value.functions))
-            .chain(branch::into_records(value.branches))
-            .chain(line::into_records(value.lines))
+            .chain(version)
+            .chain(function::into_records(value.functions, summary))
+            .chain(branch::into_records(value.branches, summary))
+            .chain(line::into_records(value.lines, summary))
+            .chain(
+                value
+                    .unknown_records
+                    .into_iter()
+                    .map(|text| Record::Unknown { text }),
+            )
             .chain(iter::once(Record::EndOfRecord))
     });
     Box::new(iter)
@@ -0,0 +1,203 @@
+use super::{Counter, Metric, SortBy, Stats, Summary};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+use std::path::Path;
+
+/// A formatter which renders a [`Summary`] as a JSON object, for consumption by scripts.
+///
+/// This `struct` is created by the [`json`] method on [`Summary`].
+/// The object has the numbers of the whole report (`total`), of each source file (`files`), and
+/// the coverage rates below the thresholds given by [`violations`] (`violations`). Each category has the numbers of found
+/// and hit items, and the coverage rate in percent, which is `null` if no item is found.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::summary::Thresholds;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+///
+/// let summary = report.summary();
+/// let violations = summary.check(&Thresholds::new().lines(75.0));
+/// let json = summary.json().violations(&violations).to_string();
+/// assert_eq!(json, concat!(
+///     r#"{"total":{"lines":{"found":2,"hit":1,"rate":50.0},"#,
+///     r#""functions":{"found":0,"hit":0,"rate":null},"#,
+///     r#""branches":{"found":0,"hit":0,"rate":null}},"#,
+///     r#""files":[{"path":"src/main.rs","lines":{"found":2,"hit":1,"rate":50.0},"#,
+///     r#""functions":{"found":0,"hit":0,"rate":null},"#,
+///     r#""branches":{"found":0,"hit":0,"rate":null}}],"#,
+///     r#""violations":[{"path":null,"metric":"lines","actual":50.0,"minimum":75.0}]}"#,
+///     "\n",
+/// ));
+/// ```
+///
+/// [`Summary`]: struct.Summary.html
+/// [`json`]: struct.Summary.html#method.json
+/// [`violations`]: #method.violations
+#[derive(Debug, Clone, Copy)]
+pub struct Json<'a> {
+    summary: &'a Summary,
+    sort: SortBy,
+    reverse: bool,
+    limit: Option<usize>,
+    violations: &'a [super::Violation],
+}
+
+impl<'a> Json<'a> {
+    pub(super) fn new(summary: &'a Summary) -> Self {
+        Json {
+            summary,
+            sort: SortBy::default(),
+            reverse: false,
+            limit: None,
+            violations: &[],
+        }
+    }
+
+    /// Specifies the order of source files.
+    ///
+    /// Source files are sorted by path by default.
+    pub fn sort_by(mut self, sort: SortBy) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Specifies whether the order of source files is reversed.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Specifies the maximum number of source files in `files`.
+    ///
+    /// All source files are output by default. `total` still counts all source files.
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Specifies the violations of thresholds output in `violations`.
+    ///
+    /// The violations are usually returned by [`Summary::check`] of the summary which decides the
+    /// outcome of a coverage gate, which may differ from the rendered one (e.g. a rollup by
+    /// directory). No violations are output by default.
+    ///
+    /// [`Summary::check`]: struct.Summary.html#method.check
+    pub fn violations(mut self, violations: &'a [super::Violation]) -> Self {
+        self.violations = violations;
+        self
+    }
+}
+
+impl fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut files = self.summary.sorted_files(self.sort);
+        if self.reverse {
+            files.reverse();
+        }
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let object = Object {
+            total: (&self.summary.total).into(),
+            files: files
+                .into_iter()
+                .take(limit)
+                .map(|(path, stats)| File {
+                    path: path.to_string_lossy(),
+                    stats: stats.into(),
+                })
+                .collect(),
+            violations: self
+                .violations
+                .iter()
+                .map(|violation| Violation {
+                    path: violation.path.as_deref().map(Path::to_string_lossy),
+                    metric: match violation.metric {
+                        Metric::Lines => "lines",
+                        Metric::Branches => "branches",
+                        Metric::Functions => "functions",
+                    },
+                    actual: violation.actual,
+                    minimum: violation.minimum,
+                })
+                .collect(),
+        };
+        let json = serde_json::to_string(&object).map_err(|_| fmt::Error)?;
+        writeln!(f, "{}", json)
+    }
+}
+
+#[derive(Serialize)]
+struct Object<'a> {
+    total: Categories,
+    files: Vec<File<'a>>,
+    violations: Vec<Violation<'a>>,
+}
+
+#[derive(Serialize)]
+struct File<'a> {
+    path: Cow<'a, str>,
+    // Inlines the categories into the object of the file.
+    #[serde(flatten)]
+    stats: Categories,
+}
+
+#[derive(Serialize)]
+struct Categories {
+    lines: Rate,
+    functions: Rate,
+    branches: Rate,
+}
+
+#[derive(Serialize)]
+struct Rate {
+    found: u32,
+    hit: u32,
+    rate: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Violation<'a> {
+    path: Option<Cow<'a, str>>,
+    metric: &'static str,
+    actual: f64,
+    minimum: f64,
+}
+
+impl From<&Stats> for Categories {
+    fn from(stats: &Stats) -> Self {
+        Categories {
+            lines: stats.lines.into(),
+            functions: stats.functions.into(),
+            branches: stats.branches.into(),
+        }
+    }
+}
+
+impl From<Counter> for Rate {
+    fn from(counter: Counter) -> Self {
+        Rate {
+            found: counter.found,
+            hit: counter.hit,
+            rate: counter.rate().map(|rate| rate * 100.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Report;
+
+    #[test]
+    fn escape() {
+        let mut report = Report::new();
+        let _ = report
+            .section("", "dir\\\"quoted\"\n\u{1}.rs")
+            .add_line(1, 1);
+        let json = report.summary().json().to_string();
+        assert!(json.contains(r#""path":"dir\\\"quoted\"\n\u0001.rs""#));
+    }
+}
@@ -0,0 +1,107 @@
+use super::{SortBy, Summary};
+use std::fmt;
+
+/// A formatter which renders a [`Summary`] as a GitHub-flavored Markdown table.
+///
+/// This `struct` is created by the [`markdown`] method on [`Summary`].
+/// The table has a row for the whole report followed by rows for each source file.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::summary::SortBy;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/lib.rs").add_line(1, 1).add_function("foo", 1, 1);
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+///
+/// let summary = report.summary();
+/// let table = summary.markdown().sort_by(SortBy::LineRate).limit(Some(1)).to_string();
+/// assert_eq!(table, "\
+/// | File | Lines | Functions | Branches |
+/// | :--- | ---: | ---: | ---: |
+/// | **Total** | 66.67% (2/3) | 100.00% (1/1) | - |
+/// | src/main.rs | 50.00% (1/2) | - | - |
+/// | _1 more file_ | | | |
+/// ");
+/// ```
+///
+/// [`Summary`]: struct.Summary.html
+/// [`markdown`]: struct.Summary.html#method.markdown
+#[derive(Debug, Clone, Copy)]
+pub struct Markdown<'a> {
+    summary: &'a Summary,
+    sort: SortBy,
+    limit: Option<usize>,
+}
+
+impl<'a> Markdown<'a> {
+    pub(super) fn new(summary: &'a Summary) -> Self {
+        Markdown {
+            summary,
+            sort: SortBy::default(),
+            limit: None,
+        }
+    }
+
+    /// Specifies the order of source files.
+    ///
+    /// Source files are sorted by path by default.
+    pub fn sort_by(mut self, sort: SortBy) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Specifies the maximum number of source files in the table.
+    ///
+    /// All source files are output by default.
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl fmt::Display for Markdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "| File | Lines | Functions | Branches |")?;
+        writeln!(f, "| :--- | ---: | ---: | ---: |")?;
+        let total = &self.summary.total;
+        writeln!(
+            f,
+            "| **Total** | {} | {} | {} |",
+            total.lines, total.functions, total.branches
+        )?;
+
+        let files = self.summary.sorted_files(self.sort);
+        let limit = self.limit.unwrap_or(files.len()).min(files.len());
+        for (path, stats) in &files[..limit] {
+            writeln!(
+                f,
+                "| {} | {} | {} | {} |",
+                escape(&path.display().to_string()),
+                stats.lines,
+                stats.functions,
+                stats.branches
+            )?;
+        }
+        let rest = files.len() - limit;
+        if rest > 0 {
+            let s = if rest == 1 { "" } else { "s" };
+            writeln!(f, "| _{} more file{}_ | | | |", rest, s)?;
+        }
+        Ok(())
+    }
+}
+
+// Escapes characters which have special meanings in a Markdown table.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '|' | '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
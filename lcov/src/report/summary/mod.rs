@@ -0,0 +1,596 @@
+//! A summary of coverage information.
+//!
+//! The [`Summary`] structure contains the numbers of found and hit functions, branches and lines
+//! of every source file and of the whole report.
+//!
+//! [`Summary`]: struct.Summary.html
+#[cfg(feature = "json")]
+pub use self::json::Json;
+pub use self::markdown::Markdown;
+pub use self::text::Text;
+pub use self::threshold::{Metric, Thresholds, Violation};
+pub use self::trend::{Delta, Trend};
+use super::section::{self, Sections};
+use crate::filter::line_num::{LineNum, Range};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::{Add, AddAssign, RangeInclusive};
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(feature = "json")]
+mod json;
+mod markdown;
+mod text;
+mod threshold;
+mod trend;
+
+/// The numbers of found and hit items.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Counter {
+    /// Number of items found.
+    pub found: u32,
+    /// Number of items hit.
+    pub hit: u32,
+}
+
+impl Counter {
+    /// Creates a new counter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::summary::Counter;
+    /// let counter = Counter::new(10, 8);
+    /// assert_eq!(counter.found, 10);
+    /// assert_eq!(counter.hit, 8);
+    /// ```
+    pub fn new(found: u32, hit: u32) -> Self {
+        Counter { found, hit }
+    }
+
+    /// Returns the ratio of hit items to found items.
+    ///
+    /// Returns `None` if no items are found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::summary::Counter;
+    /// assert_eq!(Counter::new(10, 8).rate(), Some(0.8));
+    /// assert_eq!(Counter::new(0, 0).rate(), None);
+    /// ```
+    pub fn rate(&self) -> Option<f64> {
+        if self.found == 0 {
+            None
+        } else {
+            Some(f64::from(self.hit) / f64::from(self.found))
+        }
+    }
+
+    /// Returns the number of items not hit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::summary::Counter;
+    /// assert_eq!(Counter::new(10, 8).missed(), 2);
+    /// ```
+    pub fn missed(&self) -> u32 {
+        self.found.saturating_sub(self.hit)
+    }
+}
+
+impl Add for Counter {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Counter {
+    fn add_assign(&mut self, other: Self) {
+        self.found = self.found.saturating_add(other.found);
+        self.hit = self.hit.saturating_add(other.hit);
+    }
+}
+
+/// Formats the counter as the percentage with the numbers, or `-` if no items are found.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::report::summary::Counter;
+/// assert_eq!(Counter::new(3, 2).to_string(), "66.67% (2/3)");
+/// assert_eq!(Counter::new(0, 0).to_string(), "-");
+/// ```
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.rate() {
+            Some(rate) => write!(f, "{:.2}% ({}/{})", rate * 100.0, self.hit, self.found),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// The numbers of found and hit functions, branches and lines.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Stats {
+    /// Numbers of functions.
+    pub functions: Counter,
+    /// Numbers of branches.
+    pub branches: Counter,
+    /// Numbers of lines.
+    pub lines: Counter,
+}
+
+impl Stats {
+    /// Computes the numbers of a section.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::summary::Stats;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    /// let (_, section) = report.sections.iter().next().unwrap();
+    /// assert_eq!(Stats::of(section).lines.hit, 1);
+    /// ```
+    pub fn of(value: &section::Value) -> Self {
+        Stats {
+            functions: Counter::new(value.functions_found(), value.functions_hit()),
+            branches: Counter::new(value.branches_found(), value.branches_hit()),
+            lines: Counter::new(value.lines_found(), value.lines_hit()),
+        }
+    }
+
+    /// Returns the numbers of the coverage category.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::summary::{Counter, Metric, Stats};
+    ///
+    /// let stats = Stats { lines: Counter::new(2, 1), ..Stats::default() };
+    /// assert_eq!(stats.counter(Metric::Lines), Counter::new(2, 1));
+    /// assert_eq!(stats.counter(Metric::Branches), Counter::new(0, 0));
+    /// ```
+    pub fn counter(&self, metric: Metric) -> Counter {
+        match metric {
+            Metric::Lines => self.lines,
+            Metric::Branches => self.branches,
+            Metric::Functions => self.functions,
+        }
+    }
+}
+
+impl Add for Stats {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.functions += other.functions;
+        self.branches += other.branches;
+        self.lines += other.lines;
+    }
+}
+
+/// A summary of coverage information of a report.
+///
+/// Sections of the same source file are merged before counting, so a line is counted as hit if
+/// it is executed by any test.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("test1", "src/main.rs").add_line(1, 1).add_line(2, 0);
+/// let _ = report.section("test2", "src/main.rs").add_line(2, 1);
+/// let _ = report.section("test1", "src/lib.rs").add_line(1, 0);
+///
+/// let summary = report.summary();
+/// assert_eq!(summary.files.len(), 2);
+/// assert_eq!(summary.total.lines.found, 3);
+/// assert_eq!(summary.total.lines.hit, 2);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Summary {
+    /// Numbers of the whole report.
+    pub total: Stats,
+    /// Numbers of each source file.
+    pub files: BTreeMap<PathBuf, Stats>,
+}
+
+impl Summary {
+    /// Computes the summary of sections.
+    pub fn new(sections: &Sections) -> Self {
+        let mut summary = Summary::default();
+        for (path, value) in sections.merge_by_file() {
+            let stats = Stats::of(&value);
+            summary.total += stats;
+            let _ = summary.files.insert(path.into(), stats);
+        }
+        summary
+    }
+
+    /// Returns the source files and their numbers in the specified order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::summary::SortBy;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "a.rs").add_line(1, 1);
+    /// let _ = report.section("", "b.rs").add_line(1, 0);
+    ///
+    /// let summary = report.summary();
+    /// let files = summary.sorted_files(SortBy::LineRate);
+    /// assert_eq!(files[0].0, Path::new("b.rs"));
+    /// ```
+    pub fn sorted_files(&self, sort: SortBy) -> Vec<(&Path, &Stats)> {
+        let mut files = self
+            .files
+            .iter()
+            .map(|(path, stats)| (path.as_path(), stats))
+            .collect::<Vec<_>>();
+        // `sort_by` is stable, so files with the same value are sorted by path.
+        files.sort_by(|(_, a), (_, b)| sort.compare(a, b));
+        files
+    }
+
+    /// Aggregates the numbers of source files into their directories.
+    ///
+    /// Directories are truncated to the first `depth` components if specified, like the directory
+    /// index of genhtml with a limited depth. Source files at the top level are aggregated into
+    /// `.`. The total is left as it is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use std::path::Path;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/lib.rs").add_line(1, 1);
+    /// let _ = report.section("", "src/report/mod.rs").add_line(1, 0);
+    /// let _ = report.section("", "build.rs").add_line(1, 1);
+    ///
+    /// let summary = report.summary();
+    /// let dirs = summary.by_directory(None);
+    /// assert_eq!(dirs.files.len(), 3);
+    /// assert_eq!(dirs.files[Path::new("src/report")].lines.hit, 0);
+    ///
+    /// let dirs = summary.by_directory(Some(1));
+    /// assert_eq!(dirs.files.len(), 2);
+    /// assert_eq!(dirs.files[Path::new(".")].lines.found, 1);
+    /// assert_eq!(dirs.files[Path::new("src")].lines.found, 2);
+    /// ```
+    pub fn by_directory(&self, depth: Option<usize>) -> Summary {
+        let mut files = BTreeMap::<PathBuf, Stats>::new();
+        for (path, stats) in &self.files {
+            *files.entry(directory(path, depth)).or_default() += *stats;
+        }
+        Summary {
+            total: self.total,
+            files,
+        }
+    }
+
+    /// Returns the coverage rates below the minimum specified by `thresholds`.
+    ///
+    /// See [`Thresholds`] for more.
+    ///
+    /// [`Thresholds`]: struct.Thresholds.html
+    pub fn check(&self, thresholds: &Thresholds) -> Vec<Violation> {
+        threshold::check(self, thresholds)
+    }
+
+    /// Returns a formatter which renders the summary as a plain text table.
+    ///
+    /// See [`Text`] for more.
+    ///
+    /// [`Text`]: struct.Text.html
+    pub fn text(&self) -> Text<'_> {
+        Text::new(self)
+    }
+
+    /// Returns a formatter which renders the summary as a GitHub-flavored Markdown table.
+    ///
+    /// See [`Markdown`] for more.
+    ///
+    /// [`Markdown`]: struct.Markdown.html
+    pub fn markdown(&self) -> Markdown<'_> {
+        Markdown::new(self)
+    }
+
+    /// Returns a formatter which renders the summary as a JSON object.
+    ///
+    /// See [`Json`] for more.
+    ///
+    /// [`Json`]: struct.Json.html
+    #[cfg(feature = "json")]
+    pub fn json(&self) -> Json<'_> {
+        Json::new(self)
+    }
+}
+
+/// Coverage information of a function, including the lines and branches attributed to it.
+///
+/// Lines are attributed to functions as [`function::Regions`] does.
+/// This struct is created by [`Report::function_coverage`]. See its documentation for more.
+///
+/// [`function::Regions`]: ../section/function/struct.Regions.html
+/// [`Report::function_coverage`]: ../struct.Report.html#method.function_coverage
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FunctionCoverage {
+    /// Path of the source file.
+    pub source_file: PathBuf,
+    /// Function name.
+    pub name: String,
+    /// Range of lines which the function spans. `None` if the start line is unknown.
+    pub span: Option<RangeInclusive<u32>>,
+    /// Execution count of the function.
+    pub count: u64,
+    /// Numbers of lines attributed to the function.
+    pub lines: Counter,
+    /// Numbers of branches on the lines attributed to the function.
+    pub branches: Counter,
+}
+
+pub(crate) fn function_coverage(sections: &Sections) -> Vec<FunctionCoverage> {
+    let mut functions = vec![];
+    for (path, value) in sections.merge_by_file() {
+        let regions = value.function_regions();
+        let mut counters = BTreeMap::<&str, (Counter, Counter)>::new();
+        for (key, line) in &value.lines {
+            if let Some(owner) = regions.owner(key.line) {
+                let lines = &mut counters.entry(&owner.name).or_default().0;
+                *lines += Counter::new(1, u32::from(line.count > 0));
+            }
+        }
+        for (key, branch) in &value.branches {
+            if let Some(owner) = regions.owner(key.line) {
+                let branches = &mut counters.entry(&owner.name).or_default().1;
+                *branches += Counter::new(1, u32::from(branch.taken.unwrap_or(0) > 0));
+            }
+        }
+
+        let located = regions.iter().map(|(key, span)| (key, Some(span)));
+        let unlocated = value
+            .functions
+            .iter()
+            .filter(|(_, fvalue)| fvalue.start_line.is_none())
+            .map(|(key, _)| (key, None));
+        for (key, span) in located.chain(unlocated) {
+            let (lines, branches) = counters.get(&key.name[..]).copied().unwrap_or_default();
+            functions.push(FunctionCoverage {
+                source_file: path.into(),
+                name: key.name.clone(),
+                span,
+                count: value.functions[key].count,
+                lines,
+                branches,
+            });
+        }
+    }
+    functions
+}
+
+/// A function not executed by any test.
+///
+/// This struct is created by [`Report::uncovered_functions`]. See its documentation for more.
+///
+/// [`Report::uncovered_functions`]: ../struct.Report.html#method.uncovered_functions
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct UncoveredFunction {
+    /// Path of the source file.
+    pub source_file: PathBuf,
+    /// Function name.
+    pub name: String,
+    /// Line number where the function starts. `None` if unknown.
+    pub start_line: Option<u32>,
+}
+
+pub(crate) fn uncovered_functions(sections: &Sections) -> Vec<UncoveredFunction> {
+    let mut functions = vec![];
+    for (path, value) in sections.merge_by_file() {
+        let start = functions.len();
+        functions.extend(
+            value
+                .functions
+                .into_iter()
+                .filter(|(_, value)| value.count == 0)
+                .map(|(key, value)| UncoveredFunction {
+                    source_file: path.into(),
+                    name: key.name,
+                    start_line: value.start_line,
+                }),
+        );
+        // Functions with unknown start lines come last, as in `function_coverage`.
+        functions[start..].sort_by(|a, b| {
+            (a.start_line.is_none(), a.start_line, &a.name).cmp(&(
+                b.start_line.is_none(),
+                b.start_line,
+                &b.name,
+            ))
+        });
+    }
+    functions
+}
+
+pub(crate) fn uncovered_lines(sections: &Sections, gap: u32) -> BTreeMap<PathBuf, LineNum> {
+    let mut files = BTreeMap::new();
+    for (path, value) in sections.merge_by_file() {
+        // Uncovered lines are joined across at most `gap` lines without `DA` records, but never
+        // across a covered line.
+        let mut lines = LineNum::new();
+        let mut cur = None::<(u32, u32)>;
+        for (key, value) in &value.lines {
+            cur = match cur {
+                Some((start, end)) if value.count > 0 => {
+                    lines.insert(Range::new(start, end));
+                    None
+                }
+                _ if value.count > 0 => None,
+                Some((start, end))
+                    if u64::from(key.line) <= u64::from(end) + u64::from(gap) + 1 =>
+                {
+                    Some((start, key.line))
+                }
+                Some((start, end)) => {
+                    lines.insert(Range::new(start, end));
+                    Some((key.line, key.line))
+                }
+                None => Some((key.line, key.line)),
+            };
+        }
+        if let Some((start, end)) = cur {
+            lines.insert(Range::new(start, end));
+        }
+        if !lines.is_empty() {
+            let _ = files.insert(path.into(), lines);
+        }
+    }
+    files
+}
+
+/// Coverage of changed lines, such as the lines added or modified by a pull request.
+///
+/// This struct is created by [`Report::diff_coverage`]. See its documentation for more.
+///
+/// [`Report::diff_coverage`]: ../struct.Report.html#method.diff_coverage
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DiffCoverage {
+    /// Numbers of changed lines which are instrumented and hit, of all source files.
+    pub total: Counter,
+    /// Numbers of changed lines which are instrumented and hit, of each source file.
+    pub files: BTreeMap<PathBuf, Counter>,
+}
+
+pub(crate) fn diff_coverage(
+    sections: &Sections,
+    changes: &HashMap<PathBuf, LineNum>,
+) -> DiffCoverage {
+    // Whether each changed line is hit by any test.
+    let mut files = BTreeMap::<&Path, BTreeMap<u32, bool>>::new();
+    for (key, value) in sections {
+        let Some(changed) = changes.get(&key.source_file) else {
+            continue;
+        };
+        for (line, data) in &value.lines {
+            if changed.contains(Range::from_line(line.line)) {
+                *files
+                    .entry(&key.source_file)
+                    .or_default()
+                    .entry(line.line)
+                    .or_default() |= data.count > 0;
+            }
+        }
+    }
+
+    let mut coverage = DiffCoverage::default();
+    for (path, lines) in files {
+        let hit = lines.values().filter(|&&hit| hit).count();
+        let counter = Counter::new(lines.len() as u32, hit as u32);
+        coverage.total += counter;
+        let _ = coverage.files.insert(path.into(), counter);
+    }
+    coverage
+}
+
+// The directory of `path` truncated to the first `depth` components, not counting the root.
+pub(crate) fn directory(path: &Path, depth: Option<usize>) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut dir = PathBuf::new();
+    let mut normals = 0;
+    for component in parent.components() {
+        if let Component::Normal(_) = component {
+            if Some(normals) == depth {
+                break;
+            }
+            normals += 1;
+        }
+        dir.push(component);
+    }
+    if dir.as_os_str().is_empty() {
+        dir.push(".");
+    }
+    dir
+}
+
+// Sections of the same source file are merged, so that an item is hit if any test hits it.
+// Sums up the numbers of the source files. Only the sections of source files covered by more
+// than one test are cloned to be merged.
+pub(crate) fn total(sections: &Sections) -> Stats {
+    let mut by_file = BTreeMap::<&Path, Vec<&section::Value>>::new();
+    for (key, value) in sections {
+        by_file.entry(&key.source_file).or_default().push(value);
+    }
+    let mut total = Stats::default();
+    for values in by_file.values() {
+        total += match values[..] {
+            [value] => Stats::of(value),
+            _ => {
+                let mut merged = section::Value::default();
+                for &value in values {
+                    merged.merge_lossy(value.clone());
+                }
+                Stats::of(&merged)
+            }
+        };
+    }
+    total
+}
+
+/// Specifies the order of source files in a summary.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum SortBy {
+    /// Sorts by the path of the source file.
+    #[default]
+    Path,
+    /// Sorts by the line coverage rate, lowest first.
+    LineRate,
+    /// Sorts by the branch coverage rate, lowest first.
+    BranchRate,
+    /// Sorts by the function coverage rate, lowest first.
+    FunctionRate,
+    /// Sorts by the number of lines not hit, largest first.
+    MissedLines,
+}
+
+impl SortBy {
+    fn compare(self, a: &Stats, b: &Stats) -> Ordering {
+        // Files without items are sorted last.
+        fn by_rate(a: &Counter, b: &Counter) -> Ordering {
+            match (a.rate(), b.rate()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+
+        match self {
+            SortBy::Path => Ordering::Equal,
+            SortBy::LineRate => by_rate(&a.lines, &b.lines),
+            SortBy::BranchRate => by_rate(&a.branches, &b.branches),
+            SortBy::FunctionRate => by_rate(&a.functions, &b.functions),
+            SortBy::MissedLines => b.lines.missed().cmp(&a.lines.missed()),
+        }
+    }
+}
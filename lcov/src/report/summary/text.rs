@@ -0,0 +1,212 @@
+use super::{Counter, SortBy, Stats, Summary};
+use std::fmt;
+
+/// A formatter which renders a [`Summary`] as a plain text table.
+///
+/// This `struct` is created by the [`text`] method on [`Summary`].
+/// The table has rows for each source file followed by a row for the whole report.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/lib.rs").add_line(1, 1).add_function("foo", 1, 1);
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+///
+/// let summary = report.summary();
+/// assert_eq!(summary.text().to_string(), "\
+/// File        | Lines         | Functions     | Branches
+/// ------------+---------------+---------------+---------
+/// src/lib.rs  | 100.00% (1/1) | 100.00% (1/1) | -
+/// src/main.rs | 50.00% (1/2)  | -             | -
+/// ------------+---------------+---------------+---------
+/// Total       | 66.67% (2/3)  | 100.00% (1/1) | -
+/// ");
+/// ```
+///
+/// [`Summary`]: struct.Summary.html
+/// [`text`]: struct.Summary.html#method.text
+#[derive(Debug, Clone, Copy)]
+pub struct Text<'a> {
+    summary: &'a Summary,
+    sort: SortBy,
+    reverse: bool,
+    limit: Option<usize>,
+    color: bool,
+}
+
+impl<'a> Text<'a> {
+    pub(super) fn new(summary: &'a Summary) -> Self {
+        Text {
+            summary,
+            sort: SortBy::default(),
+            reverse: false,
+            limit: None,
+            color: false,
+        }
+    }
+
+    /// Specifies the order of source files.
+    ///
+    /// Source files are sorted by path by default.
+    pub fn sort_by(mut self, sort: SortBy) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Specifies whether the order of source files is reversed.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Specifies the maximum number of source files in the table.
+    ///
+    /// All source files are output by default. The total row still counts all source files.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    /// use lcov::report::summary::SortBy;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/lib.rs").add_line(1, 1);
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    ///
+    /// let summary = report.summary();
+    /// assert_eq!(summary.text().sort_by(SortBy::LineRate).limit(Some(1)).to_string(), "\
+    /// File        | Lines        | Functions | Branches
+    /// ------------+--------------+-----------+---------
+    /// src/main.rs | 50.00% (1/2) | -         | -
+    /// ------------+--------------+-----------+---------
+    /// Total       | 66.67% (2/3) | -         | -
+    /// ");
+    /// ```
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Specifies whether coverage rates are colorized with ANSI escape sequences.
+    ///
+    /// Rates of 90% or more are shown in green, 75% or more in yellow, and the others in red.
+    /// Colors are disabled by default. Callers writing to a terminal should honor the
+    /// [`NO_COLOR`] environment variable.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::Report;
+    ///
+    /// let mut report = Report::new();
+    /// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+    ///
+    /// let summary = report.summary();
+    /// let text = summary.text().color(true).to_string();
+    /// assert!(text.contains("\x1b[31m50.00% (1/2)\x1b[0m"));
+    /// ```
+    ///
+    /// [`NO_COLOR`]: https://no-color.org/
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header = ["File", "Lines", "Functions", "Branches"].map(String::from);
+        let mut files = self.summary.sorted_files(self.sort);
+        if self.reverse {
+            files.reverse();
+        }
+        let files = files
+            .into_iter()
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|(path, stats)| row(path.display().to_string(), stats))
+            .collect::<Vec<_>>();
+        let total = row("Total".into(), &self.summary.total);
+
+        let mut widths = [0; 4];
+        for row in [&header, &total.0]
+            .into_iter()
+            .chain(files.iter().map(|(row, _)| row))
+        {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = usize::max(*width, cell.chars().count());
+            }
+        }
+
+        let no_colors = [None; 4];
+        let colors = |colors| if self.color { colors } else { &no_colors };
+        write_row(f, &header, &no_colors, &widths)?;
+        write_separator(f, &widths)?;
+        for (row, row_colors) in &files {
+            write_row(f, row, colors(row_colors), &widths)?;
+        }
+        write_separator(f, &widths)?;
+        write_row(f, &total.0, colors(&total.1), &widths)
+    }
+}
+
+// ANSI escape sequences of the colors of the cells.
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+type Row = ([String; 4], [Option<&'static str>; 4]);
+
+fn row(name: String, stats: &Stats) -> Row {
+    let cell = |counter: Counter| counter.to_string();
+    let color = |counter: Counter| match counter.rate() {
+        Some(rate) if rate >= 0.9 => Some(GREEN),
+        Some(rate) if rate >= 0.75 => Some(YELLOW),
+        Some(_) => Some(RED),
+        None => None,
+    };
+    (
+        [
+            name,
+            cell(stats.lines),
+            cell(stats.functions),
+            cell(stats.branches),
+        ],
+        [
+            None,
+            color(stats.lines),
+            color(stats.functions),
+            color(stats.branches),
+        ],
+    )
+}
+
+fn write_row(
+    f: &mut fmt::Formatter,
+    row: &[String; 4],
+    colors: &[Option<&str>; 4],
+    widths: &[usize; 4],
+) -> fmt::Result {
+    for (i, ((cell, color), &width)) in row.iter().zip(colors).zip(widths).enumerate() {
+        // Pads the cell outside of the escape sequences, which have no width on terminals.
+        let padding = width.saturating_sub(cell.chars().count());
+        match color {
+            Some(color) => write!(f, "{}{}{}", color, cell, RESET)?,
+            None => write!(f, "{}", cell)?,
+        }
+        if i == row.len() - 1 {
+            writeln!(f)?;
+        } else {
+            write!(f, "{:padding$} | ", "", padding = padding)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_separator(f: &mut fmt::Formatter, widths: &[usize; 4]) -> fmt::Result {
+    let lines = widths.map(|width| "-".repeat(width));
+    writeln!(f, "{}", lines.join("-+-"))
+}
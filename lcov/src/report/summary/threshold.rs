@@ -0,0 +1,171 @@
+use super::{Counter, Stats, Summary};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Minimum coverage rates required by [`Report::check`].
+///
+/// Rates are specified in percent. Limits for the whole report and for each source file can be
+/// specified separately. Categories without any items (e.g. a file without branches) never
+/// violate the limits.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::summary::Thresholds;
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/main.rs").add_line(1, 1).add_line(2, 0);
+/// let _ = report.section("", "src/lib.rs").add_line(1, 1);
+///
+/// let thresholds = Thresholds::new().lines(70.0).file_lines(50.0);
+/// let violations = report.check(&thresholds);
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].to_string(), "line coverage 66.67% is below the minimum 70.00%");
+/// ```
+///
+/// [`Report::check`]: ../struct.Report.html#method.check
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Thresholds {
+    total: Limits,
+    file: Limits,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+struct Limits {
+    lines: Option<f64>,
+    branches: Option<f64>,
+    functions: Option<f64>,
+}
+
+impl Thresholds {
+    /// Creates thresholds without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum line coverage rate of the whole report.
+    pub fn lines(mut self, percent: f64) -> Self {
+        self.total.lines = Some(percent);
+        self
+    }
+
+    /// Sets the minimum branch coverage rate of the whole report.
+    pub fn branches(mut self, percent: f64) -> Self {
+        self.total.branches = Some(percent);
+        self
+    }
+
+    /// Sets the minimum function coverage rate of the whole report.
+    pub fn functions(mut self, percent: f64) -> Self {
+        self.total.functions = Some(percent);
+        self
+    }
+
+    /// Sets the minimum line coverage rate of each source file.
+    pub fn file_lines(mut self, percent: f64) -> Self {
+        self.file.lines = Some(percent);
+        self
+    }
+
+    /// Sets the minimum branch coverage rate of each source file.
+    pub fn file_branches(mut self, percent: f64) -> Self {
+        self.file.branches = Some(percent);
+        self
+    }
+
+    /// Sets the minimum function coverage rate of each source file.
+    pub fn file_functions(mut self, percent: f64) -> Self {
+        self.file.functions = Some(percent);
+        self
+    }
+}
+
+/// A coverage category.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Metric {
+    /// Line coverage.
+    Lines,
+    /// Branch coverage.
+    Branches,
+    /// Function coverage.
+    Functions,
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Metric::Lines => "line",
+            Metric::Branches => "branch",
+            Metric::Functions => "function",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A coverage rate below the minimum specified by [`Thresholds`].
+///
+/// [`Thresholds`]: struct.Thresholds.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The source file, or `None` for the whole report.
+    pub path: Option<PathBuf>,
+    /// The coverage category.
+    pub metric: Metric,
+    /// The actual coverage rate in percent.
+    pub actual: f64,
+    /// The minimum coverage rate in percent.
+    pub minimum: f64,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} coverage ", self.metric)?;
+        if let Some(path) = &self.path {
+            write!(f, "of {} ", path.display())?;
+        }
+        write!(
+            f,
+            "{:.2}% is below the minimum {:.2}%",
+            self.actual, self.minimum
+        )
+    }
+}
+
+pub(super) fn check(summary: &Summary, thresholds: &Thresholds) -> Vec<Violation> {
+    let mut violations = vec![];
+    check_stats(&mut violations, None, &summary.total, &thresholds.total);
+    for (path, stats) in &summary.files {
+        check_stats(&mut violations, Some(path), stats, &thresholds.file);
+    }
+    violations
+}
+
+fn check_stats(
+    violations: &mut Vec<Violation>,
+    path: Option<&PathBuf>,
+    stats: &Stats,
+    limits: &Limits,
+) {
+    let metrics = [
+        (Metric::Lines, stats.lines, limits.lines),
+        (Metric::Branches, stats.branches, limits.branches),
+        (Metric::Functions, stats.functions, limits.functions),
+    ];
+    for (metric, counter, minimum) in metrics {
+        if let (Some(actual), Some(minimum)) = (percent(counter), minimum) {
+            if actual < minimum {
+                violations.push(Violation {
+                    path: path.cloned(),
+                    metric,
+                    actual,
+                    minimum,
+                });
+            }
+        }
+    }
+}
+
+pub(super) fn percent(counter: Counter) -> Option<f64> {
+    counter.rate().map(|rate| rate * 100.0)
+}
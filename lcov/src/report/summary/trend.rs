@@ -0,0 +1,232 @@
+use super::threshold::{self, Metric};
+use super::{Stats, Summary};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Changes of coverage over an ordered series of reports.
+///
+/// Each point of the series is the numbers of a [`Summary`], so a trend of many reports can be
+/// kept without the reports themselves. Points are usually pushed in chronological order, e.g.
+/// one for each commit or nightly run.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::Report;
+/// use lcov::report::summary::{Metric, Trend};
+/// use std::path::Path;
+///
+/// let mut trend = Trend::new();
+/// for (main_hit, lib_hit) in [(4, 1), (3, 3), (2, 3)] {
+///     let mut report = Report::new();
+///     for line in 1..=4 {
+///         let _ = report.section("", "src/main.rs").add_line(line, u64::from(line <= main_hit));
+///         let _ = report.section("", "src/lib.rs").add_line(line, u64::from(line <= lib_hit));
+///     }
+///     trend.push(&report.summary());
+/// }
+///
+/// assert_eq!(trend.len(), 3);
+/// let deltas = trend.total_deltas();
+/// assert_eq!(deltas[0].lines, Some(12.5));
+/// assert_eq!(deltas[1].lines, Some(-12.5));
+/// assert!(!trend.is_decreasing(Metric::Lines));
+/// assert_eq!(trend.decreasing_files(Metric::Lines), [Path::new("src/main.rs")]);
+/// ```
+///
+/// [`Summary`]: struct.Summary.html
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Trend {
+    total: Vec<Stats>,
+    files: BTreeMap<PathBuf, Vec<Option<Stats>>>,
+}
+
+/// A change of coverage rates between two points of a [`Trend`], in percentage points.
+///
+/// Each field is `None` if either point has no items of the category.
+///
+/// [`Trend`]: struct.Trend.html
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Delta {
+    /// Change of the line coverage rate.
+    pub lines: Option<f64>,
+    /// Change of the branch coverage rate.
+    pub branches: Option<f64>,
+    /// Change of the function coverage rate.
+    pub functions: Option<f64>,
+}
+
+impl Delta {
+    /// Computes the change of coverage rates from `before` to `after`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lcov::report::summary::{Counter, Delta, Stats};
+    ///
+    /// let before = Stats { lines: Counter::new(4, 2), ..Stats::default() };
+    /// let after = Stats { lines: Counter::new(4, 3), ..Stats::default() };
+    /// let delta = Delta::between(&before, &after);
+    /// assert_eq!(delta.lines, Some(25.0));
+    /// assert_eq!(delta.branches, None);
+    /// ```
+    pub fn between(before: &Stats, after: &Stats) -> Self {
+        let delta = |metric| {
+            let before = threshold::percent(before.counter(metric))?;
+            let after = threshold::percent(after.counter(metric))?;
+            Some(after - before)
+        };
+        Delta {
+            lines: delta(Metric::Lines),
+            branches: delta(Metric::Branches),
+            functions: delta(Metric::Functions),
+        }
+    }
+
+    /// Returns the change of the rate of `metric`.
+    pub fn get(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Lines => self.lines,
+            Metric::Branches => self.branches,
+            Metric::Functions => self.functions,
+        }
+    }
+}
+
+impl Trend {
+    /// Creates an empty trend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the numbers of a summary as the latest point.
+    pub fn push(&mut self, summary: &Summary) {
+        let len = self.total.len();
+        self.total.push(summary.total);
+        for (path, series) in &mut self.files {
+            series.push(summary.files.get(path).copied());
+        }
+        for (path, stats) in &summary.files {
+            if !self.files.contains_key(path) {
+                let mut series = vec![None; len];
+                series.push(Some(*stats));
+                let _ = self.files.insert(path.clone(), series);
+            }
+        }
+    }
+
+    /// Returns the number of points.
+    pub fn len(&self) -> usize {
+        self.total.len()
+    }
+
+    /// Returns `true` if the trend has no points.
+    pub fn is_empty(&self) -> bool {
+        self.total.is_empty()
+    }
+
+    /// Returns the numbers of the whole reports, one for each point.
+    pub fn total(&self) -> &[Stats] {
+        &self.total
+    }
+
+    /// Returns the numbers of a source file, one for each point.
+    ///
+    /// Points in which the source file does not appear are `None`.
+    pub fn file<P>(&self, path: P) -> Option<&[Option<Stats>]>
+    where
+        P: AsRef<Path>,
+    {
+        self.files.get(path.as_ref()).map(Vec::as_slice)
+    }
+
+    /// Returns the source files which appear in any point.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Returns the changes of the whole reports between consecutive points.
+    pub fn total_deltas(&self) -> Vec<Delta> {
+        self.total
+            .windows(2)
+            .map(|pair| Delta::between(&pair[0], &pair[1]))
+            .collect()
+    }
+
+    /// Returns the changes of each source file between consecutive points.
+    ///
+    /// A change is `None` if the source file does not appear in either point.
+    pub fn file_deltas(&self) -> BTreeMap<&Path, Vec<Option<Delta>>> {
+        self.files
+            .iter()
+            .map(|(path, series)| {
+                let deltas = series
+                    .windows(2)
+                    .map(|pair| Some(Delta::between(pair[0].as_ref()?, pair[1].as_ref()?)))
+                    .collect();
+                (path.as_path(), deltas)
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the coverage rate of the whole reports decreases monotonically.
+    ///
+    /// A series decreases monotonically if the rate never increases between consecutive points,
+    /// and the latest rate is lower than the first one. Points without items of the category are
+    /// skipped, and series with less than two rates never decrease.
+    pub fn is_decreasing(&self, metric: Metric) -> bool {
+        is_decreasing(self.total.iter().map(|stats| Some(*stats)), metric)
+    }
+
+    /// Returns the source files whose coverage rates decrease monotonically.
+    ///
+    /// See [`is_decreasing`] for the definition of the monotonic decrease. Points in which a
+    /// source file does not appear are skipped.
+    ///
+    /// [`is_decreasing`]: #method.is_decreasing
+    pub fn decreasing_files(&self, metric: Metric) -> Vec<&Path> {
+        self.files
+            .iter()
+            .filter(|(_, series)| is_decreasing(series.iter().copied(), metric))
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+}
+
+impl Extend<Summary> for Trend {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = Summary>,
+    {
+        for summary in iter {
+            self.push(&summary);
+        }
+    }
+}
+
+impl FromIterator<Summary> for Trend {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = Summary>,
+    {
+        let mut trend = Trend::new();
+        trend.extend(iter);
+        trend
+    }
+}
+
+fn is_decreasing<I>(series: I, metric: Metric) -> bool
+where
+    I: IntoIterator<Item = Option<Stats>>,
+{
+    let rates = series
+        .into_iter()
+        .filter_map(|stats| threshold::percent(stats?.counter(metric)))
+        .collect::<Vec<_>>();
+    match (rates.first(), rates.last()) {
+        (Some(first), Some(last)) => {
+            last < first && rates.windows(2).all(|pair| pair[1] <= pair[0])
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,260 @@
+use super::section::{self, branch, line, Sections};
+use super::RecordKind;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// A problem found by [`Report::validate`].
+///
+/// [`Report::validate`]: struct.Report.html#method.validate
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Finding {
+    /// The section in which the problem is found.
+    pub key: section::Key,
+    /// The kind of the problem.
+    pub kind: FindingKind,
+}
+
+/// All kinds of problems found by [`Report::validate`].
+///
+/// [`Report::validate`]: struct.Report.html#method.validate
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FindingKind {
+    /// A function has an execution count (`FNDA`) but no start line (`FN`).
+    FunctionWithoutStartLine {
+        /// Function name.
+        name: String,
+    },
+    /// A branch (`BRDA`) is on a line that has no line data (`DA`).
+    BranchWithoutLineData {
+        /// The branch.
+        branch: branch::Key,
+    },
+    /// A record refers to the line number zero.
+    ZeroLineNumber {
+        /// The kind of the record.
+        kind: RecordKind,
+    },
+    /// A function has a different start line in another section of the same source file.
+    ///
+    /// Merging such sections fails with [`MergeError::UnmatchedFunctionLine`].
+    ///
+    /// [`MergeError::UnmatchedFunctionLine`]: enum.MergeError.html#variant.UnmatchedFunctionLine
+    ConflictingFunctionLine {
+        /// Function name.
+        name: String,
+        /// Start line of the function in this section.
+        start_line: u32,
+        /// Test name of the other section.
+        other_test_name: String,
+        /// Start line of the function in the other section.
+        other_start_line: u32,
+    },
+    /// A line has a different checksum in another section of the same source file.
+    ///
+    /// Merging such sections fails with [`MergeError::UnmatchedChecksum`].
+    ///
+    /// [`MergeError::UnmatchedChecksum`]: enum.MergeError.html#variant.UnmatchedChecksum
+    ConflictingChecksum {
+        /// Line number.
+        line: u32,
+        /// Test name of the other section.
+        other_test_name: String,
+    },
+    /// A source file has a different revision (`VER`) in another section.
+    ///
+    /// Merging such sections fails with [`MergeError::UnmatchedVersion`].
+    ///
+    /// [`MergeError::UnmatchedVersion`]: enum.MergeError.html#variant.UnmatchedVersion
+    ConflictingVersion {
+        /// Revision of the source file in this section.
+        version: String,
+        /// Test name of the other section.
+        other_test_name: String,
+        /// Revision of the source file in the other section.
+        other_version: String,
+    },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.key.source_file.display())?;
+        if !self.key.test_name.is_empty() {
+            write!(f, " (test `{}`)", self.key.test_name)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use FindingKind::*;
+
+        match *self {
+            FunctionWithoutStartLine { ref name } => {
+                write!(f, "function `{}` has no start line", name)
+            }
+            BranchWithoutLineData { ref branch } => write!(
+                f,
+                "branch {}{},{} at line {} has no line data",
+                if branch.exception { "e" } else { "" },
+                branch.block,
+                branch.branch,
+                branch.line
+            ),
+            ZeroLineNumber { kind } => write!(f, "`{}` record refers to line 0", kind),
+            ConflictingFunctionLine {
+                ref name,
+                start_line,
+                ref other_test_name,
+                other_start_line,
+            } => write!(
+                f,
+                "function `{}` starts at line {}, but at line {} in test `{}`",
+                name, start_line, other_start_line, other_test_name
+            ),
+            ConflictingChecksum {
+                line,
+                ref other_test_name,
+            } => write!(
+                f,
+                "checksum of line {} differs from the one in test `{}`",
+                line, other_test_name
+            ),
+            ConflictingVersion {
+                ref version,
+                ref other_test_name,
+                ref other_version,
+            } => write!(
+                f,
+                "source file revision is `{}`, but `{}` in test `{}`",
+                version, other_version, other_test_name
+            ),
+        }
+    }
+}
+
+pub(crate) fn validate(sections: &Sections) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    for (key, value) in sections {
+        let mut push = |kind| {
+            findings.push(Finding {
+                key: key.clone(),
+                kind,
+            })
+        };
+
+        for (fkey, fvalue) in &value.functions {
+            match fvalue.start_line {
+                None => push(FindingKind::FunctionWithoutStartLine {
+                    name: fkey.name.clone(),
+                }),
+                Some(0) => push(FindingKind::ZeroLineNumber {
+                    kind: RecordKind::FunctionName,
+                }),
+                Some(_) => {}
+            }
+        }
+        for bkey in value.branches.keys() {
+            if bkey.line == 0 {
+                push(FindingKind::ZeroLineNumber {
+                    kind: RecordKind::BranchData,
+                });
+            } else if !value.lines.contains_key(&line::Key { line: bkey.line }) {
+                push(FindingKind::BranchWithoutLineData { branch: *bkey });
+            }
+        }
+        if value.lines.contains_key(&line::Key { line: 0 }) {
+            push(FindingKind::ZeroLineNumber {
+                kind: RecordKind::LineData,
+            });
+        }
+    }
+
+    findings.extend(validate_across_tests(sections));
+    findings
+}
+
+// Checks that sections with the same source file can be merged without conflicts.
+fn validate_across_tests(sections: &Sections) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut function_lines = BTreeMap::<(&Path, &str), (&str, u32)>::new();
+    let mut checksums = BTreeMap::<(&Path, u32), (&str, &str)>::new();
+    let mut versions = BTreeMap::<&Path, (&str, &str)>::new();
+
+    for (key, value) in sections {
+        let path = key.source_file.as_path();
+        if let Some(ref version) = value.version {
+            match versions.entry(path) {
+                Entry::Vacant(e) => {
+                    let _ = e.insert((&key.test_name, version));
+                }
+                Entry::Occupied(e) => {
+                    let &(other_test_name, other_version) = e.get();
+                    if other_version != version {
+                        findings.push(Finding {
+                            key: key.clone(),
+                            kind: FindingKind::ConflictingVersion {
+                                version: version.clone(),
+                                other_test_name: other_test_name.into(),
+                                other_version: other_version.into(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        for (fkey, fvalue) in &value.functions {
+            let start_line = match fvalue.start_line {
+                Some(start_line) => start_line,
+                None => continue,
+            };
+            match function_lines.entry((path, &fkey.name)) {
+                Entry::Vacant(e) => {
+                    let _ = e.insert((&key.test_name, start_line));
+                }
+                Entry::Occupied(e) => {
+                    let &(other_test_name, other_start_line) = e.get();
+                    if other_start_line != start_line {
+                        findings.push(Finding {
+                            key: key.clone(),
+                            kind: FindingKind::ConflictingFunctionLine {
+                                name: fkey.name.clone(),
+                                start_line,
+                                other_test_name: other_test_name.into(),
+                                other_start_line,
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        for (lkey, lvalue) in &value.lines {
+            let checksum = match lvalue.checksum {
+                Some(ref checksum) => checksum,
+                None => continue,
+            };
+            match checksums.entry((path, lkey.line)) {
+                Entry::Vacant(e) => {
+                    let _ = e.insert((&key.test_name, checksum));
+                }
+                Entry::Occupied(e) => {
+                    let &(other_test_name, other_checksum) = e.get();
+                    if other_checksum != checksum {
+                        findings.push(Finding {
+                            key: key.clone(),
+                            kind: FindingKind::ConflictingChecksum {
+                                line: lkey.line,
+                                other_test_name: other_test_name.into(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    findings
+}
@@ -0,0 +1,220 @@
+use super::section::{self, branch, function, line};
+
+/// A visitor over the coverage information of a [`Report`].
+///
+/// Every method has a default implementation, so visitors only implement the methods for the
+/// items they are interested in. The default [`visit_section`] visits the functions, branches
+/// and lines of the section in this order by [`walk_section`]. A visitor overriding it can call
+/// [`walk_section`] to keep visiting them.
+///
+/// Visitors are composed by tuples, which pass every item to each element, and can be reused
+/// through `&mut` references.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov::report::section::{self, line};
+/// use lcov::report::ReportVisitor;
+/// use lcov::Report;
+/// use std::path::PathBuf;
+///
+/// // Counts lines never executed.
+/// #[derive(Default)]
+/// struct MissedLines(usize);
+///
+/// impl ReportVisitor for MissedLines {
+///     fn visit_line(&mut self, _: &section::Key, _: &line::Key, value: &line::Value) {
+///         self.0 += usize::from(value.count == 0);
+///     }
+/// }
+///
+/// // Collects the source files.
+/// #[derive(Default)]
+/// struct Files(Vec<PathBuf>);
+///
+/// impl ReportVisitor for Files {
+///     fn visit_section(&mut self, key: &section::Key, _: &section::Value) {
+///         self.0.push(key.source_file.clone());
+///     }
+/// }
+///
+/// let mut report = Report::new();
+/// let _ = report.section("", "src/lib.rs").add_line(1, 1).add_line(2, 0);
+/// let _ = report.section("", "src/main.rs").add_line(1, 0);
+///
+/// let mut visitor = (MissedLines::default(), Files::default());
+/// report.accept(&mut visitor);
+/// assert_eq!(visitor.0 .0, 2);
+/// assert_eq!(visitor.1 .0, [PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]);
+/// ```
+///
+/// [`Report`]: struct.Report.html
+/// [`visit_section`]: #method.visit_section
+/// [`walk_section`]: fn.walk_section.html
+pub trait ReportVisitor {
+    /// Visits a section.
+    fn visit_section(&mut self, key: &section::Key, value: &section::Value) {
+        walk_section(self, key, value);
+    }
+
+    /// Visits a function in the section of `section`.
+    fn visit_function(
+        &mut self,
+        section: &section::Key,
+        key: &function::Key,
+        value: &function::Value,
+    ) {
+        let _ = (section, key, value);
+    }
+
+    /// Visits a branch in the section of `section`.
+    fn visit_branch(&mut self, section: &section::Key, key: &branch::Key, value: &branch::Value) {
+        let _ = (section, key, value);
+    }
+
+    /// Visits a line in the section of `section`.
+    fn visit_line(&mut self, section: &section::Key, key: &line::Key, value: &line::Value) {
+        let _ = (section, key, value);
+    }
+}
+
+/// Visits the functions, branches and lines of a section in this order.
+///
+/// This is the default implementation of [`ReportVisitor::visit_section`].
+///
+/// [`ReportVisitor::visit_section`]: trait.ReportVisitor.html#method.visit_section
+pub fn walk_section<V>(visitor: &mut V, key: &section::Key, value: &section::Value)
+where
+    V: ReportVisitor + ?Sized,
+{
+    for (k, v) in &value.functions {
+        visitor.visit_function(key, k, v);
+    }
+    for (k, v) in &value.branches {
+        visitor.visit_branch(key, k, v);
+    }
+    for (k, v) in &value.lines {
+        visitor.visit_line(key, k, v);
+    }
+}
+
+impl<V> ReportVisitor for &mut V
+where
+    V: ReportVisitor + ?Sized,
+{
+    fn visit_section(&mut self, key: &section::Key, value: &section::Value) {
+        (**self).visit_section(key, value);
+    }
+
+    fn visit_function(
+        &mut self,
+        section: &section::Key,
+        key: &function::Key,
+        value: &function::Value,
+    ) {
+        (**self).visit_function(section, key, value);
+    }
+
+    fn visit_branch(&mut self, section: &section::Key, key: &branch::Key, value: &branch::Value) {
+        (**self).visit_branch(section, key, value);
+    }
+
+    fn visit_line(&mut self, section: &section::Key, key: &line::Key, value: &line::Value) {
+        (**self).visit_line(section, key, value);
+    }
+}
+
+// Each element visits the section by itself, so elements overriding `visit_section` without
+// walking it don't prevent the others from visiting the items. The items are also forwarded, for
+// the tuple visited by `walk_section` or by the methods directly.
+impl<A, B> ReportVisitor for (A, B)
+where
+    A: ReportVisitor,
+    B: ReportVisitor,
+{
+    fn visit_section(&mut self, key: &section::Key, value: &section::Value) {
+        self.0.visit_section(key, value);
+        self.1.visit_section(key, value);
+    }
+
+    fn visit_function(
+        &mut self,
+        section: &section::Key,
+        key: &function::Key,
+        value: &function::Value,
+    ) {
+        self.0.visit_function(section, key, value);
+        self.1.visit_function(section, key, value);
+    }
+
+    fn visit_branch(&mut self, section: &section::Key, key: &branch::Key, value: &branch::Value) {
+        self.0.visit_branch(section, key, value);
+        self.1.visit_branch(section, key, value);
+    }
+
+    fn visit_line(&mut self, section: &section::Key, key: &line::Key, value: &line::Value) {
+        self.0.visit_line(section, key, value);
+        self.1.visit_line(section, key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Report;
+
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl ReportVisitor for Trace {
+        fn visit_function(&mut self, _: &section::Key, key: &function::Key, _: &function::Value) {
+            self.0.push(format!("FN {}", key.name));
+        }
+
+        fn visit_branch(&mut self, _: &section::Key, key: &branch::Key, _: &branch::Value) {
+            self.0.push(format!("BR {}", key.line));
+        }
+
+        fn visit_line(&mut self, section: &section::Key, key: &line::Key, _: &line::Value) {
+            self.0
+                .push(format!("DA {}:{}", section.source_file.display(), key.line));
+        }
+    }
+
+    #[test]
+    fn visit_order() {
+        let mut report = Report::new();
+        let _ = report
+            .section("", "b.c")
+            .add_line(2, 1)
+            .add_line(1, 0)
+            .add_branch(1, 0, 0, Some(1))
+            .add_function("f", 1, 1);
+        let _ = report.section("", "a.c").add_line(1, 1);
+
+        let mut trace = Trace::default();
+        report.accept(&mut trace);
+        assert_eq!(
+            trace.0,
+            ["DA a.c:1", "FN f", "BR 1", "DA b.c:1", "DA b.c:2"]
+        );
+    }
+
+    #[test]
+    fn tuple_visits_each() {
+        let mut report = Report::new();
+        let _ = report.section("", "a.c").add_line(1, 1);
+
+        let mut visitor = (Trace::default(), Trace::default());
+        report.accept(&mut visitor);
+        assert_eq!(visitor.0 .0, ["DA a.c:1"]);
+        assert_eq!(visitor.1 .0, ["DA a.c:1"]);
+
+        let mut visitor = (Trace::default(), Trace::default());
+        for (key, value) in &report.sections {
+            walk_section(&mut visitor, key, value);
+        }
+        assert_eq!(visitor.0 .0, ["DA a.c:1"]);
+        assert_eq!(visitor.1 .0, ["DA a.c:1"]);
+    }
+}
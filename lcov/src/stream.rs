@@ -0,0 +1,716 @@
+//! Streaming operations on LCOV records.
+//!
+//! The [`merge`] function merges LCOV tracefiles section by section, without reading them into
+//! [`Report`]s. The [`events`] function reads coverage information of LCOV tracefiles as
+//! [`Event`]s, so aggregates can be computed without building a [`Report`].
+//!
+//! [`merge`]: fn.merge.html
+//! [`events`]: fn.events.html
+//! [`Event`]: enum.Event.html
+//! [`Report`]: ../report/struct.Report.html
+use super::reader::Error as ReadError;
+use super::report::section::{self, branch, function, line};
+use super::report::{DisplayKey, IntoRecords, MergeConflict, MergeError, ParseError};
+use super::{Record, RecordKind, Report};
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::path::PathBuf;
+
+/// All possible errors that can occur when merging LCOV records by [`merge`].
+///
+/// [`merge`]: fn.merge.html
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An error indicating that parsing an input failed.
+    #[error("{}", _0)]
+    Parse(#[from] ParseError),
+
+    /// An error indicating that merging sections failed.
+    #[error("{}", _0)]
+    Merge(#[from] MergeError),
+
+    /// An error indicating that the sections of an input are not sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn main() {
+    /// use lcov::Reader;
+    /// use lcov::stream::{self, Error};
+    /// let input = "\
+    /// TN:
+    /// SF:foo.c
+    /// DA:1,1
+    /// end_of_record
+    /// TN:
+    /// SF:bar.c
+    /// DA:1,1
+    /// end_of_record
+    /// ";
+    /// let mut merged = stream::merge(vec![Reader::new(input.as_bytes())]);
+    /// assert_matches!(merged.find_map(Result::err), Some(Error::Unsorted { .. }));
+    /// # }
+    /// ```
+    #[error(
+        "sections are not sorted: `{}` follows `{}`",
+        DisplayKey(key),
+        DisplayKey(previous)
+    )]
+    Unsorted {
+        /// The section out of order.
+        key: section::Key,
+        /// The section read before.
+        previous: section::Key,
+    },
+
+    /// An error indicating that an input has more than one section of the same test name and
+    /// source file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn main() {
+    /// use lcov::Reader;
+    /// use lcov::stream::{self, Error};
+    /// let input = "\
+    /// TN:
+    /// SF:foo.c
+    /// DA:1,1
+    /// end_of_record
+    /// TN:
+    /// SF:foo.c
+    /// DA:2,1
+    /// end_of_record
+    /// ";
+    /// let mut merged = stream::merge(vec![Reader::new(input.as_bytes())]);
+    /// assert_matches!(merged.find_map(Result::err), Some(Error::Duplicated { .. }));
+    /// # }
+    /// ```
+    #[error("sections are duplicated: `{}`", DisplayKey(key))]
+    Duplicated {
+        /// The section read more than once.
+        key: section::Key,
+    },
+}
+
+/// Merges LCOV tracefiles whose sections are sorted, such as the ones written by [`Report`].
+///
+/// The inputs are read section by section, and sections with the same test name and source file
+/// are merged as [`Report::merge`] does. Only one section per input is kept in memory, so
+/// arbitrarily many (and large) tracefiles can be merged.
+///
+/// The sections of each input must be sorted in the order of [`section::Key`], i.e. by test name,
+/// and then by source file path. Otherwise, [`Error::Unsorted`] is returned, or
+/// [`Error::Duplicated`] if an input has more than one section of the same key.
+/// The iterator yields no more records after an error.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::{Reader, Report};
+/// use lcov::stream;
+///
+/// let input1 = "TN:\nSF:bar.c\nDA:1,1\nend_of_record\nTN:\nSF:foo.c\nDA:1,1\nend_of_record\n";
+/// let input2 = "TN:\nSF:foo.c\nDA:1,2\nDA:2,0\nend_of_record\n";
+///
+/// let merged = stream::merge(vec![
+///     Reader::new(input1.as_bytes()),
+///     Reader::new(input2.as_bytes()),
+/// ]);
+/// let report = merged.collect::<Result<Report, _>>()?;
+/// assert_eq!(report.line_count("bar.c", 1), Some(1));
+/// assert_eq!(report.line_count("foo.c", 1), Some(3));
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+///
+/// [`Report`]: ../report/struct.Report.html
+/// [`Report::merge`]: ../report/struct.Report.html#method.merge
+/// [`section::Key`]: ../report/section/struct.Key.html
+/// [`Error::Unsorted`]: enum.Error.html#variant.Unsorted
+/// [`Error::Duplicated`]: enum.Error.html#variant.Duplicated
+pub fn merge<I, R, E>(inputs: I) -> Merge<R>
+where
+    I: IntoIterator<Item = R>,
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    Merge::new(inputs, false)
+}
+
+/// Merges LCOV tracefiles whose sections are sorted, with ignoring merge errors.
+///
+/// This is the streaming counterpart of [`Report::merge_lossy`]. See [`merge`] for more.
+///
+/// [`Report::merge_lossy`]: ../report/struct.Report.html#method.merge_lossy
+/// [`merge`]: fn.merge.html
+pub fn merge_lossy<I, R, E>(inputs: I) -> Merge<R>
+where
+    I: IntoIterator<Item = R>,
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    Merge::new(inputs, true)
+}
+
+/// An iterator which yields the merged LCOV records.
+///
+/// This `struct` is created by the [`merge`] and [`merge_lossy`] functions.
+/// See their documentation for more.
+///
+/// [`merge`]: fn.merge.html
+/// [`merge_lossy`]: fn.merge_lossy.html
+#[derive(Debug)]
+pub struct Merge<R> {
+    inputs: Vec<Input<R>>,
+    lossy: bool,
+    started: bool,
+    done: bool,
+    pending: Option<IntoRecords>,
+}
+
+#[derive(Debug)]
+struct Input<R> {
+    records: R,
+    current: Option<(section::Key, section::Value)>,
+    previous: Option<section::Key>,
+}
+
+impl<R, E> Input<R>
+where
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    // Reads the next section which has coverage information.
+    fn advance(&mut self) -> Result<(), Error> {
+        self.current = None;
+        loop {
+            let mut records = vec![];
+            for rec in &mut self.records {
+                let rec = rec.map_err(|e| ParseError::Read(e.into()))?;
+                let end = rec == Record::EndOfRecord;
+                records.push(rec);
+                if end {
+                    break;
+                }
+            }
+            if records.is_empty() {
+                return Ok(());
+            }
+
+            let report = Report::from_reader(records.into_iter().map(Ok::<_, ReadError>))?;
+            if let Some((key, value)) = report.sections.into_iter().next() {
+                if let Some(previous) = self.previous.take() {
+                    if key == previous {
+                        return Err(Error::Duplicated { key });
+                    }
+                    if key < previous {
+                        return Err(Error::Unsorted { key, previous });
+                    }
+                }
+                self.previous = Some(key.clone());
+                self.current = Some((key, value));
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<R, E> Merge<R>
+where
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    fn new<I>(inputs: I, lossy: bool) -> Self
+    where
+        I: IntoIterator<Item = R>,
+    {
+        let inputs = inputs
+            .into_iter()
+            .map(|records| Input {
+                records,
+                current: None,
+                previous: None,
+            })
+            .collect();
+        Merge {
+            inputs,
+            lossy,
+            started: false,
+            done: false,
+            pending: None,
+        }
+    }
+
+    // Merges the smallest sections of the inputs.
+    fn next_section(&mut self) -> Result<Option<Report>, Error> {
+        if !self.started {
+            self.started = true;
+            for input in &mut self.inputs {
+                input.advance()?;
+            }
+        }
+
+        let key = match self
+            .inputs
+            .iter()
+            .filter_map(|input| input.current.as_ref().map(|(key, _)| key))
+            .min()
+        {
+            Some(key) => key.clone(),
+            None => return Ok(None),
+        };
+
+        let mut merged = None::<section::Value>;
+        for input in &mut self.inputs {
+            if input.current.as_ref().map(|(k, _)| k) != Some(&key) {
+                continue;
+            }
+            if let Some((_, value)) = input.current.take() {
+                match &mut merged {
+                    None => merged = Some(value),
+                    Some(merged) if self.lossy => merged.merge_lossy(value),
+                    Some(merged) => merged.merge(value).map_err(|kind| {
+                        MergeError::from(MergeConflict {
+                            key: key.clone(),
+                            kind,
+                        })
+                    })?,
+                }
+            }
+            input.advance()?;
+        }
+
+        let mut report = Report::new();
+        if let Some(value) = merged {
+            let _ = report.sections.insert(key, value);
+        }
+        Ok(Some(report))
+    }
+}
+
+impl<R, E> Iterator for Merge<R>
+where
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(rec) = self.pending.as_mut().and_then(Iterator::next) {
+                return Some(Ok(rec));
+            }
+            match self.next_section() {
+                Ok(Some(report)) => self.pending = Some(report.into_records()),
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// An event of coverage information, yielded by [`Events`].
+///
+/// [`Events`]: struct.Events.html
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// A section starts.
+    ///
+    /// This event is yielded at the `SF` record, or at the first record with coverage
+    /// information if the section has no `SF` record.
+    SectionStart(section::Key),
+    /// Coverage information of a line (`DA` record).
+    Line(line::Key, line::Value),
+    /// Coverage information of a function.
+    ///
+    /// Function records (`FN`, `FNDA`, `FNL` and `FNA`) of the section are combined, and
+    /// the events are yielded in the order of the names just before [`SectionEnd`].
+    ///
+    /// [`SectionEnd`]: #variant.SectionEnd
+    Function(function::Key, function::Value),
+    /// Coverage information of a branch (`BRDA` record).
+    Branch(branch::Key, branch::Value),
+    /// The section ends (`end_of_record` record).
+    SectionEnd,
+}
+
+/// Reads LCOV records as events of coverage information.
+///
+/// Unlike [`Report::from_reader`], the coverage information is not collected into a report.
+/// Line and branch records are yielded as soon as they are read, and only the functions of the
+/// current section are kept in memory, so arbitrarily large tracefiles can be processed in
+/// constant memory. The events can be pulled by iterating [`Events`], or pushed into a consumer
+/// by [`Iterator::try_for_each`].
+///
+/// Records are not merged, i.e. duplicated records of a line or a branch in the same section
+/// are yielded as they are, and sections of the same test name and source file are yielded
+/// separately. Summary, `VER`, comment and unknown records are ignored.
+/// The iterator yields no more events after an error.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::Reader;
+/// use lcov::stream::{self, Event};
+/// use std::collections::BTreeMap;
+/// use std::path::Path;
+///
+/// let input = "\
+/// TN:
+/// SF:foo.c
+/// FN:1,main
+/// FNDA:1,main
+/// DA:1,1
+/// DA:2,0
+/// end_of_record
+/// TN:
+/// SF:bar.c
+/// DA:1,3
+/// end_of_record
+/// ";
+///
+/// // Computes the number of found and hit lines for each file.
+/// let mut totals = BTreeMap::new();
+/// let mut current = None;
+/// stream::events(Reader::new(input.as_bytes())).try_for_each(|event| {
+///     match event? {
+///         Event::SectionStart(key) => current = Some(key.source_file),
+///         Event::Line(_, value) => {
+///             let path = current.clone().unwrap();
+///             let (found, hit) = totals.entry(path).or_insert((0, 0));
+///             *found += 1;
+///             *hit += u32::from(value.count > 0);
+///         }
+///         _ => {}
+///     }
+///     Ok::<_, Box<dyn std::error::Error>>(())
+/// })?;
+/// assert_eq!(totals[Path::new("foo.c")], (2, 1));
+/// assert_eq!(totals[Path::new("bar.c")], (1, 1));
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+///
+/// [`Report::from_reader`]: ../report/struct.Report.html#method.from_reader
+/// [`Events`]: struct.Events.html
+pub fn events<I, E>(records: I) -> Events<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    Events {
+        records: records.into_iter(),
+        test_name: None,
+        opened: false,
+        started: false,
+        functions: function::Functions::new(),
+        leaders: HashMap::new(),
+        pending: VecDeque::new(),
+        done: false,
+    }
+}
+
+/// An iterator which yields the events of coverage information.
+///
+/// This `struct` is created by the [`events`] function. See its documentation for more.
+///
+/// [`events`]: fn.events.html
+#[derive(Debug)]
+pub struct Events<R> {
+    records: R,
+    test_name: Option<String>,
+    // Whether a record other than `TN`, comments and unknown records is read in the section.
+    opened: bool,
+    // Whether `Event::SectionStart` is yielded in the section.
+    started: bool,
+    functions: function::Functions,
+    leaders: HashMap<u32, (u32, Option<u32>)>,
+    pending: VecDeque<Event>,
+    done: bool,
+}
+
+impl<R, E> Events<R>
+where
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    fn start(&mut self, source_file: Option<PathBuf>) {
+        if !self.started {
+            self.started = true;
+            self.pending.push_back(Event::SectionStart(section::Key {
+                test_name: self.test_name.take().unwrap_or_default(),
+                source_file: source_file.unwrap_or_default(),
+            }));
+        }
+    }
+
+    fn end(&mut self) {
+        // Functions are read only after the section is started.
+        let functions = mem::take(&mut self.functions);
+        if self.started {
+            self.pending
+                .extend(functions.into_iter().map(|(k, v)| Event::Function(k, v)));
+            self.pending.push_back(Event::SectionEnd);
+        }
+        self.leaders.clear();
+        self.test_name = None;
+        self.opened = false;
+        self.started = false;
+    }
+
+    fn read(&mut self, rec: Record) -> Result<(), ParseError> {
+        match rec {
+            Record::TestName { name } => {
+                if self.opened {
+                    return Err(ParseError::UnexpectedRecord {
+                        expected: RecordKind::EndOfRecord,
+                        found: RecordKind::TestName,
+                    });
+                }
+                self.test_name = Some(name);
+                return Ok(());
+            }
+            Record::Comment { .. } | Record::Unknown { .. } => return Ok(()),
+            _ => {}
+        }
+        self.opened = true;
+        match rec {
+            Record::SourceFile { path } => {
+                if self.started {
+                    return Err(ParseError::UnexpectedRecord {
+                        expected: RecordKind::EndOfRecord,
+                        found: RecordKind::SourceFile,
+                    });
+                }
+                self.start(Some(path));
+            }
+            Record::FunctionName {
+                name,
+                start_line,
+                end_line,
+            } => {
+                self.start(None);
+                let data = self.functions.entry(function::Key { name }).or_default();
+                data.start_line = Some(start_line);
+                data.end_line = end_line;
+            }
+            Record::FunctionData { name, count } => {
+                self.start(None);
+                let data = self.functions.entry(function::Key { name }).or_default();
+                data.count = data.count.saturating_add(count);
+            }
+            Record::FunctionLeader {
+                index,
+                start_line,
+                end_line,
+            } => {
+                self.start(None);
+                let _ = self.leaders.insert(index, (start_line, end_line));
+            }
+            Record::FunctionAlias { index, count, name } => {
+                self.start(None);
+                let data = self.functions.entry(function::Key { name }).or_default();
+                if let Some(&(start_line, end_line)) = self.leaders.get(&index) {
+                    data.start_line = Some(start_line);
+                    data.end_line = end_line;
+                }
+                data.count = data.count.saturating_add(count);
+            }
+            Record::BranchData {
+                line,
+                block,
+                exception,
+                branch,
+                taken,
+            } => {
+                self.start(None);
+                let key = branch::Key {
+                    line,
+                    block,
+                    exception,
+                    branch,
+                };
+                self.pending
+                    .push_back(Event::Branch(key, branch::Value { taken }));
+            }
+            Record::LineData {
+                line,
+                count,
+                checksum,
+            } => {
+                self.start(None);
+                self.pending.push_back(Event::Line(
+                    line::Key { line },
+                    line::Value { count, checksum },
+                ));
+            }
+            Record::EndOfRecord => self.end(),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl<R, E> Iterator for Events<R>
+where
+    R: Iterator<Item = Result<Record, E>>,
+    E: Into<ReadError>,
+{
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.done {
+                return None;
+            }
+            let result = match self.records.next() {
+                Some(Ok(rec)) => self.read(rec),
+                Some(Err(e)) => Err(ParseError::Read(e.into())),
+                None if self.opened => Err(ParseError::UnexpectedEof),
+                None => {
+                    self.done = true;
+                    continue;
+                }
+            };
+            if let Err(e) = result {
+                self.done = true;
+                self.pending.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Reader;
+
+    fn collect(input: &str) -> Result<Vec<Event>, ParseError> {
+        events(Reader::new(input.as_bytes())).collect()
+    }
+
+    #[test]
+    fn functions_before_section_end() {
+        let input = "\
+TN:test
+SF:foo.c
+FNL:0,1,3
+FNA:0,2,main
+FN:5,helper
+DA:1,2
+BRDA:2,0,0,1
+FNDA:0,helper
+end_of_record
+";
+        let key = section::Key {
+            test_name: "test".into(),
+            source_file: "foo.c".into(),
+        };
+        let function = |name: &str, start_line, end_line, count| {
+            Event::Function(
+                function::Key { name: name.into() },
+                function::Value {
+                    start_line: Some(start_line),
+                    end_line,
+                    count,
+                },
+            )
+        };
+        assert_eq!(
+            collect(input).unwrap(),
+            [
+                Event::SectionStart(key),
+                Event::Line(
+                    line::Key { line: 1 },
+                    line::Value {
+                        count: 2,
+                        checksum: None
+                    }
+                ),
+                Event::Branch(
+                    branch::Key {
+                        line: 2,
+                        block: 0,
+                        exception: false,
+                        branch: 0
+                    },
+                    branch::Value { taken: Some(1) }
+                ),
+                function("helper", 5, None, 0),
+                function("main", 1, Some(3), 2),
+                Event::SectionEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn records_not_merged() {
+        let input =
+            "TN:\nSF:foo.c\nDA:1,1\nDA:1,2\nend_of_record\nTN:\nSF:foo.c\nDA:1,3\nend_of_record\n";
+        let lines = collect(input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Line(key, value) => Some((key.line, value.count)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(1, 1), (1, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn saturating_function_counts() {
+        let input = format!(
+            "TN:\nSF:foo.c\nFN:1,main\nFNDA:{0},main\nFNDA:{0},main\n\
+             FNL:0,5\nFNA:0,{0},helper\nFNA:0,{0},helper\nend_of_record\n",
+            u64::MAX
+        );
+        let counts = collect(&input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Function(key, value) => Some((key.name, value.count)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [("helper".into(), u64::MAX), ("main".into(), u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            collect("TN:\nSF:foo.c\nDA:1,1\n"),
+            Err(ParseError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            collect("TN:\nSF:foo.c\nTN:\n"),
+            Err(ParseError::UnexpectedRecord {
+                found: RecordKind::TestName,
+                ..
+            })
+        ));
+        assert_eq!(collect("TN:\nend_of_record\nTN:\n").unwrap(), []);
+    }
+}
@@ -0,0 +1,277 @@
+//! A writer of [LCOV records].
+//!
+//! The [`RecordWriter`] structure writes LCOV records to arbitrary writer, computing summary
+//! records of each section on the fly.
+//!
+//! [LCOV records]: ../enum.Record.html
+//! [`RecordWriter`]: struct.RecordWriter.html
+use super::report::section::branch;
+use super::report::SummaryRecords;
+use super::{Record, RecordKind};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+/// All possible errors that can occur when writing LCOV records by [`RecordWriter`].
+///
+/// [`RecordWriter`]: struct.RecordWriter.html
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An error indicating that I/O operation failed.
+    ///
+    /// This error occurs when the underlying writer returns an error.
+    #[error("{}", _0)]
+    Io(#[from] io::Error),
+
+    /// An error indicating that a record is written outside of a section.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matches::assert_matches;
+    /// # fn main() {
+    /// use lcov::{Record, RecordKind, RecordWriter};
+    /// use lcov::writer::Error;
+    ///
+    /// let mut writer = RecordWriter::new(vec![]);
+    /// let rec = Record::LineData { line: 1, count: 1, checksum: None };
+    /// assert_matches!(writer.write(&rec), Err(Error::OutsideSection(RecordKind::LineData)));
+    /// # }
+    /// ```
+    #[error("`{}` record is written outside of a section", _0)]
+    OutsideSection(RecordKind),
+}
+
+/// Writing LCOV records to a writer, computing summary records of each section.
+///
+/// Detail records (`FN`, `FNDA`, `FNL`, `FNA`, `BRDA` and `DA`) are written as they are, and
+/// summary records (`FNF`, `FNH`, `BRF`, `BRH`, `LF` and `LH`) are emitted when the section ends,
+/// i.e. when `end_of_record`, `TN` or `SF` record is written, or the writer is finished.
+/// Summary records written explicitly are ignored. Missing `end_of_record` records are also
+/// emitted.
+///
+/// Only the keys of the detail records are kept until the section ends, so sections don't have
+/// to be buffered by generators just to compute the totals.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lcov::{Record, RecordWriter};
+///
+/// let mut writer = RecordWriter::new(vec![]);
+/// writer.write(&Record::TestName { name: "".into() })?;
+/// writer.write(&Record::SourceFile { path: "src/main.rs".into() })?;
+/// writer.write(&Record::LineData { line: 1, count: 1, checksum: None })?;
+/// writer.write(&Record::LineData { line: 2, count: 0, checksum: None })?;
+/// writer.write(&Record::SourceFile { path: "src/lib.rs".into() })?;
+/// writer.write(&Record::LineData { line: 1, count: 3, checksum: None })?;
+/// let output = writer.finish()?;
+///
+/// assert_eq!(String::from_utf8(output)?, "\
+/// TN:
+/// SF:src/main.rs
+/// DA:1,1
+/// DA:2,0
+/// LF:2
+/// LH:1
+/// end_of_record
+/// SF:src/lib.rs
+/// DA:1,3
+/// LF:1
+/// LH:1
+/// end_of_record
+/// ");
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # try_main().expect("failed to run test");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordWriter<W> {
+    inner: W,
+    summary: SummaryRecords,
+    section: Option<Totals>,
+}
+
+// Keys of the detail records in the current section, and whether each of them is hit.
+#[derive(Debug, Default)]
+struct Totals {
+    functions: HashMap<String, bool>,
+    // Function groups (`FNL` records) in LCOV 2.x, which `FNF` and `FNH` count if exist.
+    function_groups: BTreeMap<u32, bool>,
+    branches: HashMap<branch::Key, bool>,
+    lines: HashMap<u32, bool>,
+}
+
+impl Totals {
+    fn add(&mut self, rec: &Record) {
+        match *rec {
+            Record::FunctionName { ref name, .. } => {
+                let _ = self.functions.entry(name.clone()).or_insert(false);
+            }
+            Record::FunctionData { ref name, count } => {
+                *self.functions.entry(name.clone()).or_insert(false) |= count > 0;
+            }
+            Record::FunctionLeader { index, .. } => {
+                let _ = self.function_groups.entry(index).or_insert(false);
+            }
+            Record::FunctionAlias {
+                index,
+                ref name,
+                count,
+            } => {
+                *self.functions.entry(name.clone()).or_insert(false) |= count > 0;
+                *self.function_groups.entry(index).or_insert(false) |= count > 0;
+            }
+            Record::BranchData {
+                line,
+                block,
+                exception,
+                branch,
+                taken,
+            } => {
+                let key = branch::Key {
+                    line,
+                    block,
+                    exception,
+                    branch,
+                };
+                *self.branches.entry(key).or_insert(false) |= taken.unwrap_or(0) > 0;
+            }
+            Record::LineData { line, count, .. } => {
+                *self.lines.entry(line).or_insert(false) |= count > 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn into_records(self, summary: SummaryRecords) -> Vec<Record> {
+        fn count<'a>(hits: impl Iterator<Item = &'a bool>) -> (u32, u32) {
+            hits.fold((0, 0), |(found, hit), &h| (found + 1, hit + u32::from(h)))
+        }
+
+        let (functions_found, functions_hit) = if self.function_groups.is_empty() {
+            count(self.functions.values())
+        } else {
+            count(self.function_groups.values())
+        };
+        let (branches_found, branches_hit) = count(self.branches.values());
+        let (lines_found, lines_hit) = count(self.lines.values());
+
+        let mut records = vec![];
+        if summary.should_emit(self.functions.is_empty()) {
+            records.push(Record::FunctionsFound {
+                found: functions_found,
+            });
+            records.push(Record::FunctionsHit { hit: functions_hit });
+        }
+        if summary.should_emit(self.branches.is_empty()) {
+            records.push(Record::BranchesFound {
+                found: branches_found,
+            });
+            records.push(Record::BranchesHit { hit: branches_hit });
+        }
+        if summary.should_emit(self.lines.is_empty()) {
+            records.push(Record::LinesFound { found: lines_found });
+            records.push(Record::LinesHit { hit: lines_hit });
+        }
+        records.push(Record::EndOfRecord);
+        records
+    }
+}
+
+impl<W> RecordWriter<W>
+where
+    W: Write,
+{
+    /// Creates a new `RecordWriter`.
+    pub fn new(inner: W) -> Self {
+        RecordWriter {
+            inner,
+            summary: SummaryRecords::default(),
+            section: None,
+        }
+    }
+
+    /// Specifies when summary records are emitted.
+    ///
+    /// See [`OutputOptions::summary_records`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lcov::{Record, RecordWriter};
+    /// use lcov::report::SummaryRecords;
+    ///
+    /// let mut writer = RecordWriter::new(vec![]).summary_records(SummaryRecords::Never);
+    /// writer.write(&Record::SourceFile { path: "src/main.rs".into() })?;
+    /// writer.write(&Record::LineData { line: 1, count: 1, checksum: None })?;
+    /// writer.write(&Record::EndOfRecord)?;
+    /// let output = writer.finish()?;
+    /// assert_eq!(String::from_utf8(output)?, "SF:src/main.rs\nDA:1,1\nend_of_record\n");
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # try_main().expect("failed to run test");
+    /// # }
+    /// ```
+    ///
+    /// [`OutputOptions::summary_records`]: ../report/struct.OutputOptions.html#method.summary_records
+    pub fn summary_records(mut self, summary: SummaryRecords) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Writes a record.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutsideSection`] if a detail record or `end_of_record` is written before
+    /// any `SF` record of the section.
+    ///
+    /// [`Error::OutsideSection`]: enum.Error.html#variant.OutsideSection
+    pub fn write(&mut self, rec: &Record) -> Result<(), Error> {
+        match *rec {
+            Record::TestName { .. } | Record::SourceFile { .. } => {
+                self.end_section()?;
+                if rec.is_section_start() {
+                    self.section = Some(Totals::default());
+                }
+            }
+            Record::EndOfRecord => {
+                if self.section.is_none() {
+                    return Err(Error::OutsideSection(rec.kind()));
+                }
+                return self.end_section();
+            }
+            Record::Comment { .. } | Record::Unknown { .. } => {}
+            _ if rec.is_summary() => return Ok(()),
+            _ => match self.section {
+                Some(ref mut totals) => totals.add(rec),
+                None => return Err(Error::OutsideSection(rec.kind())),
+            },
+        }
+        rec.write_to(&mut self.inner)?;
+        writeln!(self.inner)?;
+        Ok(())
+    }
+
+    /// Ends the current section if exists, flushes the writer, and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.end_section()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn end_section(&mut self) -> Result<(), Error> {
+        if let Some(totals) = self.section.take() {
+            for rec in totals.into_records(self.summary) {
+                rec.write_to(&mut self.inner)?;
+                writeln!(self.inner)?;
+            }
+        }
+        Ok(())
+    }
+}
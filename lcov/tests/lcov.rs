@@ -1,4 +1,5 @@
 use lcov::filter::{FilterMap, LineNum};
+use lcov::report::{AnonymizeOptions, ParseOptions};
 use lcov::{Reader, Record, Report};
 use std::collections::HashMap;
 use std::fs::File;
@@ -84,6 +85,22 @@ fn is_identical_report() {
     execute().expect("error");
 }
 
+#[test]
+fn is_identical_preserved_order() {
+    fn execute() -> Result<(), Error> {
+        let options = ParseOptions::new().preserve_order(true);
+        for entry in glob::glob(FIXTURE_GLOB)? {
+            let entry = entry?;
+            let records = Reader::open_file(&entry)?.collect::<Result<Vec<_>, _>>()?;
+            let report = Report::from_file_with(&entry, &options)?;
+            assert_eq!(records, report.into_records().collect::<Vec<_>>());
+        }
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
 #[test]
 fn merge_report() {
     fn execute() -> Result<(), Error> {
@@ -153,3 +170,929 @@ fn line_filter() {
 
     execute().expect("error");
 }
+
+#[test]
+fn validate_report() {
+    fn execute() -> Result<(), Error> {
+        for entry in glob::glob(FIXTURE_GLOB)? {
+            let report = Report::from_reader(Reader::open_file(entry?)?)?;
+            assert_eq!(report.validate(), vec![]);
+        }
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn report_totals() {
+    fn execute() -> Result<(), Error> {
+        for entry in glob::glob(FIXTURE_GLOB)? {
+            let report = Report::from_reader(Reader::open_file(entry?)?)?;
+            let total = report.summary().total;
+            assert_eq!(report.total_lines_found(), total.lines.found);
+            assert_eq!(report.total_lines_hit(), total.lines.hit);
+            assert_eq!(report.total_functions_found(), total.functions.found);
+            assert_eq!(report.total_functions_hit(), total.functions.hit);
+            assert_eq!(report.total_branches_found(), total.branches.found);
+            assert_eq!(report.total_branches_hit(), total.branches.hit);
+            assert_eq!(report.len(), report.sections.len());
+            assert_eq!(report.is_empty(), report.sections.is_empty());
+        }
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn passthrough_records() -> Result<(), Error> {
+    let input = "\
+TN:
+XTOOL:version=3
+SF:foo.c
+DA:1,1
+XBLAME:1,alice
+DA:2,0
+end_of_record
+SF:bar.c
+DA:1,0
+end_of_record
+";
+    let read = |input: &str| Report::from_reader(Reader::new(input.as_bytes()).passthrough(true));
+    assert!(Report::from_reader(Reader::new(input.as_bytes())).is_err());
+
+    let mut report = read(input)?;
+    report
+        .sections
+        .retain_paths(|path| path == Path::new("foo.c"));
+    let output = report
+        .clone()
+        .into_records()
+        .map(|rec| format!("{}\n", rec))
+        .collect::<String>();
+    assert!(
+        output.ends_with("DA:2,0\nLF:2\nLH:1\nXTOOL:version=3\nXBLAME:1,alice\nend_of_record\n")
+    );
+
+    let options = ParseOptions::new().preserve_order(true);
+    let preserved =
+        Report::from_reader_with(Reader::new(input.as_bytes()).passthrough(true), &options)?;
+    let output = preserved
+        .into_records()
+        .map(|rec| format!("{}\n", rec))
+        .collect::<String>();
+    assert_eq!(output, input);
+
+    report.merge(read(input)?)?;
+    let (_, section) = report.sections.by_path("foo.c").next().unwrap();
+    assert_eq!(
+        section.unknown_records,
+        ["XTOOL:version=3", "XBLAME:1,alice"]
+    );
+    Ok(())
+}
+
+#[test]
+fn map_paths_preserve_order() -> Result<(), Error> {
+    let input = "\
+TN:
+SF:src/x/../b.c
+DA:2,1
+DA:1,0
+end_of_record
+TN:
+SF:a.c
+DA:1,1
+end_of_record
+TN:
+SF:src/b.c
+DA:3,1
+DA:1,1
+end_of_record
+";
+    let options = ParseOptions::new().preserve_order(true);
+    let mut report = Report::from_reader_with(Reader::new(input.as_bytes()), &options)?;
+    report.normalize();
+    let output = report
+        .into_records()
+        .map(|rec| format!("{}\n", rec))
+        .collect::<String>();
+    assert_eq!(
+        output,
+        "\
+TN:
+SF:src/b.c
+DA:2,1
+DA:1,1
+DA:3,1
+end_of_record
+TN:
+SF:a.c
+DA:1,1
+end_of_record
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn map_keys_conflict() -> Result<(), Error> {
+    use lcov::report::{MergeError, Resolution};
+
+    let input = "\
+TN:unit_1
+SF:a.c
+FN:3,main
+FNDA:1,main
+end_of_record
+TN:unit_2
+SF:a.c
+FN:4,main
+FNDA:2,main
+end_of_record
+";
+    let shard = |name: &str| name.trim_end_matches(|c: char| c.is_ascii_digit()).into();
+
+    let mut report = input.parse::<Report>()?;
+    let result = report.map_test_names(shard);
+    assert!(matches!(
+        result,
+        Err(MergeError::UnmatchedFunctionLine {
+            start_line: 3,
+            other_start_line: 4,
+            ..
+        })
+    ));
+    // The sections are merged anyway.
+    assert_eq!(report.sections.len(), 1);
+    let (_, section) = report.sections.iter().next().unwrap();
+    assert_eq!(section.functions.values().next().unwrap().count, 3);
+
+    let mut report = input.parse::<Report>()?;
+    report.map_test_names_resolve(shard, |_| Resolution::KeepLeft)?;
+    let (_, section) = report.sections.iter().next().unwrap();
+    assert_eq!(
+        section.functions.values().next().unwrap().start_line,
+        Some(3)
+    );
+
+    let mut report = input.parse::<Report>()?;
+    assert!(report.map_paths(|_| PathBuf::from("b.c")).is_ok());
+    assert_eq!(report.sections.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn strict_parse() {
+    fn execute() -> Result<(), Error> {
+        let options = ParseOptions::new().strict(true);
+        for entry in glob::glob(FIXTURE_GLOB)? {
+            let _ = Report::from_file_with(entry?, &options)?;
+        }
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn function_alias() {
+    fn execute() -> Result<(), Error> {
+        let input = "\
+TN:
+SF:/path/to/source/file.cpp
+FNL:0,3,5
+FNA:0,2,foo<int>
+FNA:0,0,foo<char>
+FNF:1
+FNH:1
+DA:3,2
+DA:4,2
+LF:2
+LH:2
+end_of_record
+";
+        let report = Report::from_reader(Reader::new(input.as_bytes()))?;
+        let (_, section) = report.sections.iter().next().unwrap();
+        assert_eq!(section.functions.len(), 2);
+        for value in section.functions.values() {
+            assert_eq!(value.start_line, Some(3));
+            assert_eq!(value.end_line, Some(5));
+        }
+
+        let options = ParseOptions::new().preserve_order(true);
+        let report = Report::from_reader_with(Reader::new(input.as_bytes()), &options)?;
+        let output = report
+            .into_records()
+            .map(|rec| format!("{}\n", rec))
+            .collect::<String>();
+        assert_eq!(input, output);
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn function_alias_saturating_count() {
+    fn execute() -> Result<(), Error> {
+        let input = format!(
+            "TN:\nSF:file.cpp\nFNL:0,3\nFNA:0,{0},foo\nFNA:0,{0},foo\nend_of_record\n",
+            u64::MAX
+        );
+        let report = Report::from_reader(Reader::new(input.as_bytes()))?;
+        let (_, section) = report.sections.iter().next().unwrap();
+        assert_eq!(section.functions.values().next().unwrap().count, u64::MAX);
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn build_exception_branches() {
+    fn execute() -> Result<(), Error> {
+        let mut report = Report::new();
+        let _ = report
+            .section("", "foo.cpp")
+            .add_branch(1, 0, 0, Some(1))
+            .add_exception_branch(1, 0, 0, None)
+            .add_exception_branch(1, 0, 0, Some(2));
+        let output = report
+            .clone()
+            .into_records()
+            .map(|rec| format!("{}\n", rec))
+            .collect::<String>();
+        assert!(output.contains("BRDA:1,0,0,1\n"));
+        assert!(output.contains("BRDA:1,e0,0,2\n"));
+        assert_eq!(output.parse::<Report>()?, report);
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn version() {
+    fn execute() -> Result<(), Error> {
+        let input = "\
+TN:
+SF:/path/to/source/file.rs
+VER:2a7f3c1
+DA:1,2
+LF:1
+LH:1
+end_of_record
+";
+        let report = Report::from_reader(Reader::new(input.as_bytes()))?;
+        let (_, section) = report.sections.iter().next().unwrap();
+        assert_eq!(section.version.as_deref(), Some("2a7f3c1"));
+
+        let mut merged = report.clone();
+        merged.merge(report.clone())?;
+        let (_, section) = merged.sections.iter().next().unwrap();
+        assert_eq!(section.version.as_deref(), Some("2a7f3c1"));
+
+        let output = report
+            .into_records()
+            .map(|rec| format!("{}\n", rec))
+            .collect::<String>();
+        assert_eq!(input, output);
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn comment() {
+    fn execute() -> Result<(), Error> {
+        let input = "\
+# generated by geninfo
+TN:
+SF:/path/to/source/file.rs
+# line data
+DA:1,2
+LF:1
+LH:1
+end_of_record
+# end of file
+";
+        let report = Report::from_reader(Reader::new(input.as_bytes()))?;
+        let output = report
+            .into_records()
+            .map(|rec| format!("{}\n", rec))
+            .collect::<String>();
+        assert_eq!(
+            output,
+            "TN:\nSF:/path/to/source/file.rs\nDA:1,2\nLF:1\nLH:1\nend_of_record\n"
+        );
+
+        let options = ParseOptions::new().preserve_order(true);
+        let report = Report::from_reader_with(Reader::new(input.as_bytes()), &options)?;
+        let output = report
+            .into_records()
+            .map(|rec| format!("{}\n", rec))
+            .collect::<String>();
+        assert_eq!(input, output);
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn check_thresholds() {
+    use lcov::report::summary::{Metric, Thresholds};
+
+    let mut report = Report::new();
+    let _ = report
+        .section("", "src/main.rs")
+        .add_line(1, 1)
+        .add_line(2, 0)
+        .add_branch(1, 0, 0, Some(1))
+        .add_branch(1, 0, 1, Some(0));
+    let _ = report.section("", "src/lib.rs").add_line(1, 1);
+
+    let thresholds = Thresholds::new()
+        .lines(60.0)
+        .branches(60.0)
+        .functions(100.0)
+        .file_lines(60.0);
+    let violations = report.check(&thresholds);
+    let found = violations
+        .iter()
+        .map(|v| (v.path.as_deref(), v.metric))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            (None, Metric::Branches),
+            (Some(Path::new("src/main.rs")), Metric::Lines),
+        ]
+    );
+}
+
+#[test]
+fn collect_records() {
+    fn execute() -> Result<(), Error> {
+        let records = open_fixture("report.info")?.collect::<Result<Vec<_>, _>>()?;
+        let report = records.into_iter().collect::<Report>();
+        check_report_same(report, Report::from_reader(open_fixture("report.info")?)?);
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+#[should_panic(expected = "invalid LCOV records")]
+fn collect_invalid_records() {
+    let _ = vec![
+        Record::TestName { name: "".into() },
+        Record::LinesFound { found: 0 },
+    ]
+    .into_iter()
+    .collect::<Report>();
+}
+
+#[test]
+fn collect_lossy_records() {
+    fn execute() -> Result<(), Error> {
+        use lcov::report::LossyCollect;
+
+        let mut records = vec![
+            Record::TestName { name: "".into() },
+            Record::LinesFound { found: 0 },
+            Record::EndOfRecord,
+        ];
+        records.extend(open_fixture("report.info")?.collect::<Result<Vec<_>, _>>()?);
+        records.push(Record::SourceFile {
+            path: "unterminated.c".into(),
+        });
+        let LossyCollect(report) = records.into_iter().collect();
+        check_report_same(report, Report::from_reader(open_fixture("report.info")?)?);
+
+        let mut collect = LossyCollect::default();
+        collect.extend(vec![Record::EndOfRecord]);
+        assert_eq!(collect.0, Report::new());
+        Ok(())
+    }
+
+    execute().expect("error");
+}
+
+#[test]
+fn merge_resolve() -> Result<(), Error> {
+    use lcov::report::{ConflictKind, MergeError, Resolution};
+
+    let input1 = "TN:test\nSF:foo.c\nVER:1\nDA:1,1,a\nDA:2,1,b\nend_of_record\n";
+    let input2 = "TN:test\nSF:foo.c\nVER:2\nDA:1,1,c\nDA:2,1,d\nend_of_record\n";
+
+    let mut conflicts = vec![];
+    let mut report = input1.parse::<Report>()?;
+    report.merge_resolve(input2.parse()?, |conflict| {
+        assert_eq!(conflict.key.test_name, "test");
+        assert_eq!(conflict.key.source_file, Path::new("foo.c"));
+        conflicts.push(conflict.kind.clone());
+        Resolution::KeepLeft
+    })?;
+    assert_eq!(conflicts.len(), 3);
+    assert_eq!(
+        conflicts[1],
+        ConflictKind::Checksum {
+            line: 1,
+            checksum: "a".into(),
+            other_checksum: "c".into(),
+        }
+    );
+    let output = report
+        .into_records()
+        .map(|rec| rec.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(output[2], "VER:1");
+    assert_eq!(output[3], "DA:1,2,a");
+
+    let mut report = input1.parse::<Report>()?;
+    let result = report.merge_resolve(input2.parse()?, |_| Resolution::Error);
+    assert_eq!(
+        result,
+        Err(MergeError::UnmatchedVersion {
+            key: lcov::report::section::Key {
+                test_name: "test".into(),
+                source_file: "foo.c".into(),
+            },
+            version: "1".into(),
+            other_version: "2".into(),
+        })
+    );
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "foo.c (test `test`): unmatched version of source file: `1` and `2`"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn cache() -> Result<(), Error> {
+    let report = Report::from_reader(open_fixture("report_checksum.info")?)?;
+    let mut cache = vec![];
+    report.to_cache(&mut cache)?;
+    check_report_same(Report::from_cache(&cache[..])?, report);
+
+    // Truncated caches and broken length prefixes are errors, without allocating for them.
+    assert!(Report::from_cache(&cache[..cache.len() - 3]).is_err());
+    let mut report = Report::new();
+    let _ = report.section("", "foo.c").add_line(1, 1);
+    let mut cache = vec![];
+    report.to_cache(&mut cache)?;
+    // Header (12 bytes), the number of sections (8 bytes), then the length of the test name.
+    cache[20..28].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+    assert!(Report::from_cache(&cache[..]).is_err());
+    Ok(())
+}
+
+#[test]
+fn merger() -> Result<(), Error> {
+    use lcov::report::Merger;
+
+    for merged_file in &["report_checksum.info", "report.info"] {
+        let merged_file = PathBuf::from(merged_file);
+        let init_file = merged_file.with_extension("init.info");
+        let run_file = merged_file.with_extension("run.info");
+
+        let report1 = Report::from_reader(open_fixture(merged_file)?)?;
+
+        let mut merger = Merger::new();
+        merger.merge(Report::from_reader(open_fixture(init_file)?)?)?;
+        merger.merge(Report::from_reader(open_fixture(run_file)?)?)?;
+
+        check_report_same(report1, merger.finish());
+    }
+    Ok(())
+}
+
+#[test]
+fn merge_max() -> Result<(), Error> {
+    use lcov::report::{MergeStrategy, Merger, Resolution};
+
+    for merged_file in &["report_checksum.info", "report.info"] {
+        let merged_file = PathBuf::from(merged_file);
+        let init_file = merged_file.with_extension("init.info");
+        let run_file = merged_file.with_extension("run.info");
+
+        // The counts in the initial tracefiles are all zero, so the maxima equal the sums.
+        let report1 = Report::from_reader(open_fixture(&merged_file)?)?;
+        let run = Report::from_reader(open_fixture(&run_file)?)?;
+
+        let mut report2 = Report::from_reader(open_fixture(&init_file)?)?;
+        report2.merge_with_strategy(run.clone(), MergeStrategy::Max, |_| Resolution::Error)?;
+        check_report_same(report1.clone(), report2);
+
+        let mut merger = Merger::new().strategy(MergeStrategy::Max);
+        merger.merge(Report::from_reader(open_fixture(&init_file)?)?)?;
+        merger.merge(run.clone())?;
+        check_report_same(report1, merger.finish());
+
+        // Merging a report with itself keeps the counts.
+        let mut report3 = run.clone();
+        report3.merge_with_strategy(run.clone(), MergeStrategy::Max, |_| Resolution::Error)?;
+        check_report_same(run, report3);
+    }
+    Ok(())
+}
+
+#[test]
+fn merge_shifted_function_lines() -> Result<(), Error> {
+    use lcov::report::Merger;
+
+    let gcc = "TN:\nSF:foo.h\nFN:12,foo\nFNDA:1,foo\nend_of_record\n";
+    let clang = "TN:\nSF:foo.h\nFN:11,foo\nFNDA:2,foo\nend_of_record\n";
+
+    let mut merger = Merger::new();
+    merger.merge(gcc.parse()?)?;
+    assert!(merger.merge(clang.parse()?).is_err());
+
+    for (first, second) in &[(gcc, clang), (clang, gcc)] {
+        let mut merger = Merger::new();
+        merger.merge(first.parse()?)?;
+        merger.merge_resolve(second.parse()?, |conflict| {
+            conflict.kind.resolve_function_line(1)
+        })?;
+        let report = merger.finish();
+        let (_, section) = report.sections.iter().next().unwrap();
+        let (_, function) = section.functions.iter().next().unwrap();
+        assert_eq!(function.start_line, Some(11));
+        assert_eq!(function.count, 3);
+    }
+    Ok(())
+}
+
+#[test]
+fn subtract_baseline_of_other_tests() -> Result<(), Error> {
+    let mut report = Report::new();
+    let _ = report
+        .section("unit", "a.c")
+        .add_line(1, 10)
+        .add_function("f", 1, 5);
+    let _ = report.section("integration", "a.c").add_line(1, 4);
+    let mut baseline = Report::new();
+    let _ = baseline
+        .section("seed", "a.c")
+        .add_line(1, 1)
+        .add_function("f", 1, 2);
+    let _ = baseline.section("init", "a.c").add_line(1, 2);
+
+    report.subtract(&baseline);
+    let counts = report
+        .sections
+        .iter()
+        .map(|(key, value)| {
+            (
+                key.test_name.as_str(),
+                value.lines.values().next().unwrap().count,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(counts, [("integration", 1), ("unit", 7)]);
+    assert_eq!(report.function_count("a.c", "f"), Some(3));
+    Ok(())
+}
+
+#[test]
+fn uncovered_ranges() -> Result<(), Error> {
+    let input = "TN:\nSF:a.c\nDA:120,0\nDA:121,5\nDA:122,0\nDA:124,0\nDA:130,0\nend_of_record\n";
+    let report = input.parse::<Report>()?;
+    let ranges = report
+        .uncovered_ranges(1)
+        .into_iter()
+        .map(|(path, lines)| format!("{}: {}", path.display(), lines))
+        .collect::<Vec<_>>();
+    assert_eq!(ranges, ["a.c: 120, 122-124, 130"]);
+    Ok(())
+}
+
+#[test]
+fn uncovered_functions() -> Result<(), Error> {
+    let input = "TN:\nSF:a.c\nFNDA:0,zeta\nFN:9,beta\nFNDA:0,beta\nFNDA:0,alpha\n\
+                 FN:3,gamma\nFNDA:0,gamma\nend_of_record\n";
+    let report = input.parse::<Report>()?;
+    let uncovered = report
+        .uncovered_functions()
+        .into_iter()
+        .map(|f| (f.name, f.start_line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        uncovered,
+        [
+            ("gamma".to_string(), Some(3)),
+            ("beta".to_string(), Some(9)),
+            ("alpha".to_string(), None),
+            ("zeta".to_string(), None),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn unexecuted_branches() -> Result<(), Error> {
+    use lcov::report::section::branch::State;
+    use lcov::report::{MergeStrategy, Merger};
+    use lcov::stream;
+
+    let input1 = "TN:\nSF:foo.c\nBRDA:1,0,0,-\nBRDA:1,0,1,-\nBRDA:1,0,2,0\nend_of_record\n";
+    let input2 = "TN:\nSF:foo.c\nBRDA:1,0,0,-\nBRDA:1,0,1,0\nBRDA:1,0,2,-\nend_of_record\n";
+    let expected = [State::NotExecuted, State::NotTaken, State::NotTaken];
+    let states = |report: &Report| {
+        report
+            .branches_at("foo.c", 1)
+            .values()
+            .map(|value| value.state())
+            .collect::<Vec<_>>()
+    };
+
+    let mut report = input1.parse::<Report>()?;
+    report.merge(input2.parse()?)?;
+    assert_eq!(states(&report), expected);
+
+    for strategy in [MergeStrategy::Sum, MergeStrategy::Max] {
+        let mut merger = Merger::new().strategy(strategy);
+        merger.merge(input1.parse()?)?;
+        merger.merge(input2.parse()?)?;
+        assert_eq!(states(&merger.finish()), expected, "{:?}", strategy);
+    }
+
+    let merged = stream::merge(vec![
+        Reader::new(input1.as_bytes()),
+        Reader::new(input2.as_bytes()),
+    ])
+    .collect::<Result<Report, _>>()?;
+    assert_eq!(states(&merged), expected);
+
+    let output = report
+        .into_records()
+        .map(|rec| rec.to_string())
+        .collect::<Vec<_>>();
+    assert!(output.contains(&"BRDA:1,0,0,-".to_string()));
+    assert!(output.contains(&"BRDA:1,0,1,0".to_string()));
+    Ok(())
+}
+
+#[test]
+fn stream_merge() -> Result<(), Error> {
+    use lcov::stream;
+
+    for merged_file in &["report_checksum.info", "report.info"] {
+        let merged_file = PathBuf::from(merged_file);
+        let init_file = merged_file.with_extension("init.info");
+        let run_file = merged_file.with_extension("run.info");
+
+        // The inputs of the streaming merge must be sorted.
+        let init = Report::from_reader(open_fixture(init_file)?)?;
+        let run = Report::from_reader(open_fixture(run_file)?)?;
+        let inputs = vec![init.into_records(), run.into_records()]
+            .into_iter()
+            .map(|records| records.map(Ok::<_, lcov::reader::Error>));
+        let merged = stream::merge(inputs).collect::<Result<Vec<_>, _>>()?;
+
+        let expected = Report::from_reader(open_fixture(&merged_file)?)?;
+        assert_eq!(
+            merged,
+            expected.into_records().collect::<Vec<_>>(),
+            "{}",
+            merged_file.display()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn record_writer() -> Result<(), Error> {
+    use lcov::RecordWriter;
+
+    for file in &["report_checksum.info", "report.info"] {
+        let report = Report::from_reader(open_fixture(file)?)?;
+
+        let mut writer = RecordWriter::new(vec![]);
+        for rec in report.clone().into_records() {
+            if !rec.is_summary() && !rec.is_section_end() {
+                writer.write(&rec)?;
+            }
+        }
+        let output = writer.finish()?;
+
+        let options = ParseOptions::new().strict(true);
+        let written = Report::from_reader_with(Reader::new(&output[..]), &options)?;
+        assert_eq!(written, report);
+    }
+    Ok(())
+}
+
+#[test]
+fn function_regions() -> Result<(), Error> {
+    let input = "\
+TN:
+SF:foo.c
+FN:1,5,foo
+FN:3,4,closure
+FN:10,bar
+FN:20,baz
+DA:2,1
+DA:7,0
+DA:12,1
+end_of_record
+";
+    let report = input.parse::<Report>()?;
+    let (_, section) = report.sections.iter().next().unwrap();
+    let regions = section.function_regions();
+
+    let owner = |line| regions.owner(line).map(|key| key.name.as_str());
+    assert_eq!(owner(2), Some("foo"));
+    assert_eq!(owner(3), Some("closure"));
+    assert_eq!(owner(5), Some("foo"));
+    assert_eq!(owner(7), None);
+    assert_eq!(owner(12), Some("bar"));
+    assert_eq!(owner(25), Some("baz"));
+    assert_eq!(regions.lines_of("bar"), Some(10..=19));
+    assert_eq!(
+        regions
+            .iter()
+            .map(|(key, _)| &key.name[..])
+            .collect::<Vec<_>>(),
+        ["foo", "closure", "bar", "baz"]
+    );
+    Ok(())
+}
+
+#[test]
+fn trend() -> Result<(), Error> {
+    use lcov::report::summary::{Metric, Trend};
+
+    let inputs = [
+        "TN:\nSF:a.c\nDA:1,1\nDA:2,1\nend_of_record\n",
+        "TN:\nSF:a.c\nDA:1,1\nDA:2,0\nend_of_record\nTN:\nSF:b.c\nBRDA:1,0,0,1\nDA:1,0\nend_of_record\n",
+        "TN:\nSF:b.c\nBRDA:1,0,0,1\nDA:1,1\nend_of_record\n",
+    ];
+    let trend = inputs
+        .iter()
+        .map(|input| Ok(input.parse::<Report>()?.summary()))
+        .collect::<Result<Trend, Error>>()?;
+
+    assert_eq!(trend.len(), 3);
+    assert_eq!(trend.paths().collect::<Vec<_>>(), ["a.c", "b.c"]);
+    assert_eq!(trend.file("b.c").unwrap()[0], None);
+    assert!(trend.file("a.c").unwrap()[2].is_none());
+
+    let deltas = trend.file_deltas();
+    assert_eq!(deltas[Path::new("a.c")][0].unwrap().lines, Some(-50.0));
+    assert_eq!(deltas[Path::new("a.c")][1], None);
+    assert_eq!(deltas[Path::new("b.c")][0], None);
+    assert_eq!(deltas[Path::new("b.c")][1].unwrap().lines, Some(100.0));
+    assert_eq!(deltas[Path::new("b.c")][1].unwrap().branches, Some(0.0));
+
+    let total = trend.total_deltas();
+    assert_eq!(total[0].branches, None);
+    assert!(trend.decreasing_files(Metric::Lines) == [Path::new("a.c")]);
+    assert!(!trend.is_decreasing(Metric::Lines));
+    assert!(!trend.is_decreasing(Metric::Branches));
+    Ok(())
+}
+
+#[test]
+fn line_terminators() -> Result<(), Error> {
+    use lcov::reader::Recovery;
+
+    let expected = "TN:\nSF:foo.c\nDA:1,1\nDA:2,0\nend_of_record\n"
+        .parse::<Report>()?
+        .into_records()
+        .collect::<Vec<_>>();
+    let inputs = [
+        "\u{feff}TN:\r\nSF:foo.c\r\nDA:1,1\r\nDA:2,0\r\nend_of_record\r\n",
+        "TN:\rSF:foo.c\rDA:1,1\rDA:2,0\rend_of_record",
+        "\u{feff}TN:\nSF:foo.c\r\nDA:1,1\rDA:2,0\r\nend_of_record\r",
+    ];
+    for input in inputs {
+        // Reads a byte at a time, so that line terminators are split between buffers.
+        let reader = Reader::new(BufReader::with_capacity(1, input.as_bytes()));
+        let records = reader.collect::<Result<Vec<_>, _>>()?;
+        let report = Report::from_reader(records.into_iter().map(Ok::<_, lcov::reader::Error>))?;
+        assert_eq!(report.into_records().collect::<Vec<_>>(), expected);
+    }
+
+    // Every `\r` terminates a line, except that it forms one terminator with a following `\n`.
+    for input in ["A\r\r\nB\r\rC", "A\n\nB\r\n\r\nC"] {
+        let reader =
+            Reader::new(BufReader::with_capacity(1, input.as_bytes())).on_error(|line, text, _| {
+                Recovery::Replace(Record::Comment {
+                    text: format!("{}:{}", line, text),
+                })
+            });
+        let records = reader.collect::<Result<Vec<_>, _>>()?;
+        let lines = records
+            .iter()
+            .map(|rec| rec.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, ["#1:A", "#2:", "#3:B", "#4:", "#5:C"]);
+    }
+
+    let mut reader = Reader::new("TN:\n\u{feff}SF:foo.c\n".as_bytes());
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_err());
+    Ok(())
+}
+
+#[test]
+fn invalid_utf8_recovery() -> Result<(), Error> {
+    use lcov::reader::Recovery;
+    use lcov::record::ParseRecordError;
+
+    fn assert_sync<T: Sync>(_: &T) {}
+
+    let input = &b"TN:\nSF:foo.c\nDA:1,1\nXX:caf\xe9\nend_of_record\n"[..];
+    let reader = Reader::new(input).on_error(|line, text, error| {
+        assert!(matches!(error, ParseRecordError::InvalidUtf8(_)));
+        Recovery::Replace(Record::Comment {
+            text: format!("{}:{}", line, text),
+        })
+    });
+    assert_sync(&reader);
+    let records = reader.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        records[3],
+        Record::Comment {
+            text: "4:XX:caf\u{fffd}".into()
+        }
+    );
+
+    let reader = Reader::new(input).on_error(|_, _, _| Recovery::Skip);
+    assert_eq!(reader.collect::<Result<Vec<_>, _>>()?.len(), 4);
+    Ok(())
+}
+
+#[test]
+fn reader_location() {
+    use lcov::reader::{Error as ReadError, Recovery};
+    use lcov::record::ParseRecordError;
+
+    let input = "TN:\r\nXX:skipped\r\nSF:foo.c\r\nDA:x,1\r\n";
+    let mut reader = Reader::new(BufReader::with_capacity(3, input.as_bytes()))
+        .name("<network>")
+        .on_error(|_, text, _| {
+            if text.starts_with("XX:") {
+                Recovery::Skip
+            } else {
+                Recovery::Abort
+            }
+        });
+    assert_eq!(reader.input_name(), Some("<network>"));
+    assert!(reader.by_ref().take(2).all(|rec| rec.is_ok()));
+    assert_eq!(reader.line(), 3);
+    match reader.next() {
+        Some(Err(ReadError::Labeled {
+            name,
+            offset,
+            error,
+        })) => {
+            assert_eq!(name, "<network>");
+            assert_eq!(offset, 27);
+            assert!(matches!(
+                error.unlabeled(),
+                ReadError::ParseRecord(4, ParseRecordError::ParseIntError("line", _))
+            ));
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn anonymize() -> Result<(), Error> {
+    let input = "\
+TN:unit
+SF:/src/acme/foo.c
+DA:1,1,c2f3
+XBLAME:1,alice
+end_of_record
+TN:unit
+SF:/build/acme/foo.c
+DA:2,0,a8e1
+end_of_record
+";
+    let mut report = Report::from_reader(Reader::new(input.as_bytes()).passthrough(true))?;
+    let options = AnonymizeOptions::new()
+        .strip_prefix("/src/acme")
+        .strip_prefix("/build/acme");
+    report.anonymize(&options);
+
+    assert_eq!(report.len(), 1);
+    let (key, section) = report.sections.iter().next().unwrap();
+    assert_eq!(key.source_file, Path::new("foo.c"));
+    assert_eq!(key.test_name.len(), 16);
+    assert_ne!(key.test_name, "unit");
+    assert!(section.unknown_records.is_empty());
+    assert!(section.lines.values().all(|line| line.checksum.is_none()));
+    assert_eq!(report.total_lines_found(), 2);
+    Ok(())
+}
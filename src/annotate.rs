@@ -0,0 +1,190 @@
+//! Rendering source files annotated with execution counts, like `gcov -t`.
+use lcov::report::section;
+use lcov::Report;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Options to render annotated source files.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Directory which relative paths of source files are resolved from.
+    pub source_dir: Option<PathBuf>,
+}
+
+/// Writes an annotated source file for each source file of the report into `dir`.
+///
+/// Files are named after the paths of the source files, like `gcov --preserve-paths`:
+/// `/` is replaced with `#` and `..` with `^`, e.g. `src#main.c.gcov` and `#usr#x.c.gcov`.
+/// If names still collide, e.g. for paths containing `#`, `~2`, `~3`, ... are appended to the
+/// later ones, e.g. `src#main.c~2.gcov`. Returns the paths of the files written.
+pub fn render(report: &Report, dir: &Path, options: &Options) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    let mut names = HashSet::new();
+    for (path, value) in report.sections.merge_by_file() {
+        let base = file_name(path);
+        let mut name = format!("{}.gcov", base);
+        let mut n = 1;
+        while !names.insert(name.clone()) {
+            n += 1;
+            name = format!("{}~{}.gcov", base, n);
+        }
+        let out = dir.join(name);
+        let mut writer = io::BufWriter::new(fs::File::create(&out)?);
+        write(&mut writer, path, &value, options)?;
+        writer.flush()?;
+        written.push(out);
+    }
+    Ok(written)
+}
+
+/// Writes the annotated source file of all source files of the report to `writer`, one after
+/// another.
+pub fn render_to<W>(report: &Report, writer: &mut W, options: &Options) -> io::Result<()>
+where
+    W: Write,
+{
+    for (path, value) in report.sections.merge_by_file() {
+        write(writer, path, &value, options)?;
+    }
+    Ok(())
+}
+
+/// Writes the annotated source file.
+///
+/// Each line is prefixed with the execution count, `#####` for lines not executed, or `-` for
+/// lines without coverage information, and the line number. If the source file cannot be read,
+/// only the lines with coverage information are written, without their text.
+pub fn write<W>(
+    writer: &mut W,
+    path: &Path,
+    value: &section::Value,
+    options: &Options,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let source_path = match &options.source_dir {
+        Some(dir) => dir.join(path),
+        None => path.into(),
+    };
+    let source = fs::read_to_string(source_path).ok();
+    let source_lines = source
+        .as_deref()
+        .map(|s| s.lines().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    writeln!(writer, "{:>9}:{:>5}:Source:{}", "-", 0, path.display())?;
+    let last = value
+        .lines
+        .keys()
+        .map(|key| key.line as usize)
+        .max()
+        .unwrap_or(0)
+        .max(source_lines.len());
+    for line in 1..=last {
+        let data = value.lines.get(&section::line::Key { line: line as u32 });
+        if data.is_none() && source.is_none() {
+            continue;
+        }
+        let count = match data {
+            Some(data) if data.count > 0 => data.count.to_string(),
+            Some(_) => "#####".into(),
+            None => "-".into(),
+        };
+        let text = source_lines.get(line - 1).copied().unwrap_or("");
+        writeln!(writer, "{:>9}:{:>5}:{}", count, line, text)?;
+    }
+    Ok(())
+}
+
+// Returns the file name without the `.gcov` extension. The root directory is kept as an empty
+// component, so absolute paths start with `#` and don't collide with relative ones.
+fn file_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy()),
+            Component::ParentDir => Some("^".into()),
+            Component::RootDir => Some("".into()),
+            Component::Prefix(prefix) => Some(
+                prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .replace(':', "~")
+                    .into(),
+            ),
+            Component::CurDir => None,
+        })
+        .collect::<Vec<_>>()
+        .join("#")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotate() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("main.c"),
+            "int main() {\n  foo();\n  bar();\n}\n",
+        )
+        .unwrap();
+
+        let mut report = Report::new();
+        let _ = report
+            .section("", "main.c")
+            .add_line(1, 1)
+            .add_line(2, 1)
+            .add_line(3, 0);
+        let options = Options {
+            source_dir: Some(dir.path().into()),
+        };
+        let mut output = vec![];
+        render_to(&report, &mut output, &options).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "        -:    0:Source:main.c\n\
+             \x20       1:    1:int main() {\n\
+             \x20       1:    2:  foo();\n\
+             \x20   #####:    3:  bar();\n\
+             \x20       -:    4:}\n"
+        );
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_name(Path::new("src/main.c")), "src#main.c");
+        assert_eq!(
+            file_name(Path::new("/usr/include/stdio.h")),
+            "#usr#include#stdio.h"
+        );
+        assert_eq!(file_name(Path::new("../lib/./foo.c")), "^#lib#foo.c");
+    }
+
+    #[test]
+    fn colliding_file_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut report = Report::new();
+        let _ = report.section("", "/usr/x.c").add_line(1, 1);
+        let _ = report.section("", "usr/x.c").add_line(1, 2);
+        let _ = report.section("", "usr#x.c").add_line(1, 3);
+
+        let written = render(&report, dir.path(), &Options::default()).unwrap();
+        let names = written
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["#usr#x.c.gcov", "usr#x.c.gcov", "usr#x.c~2.gcov"]);
+        let content = fs::read_to_string(&written[2]).unwrap();
+        assert!(
+            content.starts_with("        -:    0:Source:usr#x.c\n"),
+            "{}",
+            content
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Utility commands to operate and analyze LCOV trace file at blazingly fast.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+use clap::{CommandFactory, Parser};
+use lcov::report::ParseOptions;
+use lcov_util::config::Config;
+use lcov_util::error::ErrorOutput;
+use lcov_util::generate::Generate;
+use lcov_util::paths::PathOptions;
+use lcov_util::rc::Rc;
+use lcov_util::{annotate, input};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    about = "Writes source files annotated with execution counts, like `gcov -t`",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
+struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// Reads the default options from FILE instead of `lcov.toml`
+    #[clap(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(flatten)]
+    rc: Rc,
+
+    #[clap(flatten)]
+    paths: PathOptions,
+
+    /// Disables varidation such as checksum checking
+    #[clap(long = "loose")]
+    loose: bool,
+
+    /// Writes the annotated files to DIR
+    #[clap(
+        short = 'o',
+        long = "output-directory",
+        value_name = "DIR",
+        default_value = ".",
+        conflicts_with = "stdout"
+    )]
+    output_directory: PathBuf,
+
+    /// Writes all annotated files to the standard output
+    #[clap(long = "stdout")]
+    stdout: bool,
+
+    /// Resolves relative paths of source files from DIR
+    #[clap(short = 's', long = "source-directory", value_name = "DIR")]
+    source_directory: Option<PathBuf>,
+
+    /// LCOV tracefiles to annotate
+    ///
+    /// `-` means the standard input. Directories are searched recursively for `*.info` files,
+    /// and glob patterns (e.g. `coverage/**/*.info`) are expanded.
+    #[clap(name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = Config::load(opt.config.as_deref())?;
+    opt.rc.apply(&mut config)?;
+    opt.paths.apply(&mut config);
+    config.loose |= opt.loose;
+    let inputs = input::collect(&opt.files, false)?;
+    let mut report = input::read_merged(&inputs, &ParseOptions::new(), config.loose)?;
+    config.apply(&mut report)?;
+
+    let options = annotate::Options {
+        source_dir: opt.source_directory,
+    };
+    if opt.stdout {
+        let stdout = io::stdout();
+        let mut writer = io::BufWriter::new(stdout.lock());
+        annotate::render_to(&report, &mut writer, &options)?;
+        writer.flush()?;
+    } else {
+        let _ = annotate::render(&report, &opt.output_directory, &options)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let opt = Opt::parse();
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    if let Err(e) = run(opt) {
+        errors.print(&*e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,117 @@
+//! Utility commands to operate and analyze LCOV trace file at blazingly fast.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+use clap::{CommandFactory, Parser};
+use lcov::report::ParseOptions;
+use lcov_util::config::Config;
+use lcov_util::error::ErrorOutput;
+use lcov_util::generate::Generate;
+use lcov_util::paths::PathOptions;
+use lcov_util::rc::Rc;
+use lcov_util::{cobertura, input};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    about = "Converts LCOV tracefiles into Cobertura XML for GitLab coverage visualization",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
+struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// Reads the default options from FILE instead of `lcov.toml`
+    #[clap(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(flatten)]
+    rc: Rc,
+
+    #[clap(flatten)]
+    paths: PathOptions,
+
+    /// Disables varidation such as checksum checking
+    #[clap(long = "loose")]
+    loose: bool,
+
+    /// Writes the XML to FILE instead of the standard output
+    #[clap(short = 'o', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Root directory of the project, which paths of source files are relative to
+    /// [default: the current directory]
+    ///
+    /// On GitLab CI, this is usually `$CI_PROJECT_DIR`.
+    #[clap(long = "source-root", value_name = "DIR")]
+    source_root: Option<PathBuf>,
+
+    /// LCOV tracefiles to convert
+    ///
+    /// `-` means the standard input. Directories are searched recursively for `*.info` files,
+    /// and glob patterns (e.g. `coverage/**/*.info`) are expanded.
+    #[clap(name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = Config::load(opt.config.as_deref())?;
+    opt.rc.apply(&mut config)?;
+    opt.paths.apply(&mut config);
+    config.loose |= opt.loose;
+    let inputs = input::collect(&opt.files, false)?;
+    let mut report = input::read_merged(&inputs, &ParseOptions::new(), config.loose)?;
+    config.apply(&mut report)?;
+
+    let options = cobertura::Options {
+        source_root: match opt.source_root {
+            Some(dir) => dir,
+            None => env::current_dir()?,
+        },
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+    };
+    let mut writer: Box<dyn Write> = match &opt.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    cobertura::write(&report, &mut writer, &options)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn main() {
+    let opt = Opt::parse();
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    if let Err(e) = run(opt) {
+        errors.print(&*e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,161 @@
+//! Utility commands to operate and analyze LCOV trace file at blazingly fast.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+use clap::{CommandFactory, Parser};
+use lcov::filter::{FilterMap, MinCount, SectionFilter};
+use lcov::report::ParseOptions;
+use lcov_util::config::Config;
+use lcov_util::error::ErrorOutput;
+use lcov_util::generate::Generate;
+use lcov_util::input::Input;
+use lcov_util::lines::FileLines;
+use lcov_util::output;
+use lcov_util::paths::PathOptions;
+use lcov_util::rc::Rc;
+use lcov_util::wildcard::Wildcard;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    about = "Filters LCOV tracefile",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
+struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// Reads the default options from FILE instead of `lcov.toml`
+    #[clap(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(flatten)]
+    rc: Rc,
+
+    #[clap(flatten)]
+    paths: PathOptions,
+
+    /// Keeps only source files matching PATTERN (can be specified multiple times)
+    ///
+    /// PATTERN is a shell wildcard matched against the whole path, as `lcov --extract` does:
+    /// `*` matches any characters including `/`, `?` matches any single character, and the others
+    /// match themselves.
+    #[clap(short = 'e', long = "extract", value_name = "PATTERN")]
+    extract: Vec<Wildcard>,
+
+    /// Removes source files matching PATTERN (can be specified multiple times)
+    ///
+    /// PATTERN is interpreted in the same way as --extract.
+    #[clap(short = 'r', long = "remove", value_name = "PATTERN")]
+    remove: Vec<Wildcard>,
+
+    /// Keeps only the specified lines of a source file, e.g. `src/main.rs:1-5,10,20-`
+    ///
+    /// Source files not specified by --lines or --diff are removed.
+    #[clap(long = "lines", value_name = "FILE:RANGES")]
+    lines: Vec<String>,
+
+    /// Keeps only the lines added or modified by a unified diff
+    ///
+    /// Source files not specified by --lines or --diff are removed.
+    #[clap(long = "diff", value_name = "FILE")]
+    diff: Vec<PathBuf>,
+
+    /// Removes lines (and branches on them) executed fewer than N times
+    #[clap(long = "min-count", value_name = "N")]
+    min_count: Option<u64>,
+
+    /// Removes branch coverage information
+    #[clap(long = "no-branches")]
+    no_branches: bool,
+
+    /// Removes function coverage information
+    #[clap(long = "no-functions")]
+    no_functions: bool,
+
+    /// Removes checksums of lines
+    #[clap(long = "no-checksums")]
+    no_checksums: bool,
+
+    /// Writes the filtered tracefile to FILE instead of the standard output
+    #[clap(short = 'o', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// LCOV tracefile to filter (`-` for the standard input)
+    #[clap(name = "FILE", required = true)]
+    file: Option<PathBuf>,
+}
+
+fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut lines = FileLines::new();
+    for spec in &opt.lines {
+        lines.add_spec(spec)?;
+    }
+    for path in &opt.diff {
+        lines.add_diff(&fs::read_to_string(path)?);
+    }
+
+    let mut config = Config::load(opt.config.as_deref())?;
+    opt.rc.apply(&mut config)?;
+    opt.paths.apply(&mut config);
+    config.filter.extract.extend(opt.extract);
+    config.filter.remove.extend(opt.remove);
+    config.filter.no_branches |= opt.no_branches;
+    config.filter.no_functions |= opt.no_functions;
+    config.filter.no_checksums |= opt.no_checksums;
+
+    let file = opt
+        .file
+        .as_deref()
+        .expect("FILE is required without subcommands");
+    let mut report = Input::from_arg(file).read_report(&ParseOptions::new())?;
+    config.apply(&mut report)?;
+    if !lines.is_empty() {
+        report.sections.filter_map(|(key, mut value)| {
+            lines.get(&key.source_file)?.apply(&mut value);
+            if value.is_empty() {
+                None
+            } else {
+                Some((key, value))
+            }
+        });
+    }
+    if let Some(min) = opt.min_count {
+        MinCount::new(min).filter_sections(&mut report.sections);
+    }
+
+    output::write_records(opt.output.as_deref(), report.into_records())?;
+
+    Ok(())
+}
+
+fn main() {
+    let opt = Opt::parse();
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    if let Err(e) = run(opt) {
+        errors.print(&*e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,113 @@
+//! Utility commands to operate and analyze LCOV trace file at blazingly fast.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+use clap::{CommandFactory, Parser};
+use lcov::report::ParseOptions;
+use lcov_util::config::Config;
+use lcov_util::error::ErrorOutput;
+use lcov_util::generate::Generate;
+use lcov_util::paths::PathOptions;
+use lcov_util::rc::Rc;
+use lcov_util::{html, input};
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    about = "Generates HTML pages from LCOV tracefiles",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
+struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// Reads the default options from FILE instead of `lcov.toml`
+    #[clap(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(flatten)]
+    rc: Rc,
+
+    #[clap(flatten)]
+    paths: PathOptions,
+
+    /// Disables varidation such as checksum checking
+    #[clap(long = "loose")]
+    loose: bool,
+
+    /// Writes the HTML pages to DIR
+    #[clap(
+        short = 'o',
+        long = "output-directory",
+        value_name = "DIR",
+        default_value = "."
+    )]
+    output_directory: PathBuf,
+
+    /// Title of the pages [default: LCOV coverage report]
+    #[clap(short = 't', long = "title")]
+    title: Option<String>,
+
+    /// Removes PREFIX from the paths of source files in the pages
+    #[clap(short = 'p', long = "prefix", value_name = "PREFIX")]
+    prefix: Option<PathBuf>,
+
+    /// LCOV tracefiles to render
+    ///
+    /// `-` means the standard input. Directories are searched recursively for `*.info` files,
+    /// and glob patterns (e.g. `coverage/**/*.info`) are expanded.
+    #[clap(name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = Config::load(opt.config.as_deref())?;
+    opt.rc.apply(&mut config)?;
+    opt.paths.apply(&mut config);
+    config.loose |= opt.loose;
+    let inputs = input::collect(&opt.files, false)?;
+    let mut report = input::read_merged(&inputs, &ParseOptions::new(), config.loose)?;
+    config.apply(&mut report)?;
+
+    let options = html::Options {
+        title: opt
+            .title
+            .or(config.html.title)
+            .unwrap_or_else(|| "LCOV coverage report".into()),
+        prefix: opt.prefix.or(config.html.prefix),
+        cancellation: None,
+    };
+    html::render(&report, &opt.output_directory, &options)?;
+
+    Ok(())
+}
+
+fn main() {
+    let opt = Opt::parse();
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    if let Err(e) = run(opt) {
+        errors.print(&*e);
+        process::exit(1);
+    }
+}
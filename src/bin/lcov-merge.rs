@@ -10,46 +10,344 @@
 #![warn(unused_qualifications)]
 #![warn(unused_results)]
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use lcov::checksum;
+use lcov::report::{MergeStrategy, ParseOptions};
 use lcov::Report;
-use std::path::PathBuf;
+use lcov_util::config::Config;
+use lcov_util::error::ErrorOutput;
+use lcov_util::gate::{FailUnder, EXIT_FAIL_UNDER};
+use lcov_util::generate::Generate;
+use lcov_util::input::Input;
+use lcov_util::paths::PathOptions;
+use lcov_util::rc::Rc;
+use lcov_util::watch::Snapshot;
+use lcov_util::{html, input, missing, output};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::time::Duration;
+
+// Interval of polling the tracefiles in the watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
 
 #[derive(Debug, clap::Parser)]
-#[clap(about = "Merges LCOV tracefiles")]
+#[clap(
+    about = "Merges LCOV tracefiles",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
 struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// Reads the default options from FILE instead of `lcov.toml`
+    #[clap(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(flatten)]
+    rc: Rc,
+
+    #[clap(flatten)]
+    paths: PathOptions,
+
     /// Disables varidation such as checksum checking
     #[structopt(long = "loose")]
     loose: bool,
 
+    /// Writes the merged tracefile to FILE instead of the standard output
+    ///
+    /// The file is replaced atomically. If FILE ends with `.gz`, the output is compressed with gzip.
+    #[clap(short = 'o', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Writes a tracefile for each source file or directory into DIR instead of a single one
+    ///
+    /// Tracefiles are named after the source files (e.g. `DIR/src/main.rs.info`) for `file`, and
+    /// written as `coverage.info` in the directories (e.g. `DIR/src/coverage.info`) for
+    /// `directory`. Root and `..` components of absolute and relative paths are replaced, so the
+    /// tracefiles are always written under DIR. Source files mapped to the same tracefile this
+    /// way are written together.
+    #[clap(
+        long = "split",
+        value_enum,
+        value_name = "MODE",
+        requires = "output_directory",
+        conflicts_with_all = ["output", "watch"]
+    )]
+    split: Option<Split>,
+
+    /// Writes the tracefiles split by --split into DIR
+    #[clap(long = "output-directory", value_name = "DIR", requires = "split")]
+    output_directory: Option<PathBuf>,
+
+    /// How execution counts of the same line, function or branch are combined
+    #[clap(
+        long = "strategy",
+        value_enum,
+        value_name = "STRATEGY",
+        default_value = "sum"
+    )]
+    strategy: Strategy,
+
+    /// Merges start lines of a function differing by at most N lines into the smaller one
+    #[clap(long = "function-line-tolerance", value_name = "N")]
+    function_line_tolerance: Option<u32>,
+
+    /// Number of threads to parse tracefiles (defaults to the number of CPUs)
+    #[clap(short = 'j', long = "jobs", value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Merges the tracefiles again whenever any of them changes, until interrupted
+    ///
+    /// Directories and glob patterns are searched again, so new tracefiles are also merged.
+    #[clap(long = "watch", conflicts_with = "stdin_list")]
+    watch: bool,
+
+    /// Also renders the merged report as HTML pages into DIR, like `lcov-html`
+    #[clap(long = "html", value_name = "DIR")]
+    html: Option<PathBuf>,
+
+    /// Reads newline-separated paths of LCOV tracefiles from the standard input
+    #[clap(long = "stdin-list")]
+    stdin_list: bool,
+
+    /// Rewrites the paths of source files by the `[[remap]]` tables in FILE
+    ///
+    /// The rewrites are tried after the ones in the configuration file, and the first one which
+    /// applies to a path is used.
+    #[clap(long = "remap", value_name = "FILE")]
+    remap: Vec<PathBuf>,
+
+    /// Adds zero-coverage sections for source files in SRCDIR which appear in no tracefile
+    #[clap(long = "add-missing", value_name = "SRCDIR", requires = "include")]
+    add_missing: Option<PathBuf>,
+
+    /// Adds only source files matching GLOB (relative to SRCDIR) by --add-missing
+    #[clap(long = "include", value_name = "GLOB", requires = "add_missing")]
+    include: Vec<glob::Pattern>,
+
+    /// Subtracts the execution counts in the tracefile FILE from the merged ones
+    ///
+    /// The counts of all tests in FILE are subtracted from every test, matched by paths of source
+    /// files after the paths are rewritten. Counts saturate at zero.
+    #[clap(long = "baseline", value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Ignores checksums of lines in the tracefiles
+    ///
+    /// Tracefiles generated from different revisions of the source files can be merged.
+    #[clap(
+        long = "no-checksum",
+        alias = "no-checksums",
+        conflicts_with = "checksum"
+    )]
+    no_checksum: bool,
+
+    /// Computes missing checksums of lines and verifies the existing ones against the source files
+    ///
+    /// Fails if any checksum doesn't match.
+    #[clap(long = "checksum")]
+    checksum: bool,
+
+    /// Resolves relative paths of source files against DIR for --checksum [default: .]
+    #[clap(long = "source-directory", value_name = "DIR", requires = "checksum")]
+    source_directory: Option<PathBuf>,
+
+    /// Drops sections which have no lines, functions nor branches
+    #[clap(long = "prune-empty")]
+    prune_empty: bool,
+
+    /// Drops lines, functions and branches which are never executed
+    ///
+    /// Sections left without any of them are also dropped. The output no longer reports the
+    /// uncovered code, so it should not be used to compute coverage rates. --fail-under checks the
+    /// report before pruning.
+    #[clap(long = "prune-uncovered")]
+    prune_uncovered: bool,
+
+    #[clap(flatten)]
+    fail_under: FailUnder,
+
     /// LCOV tracefiles to merge
+    ///
+    /// `-` means the standard input. Directories are searched recursively for `*.info` files,
+    /// and glob patterns (e.g. `coverage/**/*.info`) are expanded.
     #[structopt(name = "FILE")]
     files: Vec<PathBuf>,
 }
 
-fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error>> {
-    let mut merged_report = Report::new();
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Strategy {
+    /// Sum of the counts, as `lcov --add-tracefile` does
+    Sum,
+    /// Maximum of the counts, e.g. to select representative runs or fuzzing inputs
+    Max,
+}
 
-    for path in &opt.files {
-        let report = Report::from_file(path)?;
-        if opt.loose {
-            merged_report.merge_lossy(report);
-        } else {
-            merged_report.merge(report)?;
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Split {
+    /// One tracefile for each source file
+    File,
+    /// One tracefile for each directory of the source files
+    Directory,
+}
+
+fn run(opt: Opt) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(jobs) = opt.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+
+    let mut config = Config::load(opt.config.as_deref())?;
+    opt.rc.apply(&mut config)?;
+    opt.paths.apply(&mut config);
+    config.loose |= opt.loose;
+    for path in &opt.remap {
+        config.load_remap(path)?;
+    }
+
+    if !opt.watch {
+        return merge(&opt, &config);
+    }
+    // The output is not watched, as it may be written into a watched directory.
+    let ignored = opt.output.iter().cloned().collect::<Vec<_>>();
+    let mut snapshot = Snapshot::take(&opt.files, &ignored);
+    loop {
+        // Errors such as a malformed tracefile are reported, and fixed ones are merged next time.
+        if let Err(e) = merge(&opt, &config) {
+            opt.errors.print(&*e);
         }
+        eprintln!("waiting for changes of the tracefiles...");
+        snapshot = snapshot.wait_for_change(&opt.files, &ignored, WATCH_INTERVAL);
     }
+}
 
-    for rec in merged_report.into_records() {
-        println!("{}", rec);
+fn merge(opt: &Opt, config: &Config) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let inputs = input::collect(&opt.files, opt.stdin_list)?;
+    let options =
+        ParseOptions::new().ignore_checksums(opt.no_checksum || config.filter.no_checksums);
+    let strategy = match opt.strategy {
+        Strategy::Sum => MergeStrategy::Sum,
+        Strategy::Max => MergeStrategy::Max,
+    };
+    let mut merged_report = input::read_merged_with(
+        &inputs,
+        &options,
+        config.loose,
+        strategy,
+        opt.function_line_tolerance,
+    )?;
+    if let Some(dir) = &opt.add_missing {
+        let _ = missing::add_missing(&mut merged_report, dir, &opt.include)?;
+    }
+    config.apply(&mut merged_report)?;
+    if let Some(path) = &opt.baseline {
+        let mut baseline = Input::from_arg(path).read_report(&options)?;
+        config.apply(&mut baseline)?;
+        merged_report.subtract(&baseline);
+    }
+    if opt.checksum {
+        let dir = opt.source_directory.as_deref().unwrap_or(Path::new("."));
+        let mismatches = checksum::apply(&mut merged_report, dir)?;
+        for mismatch in &mismatches {
+            eprintln!(
+                "{}:{}: checksum mismatch",
+                mismatch.source_file.display(),
+                mismatch.line
+            );
+        }
+        if !mismatches.is_empty() {
+            return Err(format!(
+                "{} checksum(s) don't match the source files",
+                mismatches.len()
+            )
+            .into());
+        }
     }
 
+    // Computing the summary is skipped unless needed, as it costs as much as merging.
+    let summary = opt
+        .fail_under
+        .is_enabled(&config.thresholds)
+        .then(|| merged_report.summary());
+    if opt.prune_empty {
+        merged_report.retain_sections(|_, value| !value.is_empty());
+    }
+    if opt.prune_uncovered {
+        merged_report.retain_lines(|_, _, line| line.count > 0);
+        merged_report.retain_functions(|_, _, function| function.count > 0);
+        merged_report.retain_branches(|_, _, branch| branch.taken.unwrap_or(0) > 0);
+    }
+    if let Some(dir) = &opt.html {
+        let options = html::Options {
+            title: config
+                .html
+                .title
+                .clone()
+                .unwrap_or_else(|| "LCOV coverage report".into()),
+            prefix: config.html.prefix.clone(),
+            cancellation: None,
+        };
+        html::render(&merged_report, dir, &options)?;
+    }
+    match (opt.split, &opt.output_directory) {
+        (Some(split), Some(dir)) => write_split(merged_report, split, dir)?,
+        _ => output::write_records(opt.output.as_deref(), merged_report.into_records())?,
+    }
+
+    Ok(summary.map_or(true, |summary| {
+        opt.fail_under.check(&config.thresholds, &summary)
+    }))
+}
+
+fn write_split(report: Report, split: Split, dir: &Path) -> io::Result<()> {
+    let reports = match split {
+        Split::File => report.split_by_file(),
+        Split::Directory => report.split_by_directory(None),
+    };
+    let reports = output::group_by_output(reports, |path| {
+        let path = dir.join(output::relative_path(path));
+        match split {
+            Split::File => {
+                let mut path = path.into_os_string();
+                path.push(".info");
+                PathBuf::from(path)
+            }
+            Split::Directory => path.join("coverage.info"),
+        }
+    });
+    for (path, report) in reports {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        output::write_records(Some(&path), report.into_records())?;
+    }
     Ok(())
 }
 
 fn main() {
     let opt = Opt::parse();
-    if let Err(e) = run(opt) {
-        eprintln!("{}", e);
-        process::exit(1);
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    match run(opt) {
+        Ok(true) => {}
+        Ok(false) => process::exit(EXIT_FAIL_UNDER),
+        Err(e) => {
+            errors.print(&*e);
+            process::exit(1);
+        }
     }
 }
@@ -0,0 +1,237 @@
+//! Utility commands to operate and analyze LCOV trace file at blazingly fast.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+use clap::ValueEnum;
+use clap::{CommandFactory, Parser};
+use lcov::report::summary::SortBy;
+use lcov::report::ParseOptions;
+use lcov_util::config::Config;
+use lcov_util::error::ErrorOutput;
+use lcov_util::gate::{FailUnder, EXIT_FAIL_UNDER};
+use lcov_util::generate::Generate;
+use lcov_util::input;
+use lcov_util::paths::PathOptions;
+use lcov_util::rc::Rc;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    about = "Prints coverage summary of LCOV tracefiles",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
+struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// Reads the default options from FILE instead of `lcov.toml`
+    #[clap(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(flatten)]
+    rc: Rc,
+
+    #[clap(flatten)]
+    paths: PathOptions,
+
+    /// Disables varidation such as checksum checking
+    #[clap(long = "loose")]
+    loose: bool,
+
+    /// Order of source files [default: path]
+    #[clap(long = "sort", value_name = "KEY", value_enum)]
+    sort: Option<Sort>,
+
+    /// Aggregates source files into their directories
+    ///
+    /// Directories are truncated to the first DEPTH components if specified.
+    #[clap(
+        long = "by-directory",
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    by_directory: Option<Option<usize>>,
+
+    /// Reverses the order of source files
+    #[clap(long = "reverse")]
+    reverse: bool,
+
+    /// Shows only the first N source files in the order
+    ///
+    /// The total still counts all source files.
+    #[clap(long = "top", value_name = "N")]
+    top: Option<usize>,
+
+    /// Output format
+    #[clap(
+        long = "format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value = "text"
+    )]
+    format: Format,
+
+    /// When to colorize coverage rates
+    ///
+    /// `auto` colorizes them if the standard output is a terminal and `NO_COLOR` is not set.
+    #[clap(
+        long = "color",
+        value_name = "WHEN",
+        value_enum,
+        default_value = "auto"
+    )]
+    color: Color,
+
+    #[clap(flatten)]
+    fail_under: FailUnder,
+
+    /// LCOV tracefiles to summarize
+    ///
+    /// `-` means the standard input. Directories are searched recursively for `*.info` files,
+    /// and glob patterns (e.g. `coverage/**/*.info`) are expanded.
+    #[clap(name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Sort {
+    /// Path of the source file
+    Path,
+    /// Line coverage rate, lowest first
+    #[value(alias = "coverage")]
+    Lines,
+    /// Branch coverage rate, lowest first
+    Branches,
+    /// Function coverage rate, lowest first
+    Functions,
+    /// Number of lines not hit, largest first
+    #[value(alias = "misses")]
+    Missed,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Format {
+    /// Human-readable table
+    Text,
+    /// JSON object with the numbers and the violations of the thresholds
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Color {
+    /// Colorize if the output is a terminal
+    Auto,
+    /// Always colorize
+    Always,
+    /// Never colorize
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Color::Auto => {
+                env::var_os("NO_COLOR").map_or(true, |v| v.is_empty()) && io::stdout().is_terminal()
+            }
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+impl From<Sort> for SortBy {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Path => SortBy::Path,
+            Sort::Lines => SortBy::LineRate,
+            Sort::Branches => SortBy::BranchRate,
+            Sort::Functions => SortBy::FunctionRate,
+            Sort::Missed => SortBy::MissedLines,
+        }
+    }
+}
+
+fn run(opt: Opt) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = Config::load(opt.config.as_deref())?;
+    opt.rc.apply(&mut config)?;
+    opt.paths.apply(&mut config);
+    config.loose |= opt.loose;
+    let sort = match (opt.sort, &config.summary.sort) {
+        (Some(sort), _) => sort,
+        (None, Some(sort)) => Sort::from_str(sort, true)
+            .map_err(|e| format!("invalid sort key `{}` in the config file: {}", sort, e))?,
+        (None, None) => Sort::Path,
+    };
+
+    let inputs = input::collect(&opt.files, false)?;
+    let mut report = input::read_merged(&inputs, &ParseOptions::new(), config.loose)?;
+    config.apply(&mut report)?;
+
+    let summary = report.summary();
+    let directories = opt.by_directory.map(|depth| summary.by_directory(depth));
+    let shown = directories.as_ref().unwrap_or(&summary);
+    let reverse = opt.reverse || config.summary.reverse;
+    let limit = opt.top.or(config.summary.top);
+    // Violations are checked on the per-file summary, as the exit status is.
+    let violations = summary.check(&opt.fail_under.thresholds(&config.thresholds));
+    match opt.format {
+        Format::Text => print!(
+            "{}",
+            shown
+                .text()
+                .sort_by(sort.into())
+                .reverse(reverse)
+                .limit(limit)
+                .color(opt.color.enabled())
+        ),
+        Format::Json => print!(
+            "{}",
+            shown
+                .json()
+                .sort_by(sort.into())
+                .reverse(reverse)
+                .limit(limit)
+                .violations(&violations)
+        ),
+    }
+
+    let passed = opt.fail_under.check(&config.thresholds, &summary);
+    Ok(passed)
+}
+
+fn main() {
+    let opt = Opt::parse();
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    match run(opt) {
+        Ok(true) => {}
+        Ok(false) => process::exit(EXIT_FAIL_UNDER),
+        Err(e) => {
+            errors.print(&*e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Utility commands to operate and analyze LCOV trace file at blazingly fast.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+use clap::{CommandFactory, Parser};
+use lcov::reader::Error as ReadError;
+use lcov::report::section::{self, branch};
+use lcov::report::{FindingKind, ParseError, ParseOptions};
+use lcov::{Reader, Record, RecordKind, Report};
+use lcov_util::error::{Category, Diagnostic, ErrorOutput};
+use lcov_util::generate::Generate;
+use lcov_util::input::{self, Input};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    about = "Checks the consistency of LCOV tracefiles",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true
+)]
+struct Opt {
+    #[clap(subcommand)]
+    generate: Option<Generate>,
+
+    /// Output format
+    #[clap(
+        long = "format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value = "text"
+    )]
+    format: Format,
+
+    #[clap(flatten)]
+    errors: ErrorOutput,
+
+    /// LCOV tracefiles to check
+    ///
+    /// `-` means the standard input. Directories are searched recursively for `*.info` files,
+    /// and glob patterns (e.g. `coverage/**/*.info`) are expanded.
+    #[clap(name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Format {
+    /// A line for each problem
+    Text,
+    /// JSON object with the list of problems
+    Json,
+}
+
+// The JSON output of the problems.
+#[derive(Debug, Serialize)]
+struct Problems<'a> {
+    problems: &'a [Diagnostic],
+}
+
+// Describes a problem found in a tracefile.
+fn diagnostic(
+    input: &Input,
+    line: Option<u32>,
+    message: &dyn std::fmt::Display,
+    category: Category,
+) -> Diagnostic {
+    Diagnostic {
+        file: Some(input.to_string()),
+        line,
+        message: message.to_string(),
+        category,
+    }
+}
+
+// Line numbers of the records in a tracefile.
+#[derive(Debug, Default)]
+struct Positions {
+    sections: HashMap<section::Key, SectionPositions>,
+}
+
+#[derive(Debug, Default)]
+struct SectionPositions {
+    source_file: u32,
+    functions: HashMap<String, u32>,
+    branches: HashMap<branch::Key, u32>,
+    lines: HashMap<u32, u32>,
+    records: BTreeMap<RecordKind, u32>,
+}
+
+impl Positions {
+    fn new(records: &[Record]) -> Self {
+        let mut positions = Positions::default();
+        let mut test_name = String::new();
+        let mut current = None;
+        for (i, rec) in records.iter().enumerate() {
+            let line = i as u32 + 1;
+            match rec {
+                Record::TestName { name } => test_name.clone_from(name),
+                Record::SourceFile { path } => {
+                    let key = section::Key {
+                        test_name: test_name.clone(),
+                        source_file: path.clone(),
+                    };
+                    current = Some(positions.sections.entry(key).or_insert(SectionPositions {
+                        source_file: line,
+                        ..SectionPositions::default()
+                    }));
+                    continue;
+                }
+                _ => {}
+            }
+            let section = match current.as_mut() {
+                Some(section) => section,
+                None => continue,
+            };
+            let _ = section.records.entry(rec.kind()).or_insert(line);
+            match rec {
+                Record::FunctionName { name, .. }
+                | Record::FunctionData { name, .. }
+                | Record::FunctionAlias { name, .. } => {
+                    let _ = section.functions.entry(name.clone()).or_insert(line);
+                }
+                &Record::BranchData {
+                    line: l,
+                    block,
+                    exception,
+                    branch,
+                    ..
+                } => {
+                    let key = branch::Key {
+                        line: l,
+                        block,
+                        exception,
+                        branch,
+                    };
+                    let _ = section.branches.entry(key).or_insert(line);
+                }
+                &Record::LineData { line: l, .. } => {
+                    let _ = section.lines.entry(l).or_insert(line);
+                }
+                Record::EndOfRecord => current = None,
+                _ => {}
+            }
+        }
+        positions
+    }
+
+    fn of_record(&self, key: &section::Key, kind: RecordKind) -> Option<u32> {
+        let section = self.sections.get(key)?;
+        Some(
+            section
+                .records
+                .get(&kind)
+                .copied()
+                .unwrap_or(section.source_file),
+        )
+    }
+
+    fn of_finding(&self, key: &section::Key, kind: &FindingKind) -> Option<u32> {
+        let section = self.sections.get(key)?;
+        let line = match kind {
+            FindingKind::FunctionWithoutStartLine { name }
+            | FindingKind::ConflictingFunctionLine { name, .. } => {
+                section.functions.get(name).copied()
+            }
+            FindingKind::BranchWithoutLineData { branch } => section.branches.get(branch).copied(),
+            FindingKind::ZeroLineNumber { kind } => section.records.get(kind).copied(),
+            FindingKind::ConflictingChecksum { line, .. } => section.lines.get(line).copied(),
+            FindingKind::ConflictingVersion { .. } => {
+                section.records.get(&RecordKind::Version).copied()
+            }
+        };
+        Some(line.unwrap_or(section.source_file))
+    }
+}
+
+// Appends the problems found in the tracefile to `diagnostics`.
+fn validate(
+    input: &Input,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let records = match Reader::new(input.open()?).collect::<Result<Vec<_>, _>>() {
+        Ok(records) => records,
+        Err(ReadError::ParseRecord(line, e)) => {
+            diagnostics.push(diagnostic(input, Some(line), &e, Category::Parse));
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let positions = Positions::new(&records);
+    let parse = |options| {
+        Report::from_reader_with(records.iter().cloned().map(Ok::<_, ReadError>), &options)
+    };
+
+    let report = match parse(ParseOptions::new().strict(true)) {
+        Ok(report) => report,
+        Err(e) => {
+            let line = match e {
+                ParseError::SummaryMismatch { ref key, kind, .. } => positions.of_record(key, kind),
+                _ => None,
+            };
+            diagnostics.push(diagnostic(input, line, &e, Category::Parse));
+            match e {
+                // Continues checking the rest of the tracefile.
+                ParseError::SummaryMismatch { .. } => {}
+                _ => return Ok(()),
+            }
+            parse(ParseOptions::new())?
+        }
+    };
+
+    for finding in report.validate() {
+        let line = positions.of_finding(&finding.key, &finding.kind);
+        diagnostics.push(diagnostic(input, line, &finding, Category::Validate));
+    }
+    Ok(())
+}
+
+// Returns the number of problems found in the tracefiles.
+fn run(opt: Opt) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let mut problems = 0;
+    let mut diagnostics = vec![];
+    for input in input::collect(&opt.files, false)? {
+        let before = diagnostics.len();
+        validate(&input, &mut diagnostics)?;
+        problems += diagnostics.len() - before;
+        // Text output is printed as soon as each tracefile is checked.
+        if let Format::Text = opt.format {
+            for diagnostic in diagnostics.drain(..) {
+                println!("{}", diagnostic);
+            }
+        }
+    }
+    if let Format::Json = opt.format {
+        let output = Problems {
+            problems: &diagnostics,
+        };
+        println!("{}", serde_json::to_string(&output)?);
+    }
+    Ok(problems)
+}
+
+fn main() {
+    let opt = Opt::parse();
+    if let Some(generate) = &opt.generate {
+        if let Err(e) = generate.run(Opt::command(), env!("CARGO_BIN_NAME")) {
+            opt.errors.print(&e);
+            process::exit(1);
+        }
+        return;
+    }
+    let errors = opt.errors;
+    match run(opt) {
+        Ok(0) => {}
+        Ok(problems) => {
+            eprintln!("{} problem(s) found", problems);
+            process::exit(1);
+        }
+        Err(e) => {
+            errors.print(&*e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Writing coverage reports as Cobertura XML, in the form accepted by GitLab.
+//!
+//! GitLab shows the coverage of the changed lines in merge requests from the `coverage_report`
+//! artifact in Cobertura format. It matches the `filename` of each class against the files in
+//! the repository, after joining it with one of the `<source>` paths. So paths of source files
+//! are written relative to the project root, which is the only `<source>` entry.
+use crate::html;
+use lcov::report::section;
+use lcov::report::summary::{Counter, Stats};
+use lcov::Report;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Options to write Cobertura XML.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Root directory of the project, e.g. `$CI_PROJECT_DIR` on GitLab CI.
+    ///
+    /// Absolute paths of source files under the root are written relative to it.
+    pub source_root: PathBuf,
+    /// Time when the report is generated, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// Writes the report as Cobertura XML.
+///
+/// Sections of the same source file are merged. Source files are grouped into packages by their
+/// directories.
+pub fn write<W>(report: &Report, writer: &mut W, options: &Options) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut packages = BTreeMap::<String, Vec<(PathBuf, section::Value)>>::new();
+    let mut total = Stats::default();
+    for (path, value) in report.sections.merge_by_file() {
+        let path = relative_path(path, &options.source_root);
+        total += Stats::of(&value);
+        packages
+            .entry(package_name(&path))
+            .or_default()
+            .push((path, value));
+    }
+
+    writeln!(writer, r#"<?xml version="1.0" ?>"#)?;
+    writeln!(
+        writer,
+        r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+    )?;
+    writeln!(
+        writer,
+        r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" timestamp="{}" version="{}">"#,
+        rate(total.lines),
+        rate(total.branches),
+        total.lines.hit,
+        total.lines.found,
+        total.branches.hit,
+        total.branches.found,
+        options.timestamp,
+        env!("CARGO_PKG_VERSION"),
+    )?;
+    writeln!(writer, "  <sources>")?;
+    writeln!(
+        writer,
+        "    <source>{}</source>",
+        html::escape(&options.source_root.display().to_string())
+    )?;
+    writeln!(writer, "  </sources>")?;
+    writeln!(writer, "  <packages>")?;
+    for (name, files) in &packages {
+        let stats = files.iter().fold(Stats::default(), |stats, (_, value)| {
+            stats + Stats::of(value)
+        });
+        writeln!(
+            writer,
+            r#"    <package name="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+            html::escape(name),
+            rate(stats.lines),
+            rate(stats.branches),
+        )?;
+        writeln!(writer, "      <classes>")?;
+        for (path, value) in files {
+            write_class(writer, path, value)?;
+        }
+        writeln!(writer, "      </classes>")?;
+        writeln!(writer, "    </package>")?;
+    }
+    writeln!(writer, "  </packages>")?;
+    writeln!(writer, "</coverage>")?;
+    Ok(())
+}
+
+fn write_class<W>(writer: &mut W, path: &Path, value: &section::Value) -> io::Result<()>
+where
+    W: Write,
+{
+    let stats = Stats::of(value);
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+    writeln!(
+        writer,
+        r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+        html::escape(&name),
+        html::escape(&path.display().to_string()),
+        rate(stats.lines),
+        rate(stats.branches),
+    )?;
+
+    writeln!(writer, "          <methods>")?;
+    for (key, function) in &value.functions {
+        let Some(line) = function.start_line else {
+            continue;
+        };
+        let hit = u32::from(function.count > 0);
+        writeln!(
+            writer,
+            r#"            <method name="{}" signature="" line-rate="{}" branch-rate="0" complexity="0">"#,
+            html::escape(&key.name),
+            hit,
+        )?;
+        writeln!(
+            writer,
+            r#"              <lines><line number="{}" hits="{}"/></lines>"#,
+            line, function.count,
+        )?;
+        writeln!(writer, "            </method>")?;
+    }
+    writeln!(writer, "          </methods>")?;
+
+    let branches = value.branches_by_line();
+    writeln!(writer, "          <lines>")?;
+    for (key, line) in &value.lines {
+        write!(
+            writer,
+            r#"            <line number="{}" hits="{}""#,
+            key.line, line.count
+        )?;
+        match branches.get(&key.line) {
+            Some(branches) if branches.found > 0 => writeln!(
+                writer,
+                r#" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                u64::from(branches.taken) * 100 / u64::from(branches.found),
+                branches.taken,
+                branches.found,
+            )?,
+            _ => writeln!(writer, r#" branch="false"/>"#)?,
+        }
+    }
+    writeln!(writer, "          </lines>")?;
+    writeln!(writer, "        </class>")?;
+    Ok(())
+}
+
+fn relative_path(path: &Path, root: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).into()
+}
+
+fn package_name(path: &Path) -> String {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.display().to_string().replace('/', "."),
+        _ => ".".into(),
+    }
+}
+
+fn rate(counter: Counter) -> String {
+    format!("{:.4}", counter.rate().unwrap_or(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_xml() {
+        let mut report = Report::new();
+        let _ = report
+            .section("test1", "/build/project/src/main.rs")
+            .add_function("main", 1, 1)
+            .add_branch(2, 0, 0, Some(1))
+            .add_branch(2, 0, 1, Some(0))
+            .add_line(1, 1)
+            .add_line(2, 1)
+            .add_line(3, 0);
+        let _ = report
+            .section("test2", "/build/project/src/main.rs")
+            .add_line(3, 2);
+        let _ = report.section("test1", "build.rs").add_line(1, 0);
+
+        let options = Options {
+            source_root: "/build/project".into(),
+            timestamp: 1_700_000_000_000,
+        };
+        let mut output = vec![];
+        write(&report, &mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(
+            r#"<coverage line-rate="0.7500" branch-rate="0.5000" lines-covered="3" lines-valid="4" branches-covered="1" branches-valid="2" complexity="0" timestamp="1700000000000""#
+        ));
+        assert!(output.contains("<source>/build/project</source>"));
+        assert!(output.contains(r#"<package name="." line-rate="0.0000""#));
+        assert!(output.contains(r#"<package name="src" line-rate="1.0000""#));
+        assert!(output.contains(r#"<class name="main.rs" filename="src/main.rs""#));
+        assert!(output.contains(r#"<class name="build.rs" filename="build.rs""#));
+        assert!(output.contains(
+            r#"<line number="2" hits="1" branch="true" condition-coverage="50% (1/2)"/>"#
+        ));
+        assert!(output.contains(r#"<line number="3" hits="2" branch="false"/>"#));
+        assert!(output.contains(r#"<method name="main" signature="" line-rate="1""#));
+    }
+
+    #[test]
+    fn package_names() {
+        assert_eq!(package_name(Path::new("main.rs")), ".");
+        assert_eq!(package_name(Path::new("src/a/b.rs")), "src.a");
+    }
+}
@@ -0,0 +1,412 @@
+//! Configuration file (`lcov.toml`) which sets the default options of the commands.
+//!
+//! ```toml
+//! loose = true
+//!
+//! [filter]
+//! extract = ["src/*"]
+//! remove = ["src/generated/*"]
+//! no-branches = false
+//! no-functions = false
+//! no-checksums = false
+//!
+//! add-prefix = "project"
+//!
+//! [[remap]]
+//! from = "/build/project/"
+//! to = ""
+//!
+//! [[remap]]
+//! regex = "^/home/[^/]+/checkout/"
+//! to = ""
+//!
+//! [thresholds]
+//! lines = 80.0
+//! functions = 70.0
+//! branches = 50.0
+//!
+//! [summary]
+//! sort = "missed"
+//! top = 20
+//!
+//! [html]
+//! title = "My project"
+//! prefix = "/build/project"
+//! ```
+use crate::wildcard::Wildcard;
+use lcov::filter::FilterMap;
+use lcov::report::{section, Resolution};
+use lcov::Report;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the configuration file searched by [`Config::load`].
+pub const FILE_NAME: &str = "lcov.toml";
+
+/// Default options of the commands.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Merges tracefiles, and source files remapped into the same path, without validation, like
+    /// `--loose`.
+    pub loose: bool,
+    /// Source files and records to keep.
+    pub filter: Filter,
+    /// Rewrites of the paths of source files, applied in order.
+    pub remap: Vec<Remap>,
+    /// Prepends a prefix to the relative paths of source files after `remap`, like
+    /// `--add-prefix`.
+    pub add_prefix: Option<PathBuf>,
+    /// Minimum coverage rates.
+    pub thresholds: Thresholds,
+    /// Options of `lcov-summary`.
+    pub summary: Summary,
+    /// Options of `lcov-html`.
+    pub html: Html,
+}
+
+/// Source files and records to keep.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Filter {
+    /// Keeps only source files matching any of the patterns, like `--extract`.
+    ///
+    /// Patterns are shell wildcards matched against the whole path, as `lcov --extract` does.
+    #[serde(deserialize_with = "deserialize_patterns")]
+    pub extract: Vec<Wildcard>,
+    /// Removes source files matching any of the patterns, like `--remove`.
+    #[serde(deserialize_with = "deserialize_patterns")]
+    pub remove: Vec<Wildcard>,
+    /// Removes branch coverage information, like `--no-branches`.
+    pub no_branches: bool,
+    /// Removes function coverage information, like `--no-functions`.
+    pub no_functions: bool,
+    /// Removes checksums of lines, like `--no-checksums`.
+    pub no_checksums: bool,
+}
+
+/// A rewrite of the paths of source files.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum Remap {
+    /// Replaces the prefix of the paths.
+    Prefix {
+        /// Prefix of the paths to rewrite.
+        from: PathBuf,
+        /// Path which replaces the prefix. An empty path removes the prefix.
+        to: PathBuf,
+    },
+    /// Replaces the first match of a regular expression in the paths.
+    Regex {
+        /// Regular expression matched against the paths.
+        #[serde(deserialize_with = "deserialize_regex")]
+        regex: Regex,
+        /// Replacement of the match, which can refer to capture groups such as `$1`.
+        to: String,
+    },
+}
+
+// A remapping file, which has only `[[remap]]` tables of the configuration file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemapFile {
+    #[serde(default)]
+    remap: Vec<Remap>,
+}
+
+/// Minimum coverage rates in percent.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    /// Minimum total line coverage.
+    pub lines: Option<f64>,
+    /// Minimum total function coverage.
+    pub functions: Option<f64>,
+    /// Minimum total branch coverage.
+    pub branches: Option<f64>,
+}
+
+/// Options of `lcov-summary`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Summary {
+    /// Order of source files, like `--sort`.
+    pub sort: Option<String>,
+    /// Reverses the order of source files, like `--reverse`.
+    pub reverse: bool,
+    /// Maximum number of source files shown, like `--top`.
+    pub top: Option<usize>,
+}
+
+/// Options of `lcov-html`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Html {
+    /// Title of the pages, like `--title`.
+    pub title: Option<String>,
+    /// Prefix removed from the paths of source files, like `--prefix`.
+    pub prefix: Option<PathBuf>,
+}
+
+fn deserialize_patterns<'de, D>(deserializer: D) -> Result<Vec<Wildcard>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Regex::new(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+impl Config {
+    /// Loads the configuration file.
+    ///
+    /// If `path` is `None`, [`FILE_NAME`] is searched in the current directory and its ancestors,
+    /// and the default configuration is returned if it is not found.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match find()? {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let config = toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    /// Appends the rewrites of the paths in a remapping file.
+    ///
+    /// The file has `[[remap]]` tables in the same form as the configuration file, which allows
+    /// sharing the rewrites for the checkout layouts of several build machines.
+    pub fn load_remap(&mut self, path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let file: RemapFile =
+            toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.remap.extend(file.remap);
+        Ok(())
+    }
+
+    /// Rewrites the paths of source files, and then filters the report.
+    ///
+    /// Sections whose paths are rewritten into the same one are merged. Unless [`loose`] is set,
+    /// the merge is validated as [`Report::merge`] does, and a conflict is returned as an error.
+    ///
+    /// [`loose`]: #structfield.loose
+    pub fn apply(&self, report: &mut Report) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self.remap.is_empty() || self.add_prefix.is_some() {
+            let resolution = if self.loose {
+                Resolution::KeepRight
+            } else {
+                Resolution::Error
+            };
+            report
+                .map_paths_resolve(
+                    |path| remap(&self.remap, self.add_prefix.as_deref(), path),
+                    |_| resolution,
+                )
+                .map_err(|e| format!("failed to merge remapped source files: {}", e))?;
+        }
+        report.sections.filter_map(|(key, mut value)| {
+            if !self.filter.keeps(&key.source_file) {
+                return None;
+            }
+            self.filter.apply(&mut value);
+            if value.is_empty() {
+                None
+            } else {
+                Some((key, value))
+            }
+        });
+        Ok(())
+    }
+}
+
+impl Remap {
+    /// Returns the rewritten path, or `None` if the rewrite does not apply to `path`.
+    pub fn apply(&self, path: &Path) -> Option<PathBuf> {
+        match self {
+            Remap::Prefix { from, to } => path.strip_prefix(from).ok().map(|rest| to.join(rest)),
+            Remap::Regex { regex, to } => {
+                let path = path.to_str()?;
+                regex
+                    .is_match(path)
+                    .then(|| regex.replace(path, to.as_str()).into_owned().into())
+            }
+        }
+    }
+}
+
+impl Filter {
+    /// Returns `true` if the source file is kept by `extract` and `remove`.
+    pub fn keeps(&self, path: &Path) -> bool {
+        (self.extract.is_empty() || self.extract.iter().any(|p| p.matches_path(path)))
+            && !self.remove.iter().any(|p| p.matches_path(path))
+    }
+
+    /// Removes the records disabled by `no_branches`, `no_functions` and `no_checksums`.
+    pub fn apply(&self, value: &mut section::Value) {
+        if self.no_branches {
+            value.branches.clear();
+        }
+        if self.no_functions {
+            value.functions.clear();
+        }
+        if self.no_checksums {
+            for line in value.lines.values_mut() {
+                line.checksum = None;
+            }
+        }
+    }
+}
+
+fn find() -> Result<Option<PathBuf>, Box<dyn Error + Send + Sync>> {
+    let cwd = std::env::current_dir()?;
+    Ok(cwd
+        .ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file()))
+}
+
+fn remap(remaps: &[Remap], add_prefix: Option<&Path>, path: &Path) -> PathBuf {
+    let path = remaps
+        .iter()
+        .find_map(|remap| remap.apply(path))
+        .unwrap_or_else(|| path.to_path_buf());
+    match add_prefix {
+        // An absolute path replaces the prefix.
+        Some(prefix) => prefix.join(path),
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Remap};
+    use lcov::Report;
+    use std::path::Path;
+
+    #[test]
+    fn apply() {
+        let config: Config = toml::from_str(
+            r#"
+            [filter]
+            remove = ["src/generated/*"]
+            no-branches = true
+
+            [[remap]]
+            from = "/build"
+            to = ""
+            "#,
+        )
+        .unwrap();
+
+        let mut report = Report::new();
+        let _ = report
+            .section("a", "/build/src/main.rs")
+            .add_line(1, 1)
+            .add_branch(1, 0, 0, Some(1));
+        let _ = report.section("b", "src/main.rs").add_line(1, 2);
+        let _ = report
+            .section("a", "/build/src/generated/foo.rs")
+            .add_line(1, 1);
+        config.apply(&mut report).unwrap();
+
+        let paths = report
+            .sections
+            .iter()
+            .map(|(key, value)| (key.test_name.as_str(), key.source_file.as_path(), value))
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].0, "a");
+        assert_eq!(paths[0].1, Path::new("src/main.rs"));
+        assert!(paths[0].2.branches.is_empty());
+        assert_eq!(paths[1].1, Path::new("src/main.rs"));
+    }
+
+    #[test]
+    fn remap_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("remap.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[remap]]
+            from = "/build/a"
+            to = "project"
+
+            [[remap]]
+            regex = "^/home/[^/]+/(work|src)/"
+            to = "project/"
+            "#,
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.load_remap(&path).unwrap();
+
+        let mut report = Report::new();
+        let _ = report.section("", "/build/a/main.rs").add_line(1, 1);
+        let _ = report
+            .section("", "/home/alice/work/main.rs")
+            .add_line(2, 1);
+        let _ = report.section("", "/home/bob/src/lib.rs").add_line(1, 0);
+        let _ = report.section("", "/opt/other.rs").add_line(1, 0);
+        config.apply(&mut report).unwrap();
+
+        let paths = report
+            .sections
+            .keys()
+            .map(|key| key.source_file.as_path())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                Path::new("/opt/other.rs"),
+                Path::new("project/lib.rs"),
+                Path::new("project/main.rs")
+            ]
+        );
+        assert_eq!(report.line_count("project/main.rs", 2), Some(1));
+    }
+
+    #[test]
+    fn remap_conflict() {
+        let mut config = Config::default();
+        config.remap.push(Remap::Prefix {
+            from: "/build".into(),
+            to: "".into(),
+        });
+        let input = "TN:\nSF:/build/a.c\nDA:1,1,x\nend_of_record\n\
+                     TN:\nSF:a.c\nDA:1,1,y\nend_of_record\n";
+
+        let mut report = input.parse::<Report>().unwrap();
+        let e = config.apply(&mut report).unwrap_err();
+        assert!(e.to_string().contains("a.c"), "{}", e);
+
+        config.loose = true;
+        let mut report = input.parse::<Report>().unwrap();
+        config.apply(&mut report).unwrap();
+        assert_eq!(report.sections.len(), 1);
+        assert_eq!(report.line_count("a.c", 1), Some(2));
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert!(toml::from_str::<Config>("[filter]\nexclude = []\n").is_err());
+        assert!(toml::from_str::<Config>("[[remap]]\nregex = \"(\"\nto = \"\"\n").is_err());
+        assert!(toml::from_str::<Config>("[[remap]]\nfrom = \"a\"\n").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Reporting errors of the commands to the standard error.
+use crate::input::Input;
+use lcov::reader::Error as ReadError;
+use lcov::report::{MergeError, ParseError};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// An error which occurs while reading a report from an input tracefile.
+#[derive(Debug)]
+pub struct InputError {
+    /// The tracefile.
+    pub input: Input,
+    /// The error.
+    pub error: ParseError,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.input, self.error)
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Command line options to specify how errors are reported.
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct ErrorOutput {
+    /// Format of errors printed to the standard error
+    ///
+    /// `json` prints an object with `file`, `line`, `message` and `category` fields, which CI
+    /// systems can show as annotations.
+    #[clap(
+        long = "error-format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value = "human"
+    )]
+    pub error_format: ErrorFormat,
+}
+
+/// Format of errors.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// A message
+    #[default]
+    Human,
+    /// A JSON object
+    Json,
+}
+
+impl ErrorOutput {
+    /// Prints the error to the standard error.
+    pub fn print(&self, error: &(dyn Error + 'static)) {
+        match self.error_format {
+            ErrorFormat::Human => eprintln!("{}", error),
+            ErrorFormat::Json => eprintln!("{}", Diagnostic::of(error).to_json()),
+        }
+    }
+}
+
+/// A structured description of an error, or of a problem found in a tracefile.
+///
+/// `Display` renders it as `file:line: message`, and [`to_json`] as a JSON object.
+///
+/// [`to_json`]: #method.to_json
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Diagnostic {
+    /// The tracefile or the source file which the error is about.
+    pub file: Option<String>,
+    /// The line number in `file`.
+    pub line: Option<u32>,
+    /// The message without `file` and `line`.
+    pub message: String,
+    /// The kind of the error.
+    pub category: Category,
+}
+
+/// Kinds of errors.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// Reading or writing a file failed.
+    Io,
+    /// A tracefile is malformed.
+    Parse,
+    /// A tracefile is inconsistent, e.g. it has conflicting records.
+    Validate,
+    /// Reports conflict with each other.
+    Merge,
+    /// Other errors, such as invalid options.
+    Other,
+}
+
+impl Category {
+    /// Returns the name of the category.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::Io => "io",
+            Category::Parse => "parse",
+            Category::Validate => "validate",
+            Category::Merge => "merge",
+            Category::Other => "other",
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Describes the error.
+    pub fn of(error: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = error.downcast_ref::<InputError>() {
+            let (line, category) = match &e.error {
+                ParseError::Read(read) => match read.unlabeled() {
+                    ReadError::Io(_) => (None, Category::Io),
+                    ReadError::ParseRecord(line, _) => (Some(*line), Category::Parse),
+                    _ => (None, Category::Parse),
+                },
+                _ => (None, Category::Parse),
+            };
+            return Diagnostic {
+                file: Some(e.input.to_string()),
+                line,
+                message: e.error.to_string(),
+                category,
+            };
+        }
+        if let Some(e) = error.downcast_ref::<MergeError>() {
+            let (key, line) = match e {
+                MergeError::UnmatchedFunctionLine {
+                    key, start_line, ..
+                } => (Some(key), Some(*start_line)),
+                MergeError::UnmatchedChecksum { key, line, .. } => (Some(key), Some(*line)),
+                MergeError::UnmatchedVersion { key, .. } => (Some(key), None),
+                MergeError::Cancelled => (None, None),
+            };
+            return Diagnostic {
+                file: key.map(|key| key.source_file.display().to_string()),
+                line,
+                message: e.to_string(),
+                category: Category::Merge,
+            };
+        }
+        let category = if error.downcast_ref::<io::Error>().is_some() {
+            Category::Io
+        } else {
+            Category::Other
+        };
+        Diagnostic {
+            file: None,
+            line: None,
+            message: error.to_string(),
+            category,
+        }
+    }
+
+    /// Renders the diagnostic as a JSON object with `file`, `line`, `message` and `category`
+    /// fields.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serializing a diagnostic never fails")
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            (Some(file), None) => write!(f, "{}: {}", file, self.message),
+            (None, _) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Category, Diagnostic, InputError};
+    use crate::input::Input;
+    use lcov::report::ParseOptions;
+    use lcov::{Reader, Report};
+
+    #[test]
+    fn parse_error() {
+        let input = "TN:\nSF:foo.c\nDA:1\nend_of_record\n";
+        let error = Report::from_reader_with(Reader::new(input.as_bytes()), &ParseOptions::new())
+            .unwrap_err();
+        let error = InputError {
+            input: Input::File("a.info".into()),
+            error,
+        };
+        let diagnostic = Diagnostic::of(&error);
+        assert_eq!(diagnostic.file.as_deref(), Some("a.info"));
+        assert_eq!(diagnostic.line, Some(3));
+        assert_eq!(diagnostic.category, Category::Parse);
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"file":"a.info","line":3,"message":"failed to read record: invalid record syntax at line 3: field `count` not found","category":"parse"}"#
+        );
+    }
+
+    #[test]
+    fn merge_error() {
+        let mut report = Report::new();
+        let _ = report.section("", "src/a.c").add_function("f", 1, 1);
+        let mut other = Report::new();
+        let _ = other.section("", "src/a.c").add_function("f", 2, 1);
+        let error = report.merge(other).unwrap_err();
+        let diagnostic = Diagnostic::of(&error);
+        assert_eq!(diagnostic.file.as_deref(), Some("src/a.c"));
+        assert_eq!(diagnostic.line, Some(1));
+        assert_eq!(diagnostic.category, Category::Merge);
+    }
+}
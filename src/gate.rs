@@ -0,0 +1,65 @@
+//! Failing commands when the coverage is below the thresholds.
+use crate::config;
+use lcov::report::summary::{Summary, Thresholds};
+
+/// Exit status when the coverage is below the thresholds.
+///
+/// This differs from the status of command line errors reported by clap (2), so that CI can
+/// tell a failed coverage gate from a broken command line.
+pub const EXIT_FAIL_UNDER: i32 = 3;
+
+/// Command line options to specify the minimum coverage rates.
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct FailUnder {
+    /// Exits with status 3 if the total line coverage is below PERCENT
+    #[clap(
+        long = "fail-under-lines",
+        alias = "fail-under",
+        value_name = "PERCENT"
+    )]
+    pub lines: Option<f64>,
+
+    /// Exits with status 3 if the total function coverage is below PERCENT
+    #[clap(long = "fail-under-functions", value_name = "PERCENT")]
+    pub functions: Option<f64>,
+
+    /// Exits with status 3 if the total branch coverage is below PERCENT
+    #[clap(long = "fail-under-branches", value_name = "PERCENT")]
+    pub branches: Option<f64>,
+}
+
+impl FailUnder {
+    /// Returns `true` if any threshold is specified.
+    pub fn is_enabled(&self, config: &config::Thresholds) -> bool {
+        [self.lines, self.functions, self.branches]
+            .iter()
+            .chain(&[config.lines, config.functions, config.branches])
+            .any(Option::is_some)
+    }
+
+    /// Returns the thresholds, falling back to the ones in the configuration file.
+    pub fn thresholds(&self, config: &config::Thresholds) -> Thresholds {
+        let mut thresholds = Thresholds::new();
+        if let Some(percent) = self.lines.or(config.lines) {
+            thresholds = thresholds.lines(percent);
+        }
+        if let Some(percent) = self.functions.or(config.functions) {
+            thresholds = thresholds.functions(percent);
+        }
+        if let Some(percent) = self.branches.or(config.branches) {
+            thresholds = thresholds.branches(percent);
+        }
+        thresholds
+    }
+
+    /// Prints the violations of the thresholds to the standard error.
+    ///
+    /// Returns `true` if the coverage satisfies all the thresholds.
+    pub fn check(&self, config: &config::Thresholds, summary: &Summary) -> bool {
+        let violations = summary.check(&self.thresholds(config));
+        for violation in &violations {
+            eprintln!("{}", violation);
+        }
+        violations.is_empty()
+    }
+}
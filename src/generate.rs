@@ -0,0 +1,50 @@
+//! Generating shell completion scripts and man pages of the commands, for packagers.
+use clap::Command;
+use clap_complete::Shell;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Hidden subcommands to generate files for shell integration.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum Generate {
+    /// Prints the completion script for SHELL
+    #[clap(hide = true)]
+    Completions {
+        /// The shell to complete the command in
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+
+    /// Prints the man page, or writes it as `<command>.1` into DIR
+    #[clap(hide = true)]
+    Manpages {
+        /// Directory to write the man page into
+        #[clap(value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+}
+
+impl Generate {
+    /// Generates the file for the command named `bin_name`.
+    pub fn run(&self, command: Command, bin_name: &'static str) -> io::Result<()> {
+        let mut command = command.name(bin_name).bin_name(bin_name);
+        match self {
+            Generate::Completions { shell } => {
+                clap_complete::generate(*shell, &mut command, bin_name, &mut io::stdout());
+                Ok(())
+            }
+            Generate::Manpages { dir } => {
+                let man = clap_mangen::Man::new(command);
+                match dir {
+                    Some(dir) => {
+                        let mut file = File::create(dir.join(format!("{}.1", bin_name)))?;
+                        man.render(&mut file)?;
+                        file.flush()
+                    }
+                    None => man.render(&mut io::stdout()),
+                }
+            }
+        }
+    }
+}
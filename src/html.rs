@@ -0,0 +1,207 @@
+//! Rendering coverage reports as HTML pages, like `genhtml`.
+use lcov::cancel::CancellationToken;
+use lcov::report::section;
+use lcov::report::summary::{Counter, Stats};
+use lcov::Report;
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Options to render HTML pages.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Title of the pages.
+    pub title: String,
+    /// Prefix removed from the paths of source files in the pages.
+    pub prefix: Option<PathBuf>,
+    /// Token to cancel rendering, checked before each page is written.
+    ///
+    /// Rendering fails with an [`io::ErrorKind::Other`] error wrapping [`Cancelled`] once the
+    /// token is cancelled.
+    pub cancellation: Option<CancellationToken>,
+}
+
+/// The error wrapped by the `io::Error` returned from [`render`] when rendering is cancelled.
+///
+/// Unlike `io::ErrorKind::Interrupted`, the error must not be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rendering cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.6em; text-align: left; }
+th { background: #ddd; }
+td.num { text-align: right; }
+.hi { background: #a7fc9d; }
+.med { background: #ffea20; }
+.lo { background: #ff6230; }
+pre { margin: 0; }
+tr.hit td.count { background: #a7fc9d; }
+tr.miss td.count, tr.miss td.src { background: #ff6230; }
+";
+
+/// Renders the report into `index.html` and a page for each source file in `dir`.
+pub fn render(report: &Report, dir: &Path, options: &Options) -> io::Result<()> {
+    let files = report.sections.merge_by_file();
+    fs::create_dir_all(dir.join("files"))?;
+
+    let mut total = Stats::default();
+    let mut rows = String::new();
+    for (i, (path, value)) in files.iter().enumerate() {
+        if let Some(token) = &options.cancellation {
+            if token.is_cancelled() {
+                return Err(io::Error::other(Cancelled));
+            }
+        }
+        let stats = Stats::of(value);
+        total += stats;
+        let name = display_path(path, options);
+        let page = format!("files/{}.html", i);
+        let _ = writeln!(
+            rows,
+            "<tr><td><a href=\"{}\">{}</a></td>{}</tr>",
+            page,
+            escape(&name),
+            stats_cells(&stats)
+        );
+        fs::write(dir.join(&page), file_page(path, &name, value, options))?;
+    }
+
+    let body = format!(
+        "<table>\n<tr><th>File</th><th>Lines</th><th>Functions</th><th>Branches</th></tr>\n\
+         <tr><th>Total</th>{}</tr>\n{}</table>\n",
+        stats_cells(&total),
+        rows
+    );
+    fs::write(dir.join("index.html"), page(&options.title, &body))
+}
+
+fn file_page(path: &Path, name: &str, value: &section::Value, options: &Options) -> String {
+    let mut body = format!(
+        "<p><a href=\"../index.html\">top</a> - {}</p>\n<table>\n\
+         <tr><th>Lines</th><th>Functions</th><th>Branches</th></tr>\n<tr>{}</tr>\n</table>\n",
+        escape(name),
+        stats_cells(&Stats::of(value))
+    );
+
+    body.push_str("<table>\n<tr><th>Line</th><th>Count</th><th>Source</th></tr>\n");
+    // Source files which cannot be read are rendered with the instrumented lines only.
+    let source = fs::read_to_string(path).ok();
+    let source_lines = source
+        .as_deref()
+        .map(|s| s.lines().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let last = value
+        .lines
+        .keys()
+        .map(|key| key.line as usize)
+        .max()
+        .unwrap_or(0)
+        .max(source_lines.len());
+    for line in 1..=last {
+        let data = value.lines.get(&section::line::Key { line: line as u32 });
+        let text = source_lines.get(line - 1).copied();
+        if data.is_none() && source.is_none() {
+            continue;
+        }
+        let (class, count) = match data {
+            Some(data) if data.count > 0 => ("hit", data.count.to_string()),
+            Some(_) => ("miss", "0".into()),
+            None => ("", String::new()),
+        };
+        let _ = writeln!(
+            body,
+            "<tr class=\"{}\"><td class=\"num\">{}</td><td class=\"num count\">{}</td>\
+             <td class=\"src\"><pre>{}</pre></td></tr>",
+            class,
+            line,
+            count,
+            escape(text.unwrap_or(""))
+        );
+    }
+    body.push_str("</table>\n");
+    page(&format!("{} - {}", options.title, name), &body)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>\n{1}</style>\n</head>\n<body>\n<h1>{0}</h1>\n{2}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn stats_cells(stats: &Stats) -> String {
+    [stats.lines, stats.functions, stats.branches]
+        .iter()
+        .map(|counter| format!("<td class=\"num {}\">{}</td>", level(counter), counter))
+        .collect()
+}
+
+fn level(counter: &Counter) -> &'static str {
+    match counter.rate() {
+        Some(rate) if rate >= 0.9 => "hi",
+        Some(rate) if rate >= 0.75 => "med",
+        Some(_) => "lo",
+        None => "",
+    }
+}
+
+fn display_path(path: &Path, options: &Options) -> String {
+    let path = options
+        .prefix
+        .as_deref()
+        .and_then(|prefix| path.strip_prefix(prefix).ok())
+        .unwrap_or(path);
+    path.display().to_string()
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelled() {
+        let mut report = Report::new();
+        let _ = report.section("", "src/main.rs").add_line(1, 1);
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = Options {
+            cancellation: Some(token),
+            ..Options::default()
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let error = render(&report, dir.path(), &options).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        let inner = error.into_inner().unwrap();
+        assert_eq!(inner.downcast_ref::<Cancelled>(), Some(&Cancelled));
+    }
+}
@@ -0,0 +1,194 @@
+//! Reading LCOV tracefiles from files or the standard input.
+use crate::error::InputError;
+use lcov::report::{MergeConflict, MergeStrategy, Merger, ParseError, ParseOptions, Resolution};
+use lcov::{Reader, Report};
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// An input tracefile.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Input {
+    /// The standard input, specified by `-`.
+    Stdin,
+    /// A file.
+    File(PathBuf),
+}
+
+impl Input {
+    /// Creates an input from a command line argument.
+    pub fn from_arg(path: &Path) -> Self {
+        if path == Path::new("-") {
+            Input::Stdin
+        } else {
+            Input::File(path.into())
+        }
+    }
+
+    /// Opens the input as a buffered reader.
+    pub fn open(&self) -> io::Result<Box<dyn BufRead>> {
+        let reader: Box<dyn BufRead> = match self {
+            Input::Stdin => Box::new(io::stdin().lock()),
+            Input::File(path) => Box::new(BufReader::new(File::open(path)?)),
+        };
+        Ok(reader)
+    }
+
+    /// Reads a report from the input.
+    ///
+    /// Paths of source files which are not valid UTF-8 are kept as they are.
+    pub fn read_report(&self, options: &ParseOptions) -> Result<Report, InputError> {
+        let read = || {
+            let reader = self.open().map_err(|e| ParseError::Read(e.into()))?;
+            Report::from_reader_with(Reader::new(reader).lossless_paths(true), options)
+        };
+        read().map_err(|error| InputError {
+            input: self.clone(),
+            error,
+        })
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "<stdin>"),
+            Input::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Reads reports from the inputs in parallel and merges them into one report.
+///
+/// If `loose` is `true`, reports are merged by [`Report::merge_lossy`].
+pub fn read_merged(
+    inputs: &[Input],
+    options: &ParseOptions,
+    loose: bool,
+) -> Result<Report, Box<dyn Error + Send + Sync>> {
+    read_merged_with(inputs, options, loose, MergeStrategy::Sum, None)
+}
+
+/// Reads reports from the inputs in parallel and merges them into one report, combining
+/// execution counts by `strategy`.
+///
+/// If `function_line_tolerance` is given, start lines of a function differing by at most that
+/// many lines are merged into the smaller one instead of failing.
+///
+/// See [`read_merged`] for more.
+pub fn read_merged_with(
+    inputs: &[Input],
+    options: &ParseOptions,
+    loose: bool,
+    strategy: MergeStrategy,
+    function_line_tolerance: Option<u32>,
+) -> Result<Report, Box<dyn Error + Send + Sync>> {
+    let resolve = |conflict: &MergeConflict| {
+        if loose {
+            return Resolution::KeepRight;
+        }
+        match function_line_tolerance {
+            Some(tolerance) => conflict.kind.resolve_function_line(tolerance),
+            None => Resolution::Error,
+        }
+    };
+    let merge = |mut report: Report, other: Report| {
+        report.merge_with_strategy(other, strategy, resolve)?;
+        Ok::<_, Box<dyn Error + Send + Sync>>(report)
+    };
+    inputs
+        .par_iter()
+        .map(|input| input.read_report(options))
+        .try_fold(
+            || Merger::new().strategy(strategy),
+            |mut merger, report| {
+                merger.merge_resolve(report?, resolve)?;
+                Ok::<_, Box<dyn Error + Send + Sync>>(merger)
+            },
+        )
+        .map(|merger| merger.map(Merger::finish))
+        .try_reduce(Report::new, merge)
+}
+
+/// Collects the inputs specified by command line arguments.
+///
+/// Each argument is one of the following:
+///
+/// * `-`: the standard input.
+/// * a directory: all `*.info` files in the directory and its subdirectories.
+/// * a glob pattern (e.g. `coverage/**/*.info`): all files matching the pattern.
+/// * otherwise: a file.
+///
+/// If `stdin_list` is `true`, newline-separated paths read from the standard input are appended.
+/// Empty lines are ignored.
+pub fn collect(args: &[PathBuf], stdin_list: bool) -> io::Result<Vec<Input>> {
+    let mut inputs = vec![];
+    for arg in args {
+        expand(arg, &mut inputs)?;
+    }
+    if stdin_list {
+        if inputs.contains(&Input::Stdin) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "`-` cannot be used with --stdin-list",
+            ));
+        }
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if !line.is_empty() {
+                inputs.push(Input::File(line.into()));
+            }
+        }
+    }
+    Ok(inputs)
+}
+
+fn expand(arg: &Path, inputs: &mut Vec<Input>) -> io::Result<()> {
+    if arg == Path::new("-") {
+        inputs.push(Input::Stdin);
+    } else if arg.is_dir() {
+        let mut files = vec![];
+        for entry in WalkDir::new(arg) {
+            let entry = entry?;
+            if entry.file_type().is_file() && is_tracefile(entry.path()) {
+                files.push(entry.into_path());
+            }
+        }
+        files.sort();
+        inputs.extend(files.into_iter().map(Input::File));
+    } else if !arg.exists() && is_glob(arg) {
+        let pattern = arg.to_string_lossy();
+        let paths =
+            glob::glob(&pattern).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut files = vec![];
+        for path in paths {
+            let path = path.map_err(|e| io::Error::new(e.error().kind(), e.to_string()))?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        if files.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no tracefiles match `{}`", pattern),
+            ));
+        }
+        inputs.extend(files.into_iter().map(Input::File));
+    } else {
+        inputs.push(Input::File(arg.into()));
+    }
+    Ok(())
+}
+
+fn is_tracefile(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "info")
+}
+
+fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
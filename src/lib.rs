@@ -0,0 +1,30 @@
+//! Common implementation of the commands in `lcov-util`.
+//!
+//! This library is an implementation detail of the commands, and is not intended to be used
+//! by other crates.
+
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+pub mod annotate;
+pub mod cobertura;
+pub mod config;
+pub mod error;
+pub mod gate;
+pub mod generate;
+pub mod html;
+pub mod input;
+pub mod lines;
+pub mod missing;
+pub mod output;
+pub mod paths;
+pub mod rc;
+pub mod watch;
+pub mod wildcard;
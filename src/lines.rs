@@ -0,0 +1,195 @@
+//! Line ranges of source files, specified on the command line or by a unified diff.
+use lcov::filter::line_num::{LineNum, Range};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Line ranges of source files.
+#[derive(Debug, Clone, Default)]
+pub struct FileLines {
+    files: BTreeMap<PathBuf, LineNum>,
+}
+
+impl FileLines {
+    /// Creates an empty set of line ranges.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no files are specified.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Adds line ranges specified in `FILE:RANGES` form, such as `src/main.rs:1-5,10,20-`.
+    pub fn add_spec(&mut self, spec: &str) -> Result<(), String> {
+        let (file, ranges) = spec
+            .rsplit_once(':')
+            .ok_or_else(|| format!("invalid line specification `{}`", spec))?;
+        let filter = self.files.entry(file.into()).or_default();
+        for range in ranges.split(',') {
+            filter.insert(
+                parse_range(range)
+                    .ok_or_else(|| format!("invalid line range `{}` in `{}`", range, spec))?,
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds the lines added or modified by a unified diff.
+    pub fn add_diff(&mut self, diff: &str) {
+        let mut file = None;
+        let (mut old_rest, mut new_rest, mut line) = (0, 0, 0);
+        for text in diff.lines() {
+            if old_rest > 0 || new_rest > 0 {
+                // Lines beyond the counts of the header are ignored, since the hunk is malformed.
+                match text.as_bytes().first() {
+                    Some(b'+') if new_rest > 0 => {
+                        if let Some(file) = &file {
+                            self.files
+                                .entry(PathBuf::from(file))
+                                .or_default()
+                                .insert(Range::from_line(line));
+                        }
+                        new_rest -= 1;
+                        line = line.saturating_add(1);
+                    }
+                    Some(b'+') => {}
+                    Some(b'-') => old_rest = u32::saturating_sub(old_rest, 1),
+                    Some(b'\\') => {}
+                    _ => {
+                        old_rest = u32::saturating_sub(old_rest, 1);
+                        new_rest = u32::saturating_sub(new_rest, 1);
+                        line = line.saturating_add(1);
+                    }
+                }
+                continue;
+            }
+            if let Some(path) = text.strip_prefix("+++ ") {
+                // Strips the timestamp.
+                let path = path.split('\t').next().unwrap_or(path);
+                file = match path {
+                    "/dev/null" => None,
+                    path => Some(path.strip_prefix("b/").unwrap_or(path).to_owned()),
+                };
+            } else if let Some(header) = text.strip_prefix("@@ ") {
+                if let Some((old, new)) = parse_hunk_header(header) {
+                    old_rest = old;
+                    (line, new_rest) = new;
+                }
+            }
+        }
+    }
+
+    /// Returns the line ranges of the source file.
+    ///
+    /// Relative paths match source files by trailing path components, so `src/main.rs` matches
+    /// `/path/to/project/src/main.rs`.
+    pub fn get(&self, source_file: &Path) -> Option<&LineNum> {
+        self.files.get(source_file).or_else(|| {
+            self.files
+                .iter()
+                .find(|(path, _)| path.is_relative() && source_file.ends_with(path))
+                .map(|(_, filter)| filter)
+        })
+    }
+}
+
+// `N`, `N-M`, `N-` or `-M`.
+fn parse_range(s: &str) -> Option<Range> {
+    let range = match s.split_once('-') {
+        None => Range::from_line(s.trim().parse().ok()?),
+        Some((start, end)) => {
+            let (start, end) = (start.trim(), end.trim());
+            match (start.is_empty(), end.is_empty()) {
+                (false, false) => {
+                    let (start, end) = (start.parse().ok()?, end.parse::<u32>().ok()?);
+                    if start > end {
+                        return None;
+                    }
+                    Range::from(start..end.saturating_add(1))
+                }
+                (false, true) => Range::from(start.parse::<u32>().ok()?..),
+                (true, false) => Range::from(..end.parse::<u32>().ok()?.saturating_add(1)),
+                (true, true) => return None,
+            }
+        }
+    };
+    Some(range)
+}
+
+// `-l,s +l,s @@ ...`. Returns the numbers of old lines, and the start line and the number of
+// new lines.
+fn parse_hunk_header(header: &str) -> Option<(u32, (u32, u32))> {
+    fn parse(s: &str) -> Option<(u32, u32)> {
+        match s.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((s.parse().ok()?, 1)),
+        }
+    }
+    let mut sp = header.split_whitespace();
+    let (_, old) = parse(sp.next()?.strip_prefix('-')?)?;
+    let new = parse(sp.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_range, FileLines, LineNum, Range};
+    use std::path::Path;
+
+    #[test]
+    fn range() {
+        assert_eq!(parse_range("3"), Some(Range::from_line(3)));
+        assert_eq!(parse_range("3-5"), Some(Range::from(3..6)));
+        assert_eq!(parse_range("3-"), Some(Range::from(3..)));
+        assert_eq!(parse_range("-5"), Some(Range::from(..6)));
+        assert_eq!(parse_range("5-3"), None);
+        assert_eq!(parse_range("-"), None);
+        assert_eq!(parse_range("a"), None);
+    }
+
+    #[test]
+    fn diff() {
+        let diff = "\
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
+ fn main() {
++    foo();
+-    bar();
++    baz();
+ }
+--- a/src/deleted.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn deleted() {}
+";
+        let mut lines = FileLines::new();
+        lines.add_diff(diff);
+        let filter = lines.get(Path::new("/path/to/src/main.rs")).unwrap();
+        let mut expected = LineNum::new();
+        expected.insert(2..4);
+        assert_eq!(*filter, expected);
+        assert!(lines.get(Path::new("src/deleted.rs")).is_none());
+    }
+
+    #[test]
+    fn malformed_diff() {
+        // More added lines than the header says, while old lines remain.
+        let diff = "\
++++ b/src/main.rs
+@@ -1,3 +1,1 @@
++foo();
++bar();
++baz();
+-qux();
+ }
+";
+        let mut lines = FileLines::new();
+        lines.add_diff(diff);
+        let filter = lines.get(Path::new("src/main.rs")).unwrap();
+        let mut expected = LineNum::new();
+        expected.insert(1..2);
+        assert_eq!(*filter, expected);
+    }
+}
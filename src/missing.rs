@@ -0,0 +1,149 @@
+//! Adding source files which appear in no tracefile, with zero coverage.
+use lcov::Report;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Adds a zero-coverage section for each source file in `dir` which matches any of `include`
+/// and appears in no section of `report`.
+///
+/// Patterns are matched against the paths relative to `dir`. A source file appears in the report
+/// if the path of a section refers to the same file, where relative paths are resolved against
+/// the current directory (e.g. `src/foo.c` for `--add-missing src`) or `dir` (e.g. `foo.c`).
+/// The added sections have the path in `dir` and an empty test name.
+///
+/// Since the instrumented lines of files never compiled are unknown, all lines except blank
+/// lines, comments and lines of only brackets are regarded as instrumented.
+///
+/// Returns the paths of the source files added.
+pub fn add_missing(
+    report: &mut Report,
+    dir: &Path,
+    include: &[glob::Pattern],
+) -> io::Result<Vec<PathBuf>> {
+    add_missing_in(report, &env::current_dir()?, dir, include)
+}
+
+fn add_missing_in(
+    report: &mut Report,
+    cwd: &Path,
+    dir: &Path,
+    include: &[glob::Pattern],
+) -> io::Result<Vec<PathBuf>> {
+    let base = resolve(cwd, dir);
+    let mut known = HashSet::new();
+    for key in report.sections.keys() {
+        let _ = known.insert(resolve(cwd, &key.source_file));
+        if key.source_file.is_relative() {
+            let _ = known.insert(resolve(&base, &key.source_file));
+        }
+    }
+
+    let mut added = vec![];
+    for entry in WalkDir::new(&base).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(&base).unwrap_or(entry.path());
+        if !include.iter().any(|pattern| pattern.matches_path(relative)) {
+            continue;
+        }
+        if known.contains(&resolve(&base, relative)) {
+            continue;
+        }
+        let path = dir.join(relative);
+
+        let source = match fs::read(entry.path()) {
+            Ok(source) => String::from_utf8_lossy(&source).into_owned(),
+            Err(e) => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("{}: {}", path.display(), e),
+                ))
+            }
+        };
+        let mut section = report.section("", &path);
+        for (i, text) in source.lines().enumerate() {
+            if is_instrumentable(text) {
+                section = section.add_line(i as u32 + 1, 0);
+            }
+        }
+        added.push(path);
+    }
+    Ok(added)
+}
+
+// Resolves `path` against `base`, following symbolic links if the file exists, so that
+// different paths of the same file compare equal.
+fn resolve(base: &Path, path: &Path) -> PathBuf {
+    let path = base.join(path);
+    fs::canonicalize(&path).unwrap_or(path)
+}
+
+fn is_instrumentable(text: &str) -> bool {
+    let text = text.trim();
+    !(text.is_empty()
+        || text.starts_with("//")
+        || text.starts_with("/*")
+        || text.starts_with('*')
+        || text.chars().all(|c| "{}()[];,".contains(c)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("main.c"), "int main() {\n  return 0;\n}\n").unwrap();
+        fs::write(
+            src.join("sub/foo.c"),
+            "// comment\n\nvoid foo() {\n  bar();\n}\n",
+        )
+        .unwrap();
+        fs::write(src.join("README"), "not a source file\n").unwrap();
+
+        let mut report = Report::new();
+        let _ = report.section("", src.join("main.c")).add_line(1, 1);
+        let include = [glob::Pattern::new("**/*.c").unwrap()];
+        let added = super::add_missing(&mut report, &src, &include).unwrap();
+
+        assert_eq!(added, vec![src.join("sub/foo.c")]);
+        assert_eq!(report.line_count(src.join("main.c"), 1), Some(1));
+        assert_eq!(report.line_count(src.join("main.c"), 2), None);
+        let foo = src.join("sub/foo.c");
+        assert_eq!(report.line_count(&foo, 1), None);
+        assert_eq!(report.line_count(&foo, 3), Some(0));
+        assert_eq!(report.line_count(&foo, 4), Some(0));
+        assert_eq!(report.line_count(&foo, 5), None);
+    }
+
+    #[test]
+    fn absolute_source_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("src/main.c"), "int main() {\n  return 0;\n}\n").unwrap();
+        fs::write(root.join("src/foo.c"), "void foo() {\n}\n").unwrap();
+
+        // Absolute paths of measured files, and a file of the same name in another directory.
+        let mut report = Report::new();
+        let _ = report.section("", root.join("src/main.c")).add_line(1, 1);
+        let _ = report.section("", root.join("other/foo.c")).add_line(1, 1);
+        let include = [glob::Pattern::new("*.c").unwrap()];
+        let added = add_missing_in(&mut report, &root, Path::new("src"), &include).unwrap();
+
+        assert_eq!(added, vec![PathBuf::from("src/foo.c")]);
+        assert_eq!(report.sections.len(), 3);
+        assert_eq!(report.line_count("src/foo.c", 1), Some(0));
+        assert_eq!(report.line_count("src/main.c", 1), None);
+    }
+}
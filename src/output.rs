@@ -0,0 +1,187 @@
+//! Writing LCOV records to the standard output or a file.
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lcov::{Record, Report};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use tempfile::Builder;
+
+/// Writes LCOV records to `path`, or to the standard output if `path` is `None`.
+///
+/// The file is written atomically: the records are written to a temporary file in the same
+/// directory, which is renamed to `path` on success. So an existing file is never truncated
+/// even if the command is interrupted. The permissions of an existing file are kept, and a new
+/// file is created with the default permissions, as `File::create` does.
+///
+/// If the file name ends with `.gz`, the output is compressed with gzip.
+pub fn write_records<I>(path: Option<&Path>, records: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = Record>,
+{
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let stdout = io::stdout();
+            let mut writer = BufWriter::new(stdout.lock());
+            write_all(&mut writer, records)?;
+            return writer.flush();
+        }
+    };
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut builder = Builder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Temporary files are created with the mode 0600 by default. The umask still applies.
+        let _ = builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let mut file = builder.tempfile_in(dir)?;
+    {
+        let mut writer = BufWriter::new(file.as_file_mut());
+        if is_gzip(path) {
+            let mut encoder = GzEncoder::new(&mut writer, Compression::default());
+            write_all(&mut encoder, records)?;
+            let _ = encoder.finish()?;
+        } else {
+            write_all(&mut writer, records)?;
+        }
+        writer.flush()?;
+    }
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(file.path(), metadata.permissions())?;
+    }
+    file.as_file().sync_all()?;
+    let _ = file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Converts the path of a source file into a relative path to write a tracefile for it.
+///
+/// Root and prefix components are dropped, and `..` components are replaced with `__`, so the
+/// result never escapes from the directory it is joined to. `.` components are dropped.
+pub fn relative_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            Component::ParentDir => Some("__".as_ref()),
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => None,
+        })
+        .collect()
+}
+
+/// Maps the keys of split reports to output paths by `output`, merging the reports which map to
+/// the same path.
+///
+/// Distinct source paths may map to the same output path (e.g. `/usr/x.c` and `usr/x.c` by
+/// [`relative_path`]), and writing them separately would silently overwrite one of them.
+pub fn group_by_output<F>(
+    reports: BTreeMap<PathBuf, Report>,
+    mut output: F,
+) -> BTreeMap<PathBuf, Report>
+where
+    F: FnMut(&Path) -> PathBuf,
+{
+    let mut grouped = BTreeMap::<PathBuf, Report>::new();
+    for (path, report) in reports {
+        // Split reports have disjoint sections, so merging them never conflicts.
+        grouped
+            .entry(output(&path))
+            .or_default()
+            .merge_lossy(report);
+    }
+    grouped
+}
+
+fn write_all<W, I>(writer: &mut W, records: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = Record>,
+{
+    for rec in records {
+        // Writes the raw bytes of paths, which are read losslessly by `input`.
+        rec.write_to(writer)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group_by_output, relative_path, write_records};
+    use lcov::Report;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn relative() {
+        assert_eq!(
+            relative_path(Path::new("src/main.rs")),
+            Path::new("src/main.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/usr/include/stdio.h")),
+            Path::new("usr/include/stdio.h")
+        );
+        assert_eq!(
+            relative_path(Path::new("./a/../b.c")),
+            Path::new("a/__/b.c")
+        );
+        assert_eq!(relative_path(Path::new(".")), Path::new(""));
+    }
+
+    #[test]
+    fn colliding_outputs() {
+        let mut report = Report::new();
+        let _ = report.section("", "/usr/x.c").add_line(1, 1);
+        let _ = report.section("", "usr/x.c").add_line(1, 0);
+        let _ = report.section("", "usr/y.c").add_line(1, 0);
+
+        let grouped = group_by_output(report.split_by_file(), relative_path);
+        assert_eq!(grouped.len(), 2);
+        let merged = &grouped[Path::new("usr/x.c")];
+        assert_eq!(merged.sections.len(), 2);
+        assert_eq!(merged.line_count("/usr/x.c", 1), Some(1));
+        assert_eq!(merged.line_count("usr/x.c", 1), Some(0));
+
+        let mut report = Report::new();
+        let _ = report.section("", "/a.c").add_line(1, 1);
+        let _ = report.section("", "b.c").add_line(1, 0);
+        let grouped = group_by_output(report.split_by_directory(None), relative_path);
+        assert_eq!(grouped.keys().collect::<Vec<_>>(), [&PathBuf::new()]);
+        assert_eq!(grouped[Path::new("")].sections.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions() {
+        use std::fs::{self, File, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let mut report = Report::new();
+        let _ = report.section("", "a.c").add_line(1, 1);
+
+        let path = dir.path().join("new.info");
+        write_records(Some(&path), report.clone().into_records()).unwrap();
+        let created = dir.path().join("created.info");
+        let _ = File::create(&created).unwrap();
+        assert_eq!(mode(&path), mode(&created));
+
+        let path = dir.path().join("existing.info");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o640)).unwrap();
+        write_records(Some(&path), report.into_records()).unwrap();
+        assert_eq!(mode(&path), 0o640);
+        assert!(!fs::read_to_string(&path).unwrap().is_empty());
+    }
+}
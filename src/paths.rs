@@ -0,0 +1,107 @@
+//! Rewriting the paths of source files by command line options.
+use crate::config::{Config, Remap};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Command line options to rewrite the paths of source files (`SF` records).
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct PathOptions {
+    /// Removes PREFIX from the paths of source files (can be specified multiple times)
+    #[clap(long = "strip-prefix", value_name = "PREFIX")]
+    pub strip_prefix: Vec<PathBuf>,
+
+    /// Replaces the prefix OLD of the paths of source files with NEW (can be specified multiple
+    /// times)
+    #[clap(long = "map", value_name = "OLD=NEW")]
+    pub map: Vec<Mapping>,
+
+    /// Prepends PREFIX to the relative paths of source files
+    #[clap(long = "add-prefix", value_name = "PREFIX")]
+    pub add_prefix: Option<PathBuf>,
+}
+
+impl PathOptions {
+    /// Appends the rewrites to the configuration.
+    ///
+    /// The rewrites are tried after the `[[remap]]` tables in the configuration file, and the
+    /// first one which applies to a path is used. `--add-prefix` is applied after them.
+    pub fn apply(&self, config: &mut Config) {
+        let strip = self.strip_prefix.iter().map(|prefix| Remap::Prefix {
+            from: prefix.clone(),
+            to: PathBuf::new(),
+        });
+        let map = self.map.iter().map(|mapping| Remap::Prefix {
+            from: mapping.from.clone(),
+            to: mapping.to.clone(),
+        });
+        config.remap.extend(strip.chain(map));
+        if let Some(prefix) = &self.add_prefix {
+            config.add_prefix = Some(prefix.clone());
+        }
+    }
+}
+
+/// A rewrite of the prefix of paths in the form of `OLD=NEW`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mapping {
+    /// Prefix of the paths to rewrite.
+    pub from: PathBuf,
+    /// Path which replaces the prefix.
+    pub to: PathBuf,
+}
+
+impl FromStr for Mapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| format!("`{}` is not in the form of `OLD=NEW`", s))?;
+        Ok(Mapping {
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathOptions;
+    use crate::config::Config;
+    use lcov::Report;
+    use std::path::Path;
+
+    #[test]
+    fn apply() {
+        let options = PathOptions {
+            strip_prefix: vec!["/build/".into()],
+            map: vec!["/home/alice/project=src".parse().unwrap()],
+            add_prefix: Some("repo".into()),
+        };
+        let mut config = Config::default();
+        options.apply(&mut config);
+
+        let mut report = Report::new();
+        let _ = report.section("", "/build/main.rs").add_line(1, 1);
+        let _ = report
+            .section("", "/home/alice/project/lib.rs")
+            .add_line(1, 1);
+        let _ = report.section("", "/usr/include/stdio.h").add_line(1, 1);
+        config.apply(&mut report).unwrap();
+
+        let paths = report
+            .sections
+            .keys()
+            .map(|key| key.source_file.as_path())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                Path::new("/usr/include/stdio.h"),
+                Path::new("repo/main.rs"),
+                Path::new("repo/src/lib.rs"),
+            ]
+        );
+        assert!("/build".parse::<super::Mapping>().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Settings of the Perl implementation of `lcov`, specified by `--rc KEY=VALUE`.
+//!
+//! The following settings are honored, as well as their `lcov_` and `geninfo_` prefixed forms.
+//! Other settings are ignored with a warning, so that command lines of `lcov` can be reused.
+//!
+//! * `branch_coverage`: `0` removes branch coverage information, like `--no-branches`.
+//! * `function_coverage`: `0` removes function coverage information.
+//! * `checksum`: `0` removes checksums of lines, like `--no-checksums`.
+//! * `adjust_src_path`: `FROM => TO` replaces the prefix `FROM` of the paths of source files with
+//!   `TO`, like a `[[remap]]` table.
+use crate::config::{Config, Remap};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Command line options to specify the settings of `lcov`.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct Rc {
+    /// Applies a setting of `lcov`, e.g. `branch_coverage=0` (can be specified multiple times)
+    ///
+    /// `branch_coverage`, `function_coverage`, `checksum` and `geninfo_adjust_src_path` are
+    /// supported. Other settings are ignored with a warning.
+    #[clap(long = "rc", value_name = "KEY=VALUE")]
+    pub settings: Vec<Setting>,
+}
+
+impl Rc {
+    /// Applies the settings to the configuration, overriding the configuration file.
+    pub fn apply(&self, config: &mut Config) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for setting in &self.settings {
+            setting.apply(config)?;
+        }
+        Ok(())
+    }
+}
+
+/// A setting of `lcov` in the form of `KEY=VALUE`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Setting {
+    /// Name of the setting.
+    pub key: String,
+    /// Value of the setting.
+    pub value: String,
+}
+
+impl Setting {
+    /// Applies the setting to the configuration.
+    ///
+    /// Prints a warning to the standard error if the setting is not supported.
+    pub fn apply(&self, config: &mut Config) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let key = self.key.as_str();
+        let key = key
+            .strip_prefix("lcov_")
+            .or_else(|| key.strip_prefix("geninfo_"))
+            .unwrap_or(key);
+        match key {
+            "branch_coverage" => config.filter.no_branches = !self.flag()?,
+            "function_coverage" => config.filter.no_functions = !self.flag()?,
+            "checksum" => config.filter.no_checksums = !self.flag()?,
+            "adjust_src_path" => {
+                let (from, to) = self
+                    .value
+                    .split_once("=>")
+                    .ok_or_else(|| format!("`{}` requires a value of `FROM => TO`", self.key))?;
+                config.remap.push(Remap::Prefix {
+                    from: from.trim().into(),
+                    to: to.trim().into(),
+                });
+            }
+            _ => eprintln!("warning: ignoring unsupported setting `{}`", self.key),
+        }
+        Ok(())
+    }
+
+    fn flag(&self) -> Result<bool, String> {
+        match self.value.as_str() {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            value => Err(format!(
+                "`{}` requires a value of `0` or `1`, but got `{}`",
+                self.key, value
+            )),
+        }
+    }
+}
+
+impl FromStr for Setting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("`{}` is not in the form of `KEY=VALUE`", s))?;
+        Ok(Setting {
+            key: key.trim().into(),
+            value: value.trim().into(),
+        })
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rc, Setting};
+    use crate::config::Config;
+    use lcov::Report;
+
+    fn rc(settings: &[&str]) -> Rc {
+        Rc {
+            settings: settings.iter().map(|s| s.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let setting = "geninfo_adjust_src_path = /build => /src"
+            .parse::<Setting>()
+            .unwrap();
+        assert_eq!(setting.key, "geninfo_adjust_src_path");
+        assert_eq!(setting.value, "/build => /src");
+        assert!("branch_coverage".parse::<Setting>().is_err());
+    }
+
+    #[test]
+    fn apply() {
+        let mut config = Config::default();
+        rc(&[
+            "lcov_branch_coverage=0",
+            "function_coverage=0",
+            "geninfo_adjust_src_path=/build/ =>",
+            "genhtml_hi_limit=90",
+        ])
+        .apply(&mut config)
+        .unwrap();
+
+        let mut report = Report::new();
+        let _ = report
+            .section("", "/build/src/main.rs")
+            .add_line(1, 1)
+            .add_function("main", 1, 1)
+            .add_branch(1, 0, 0, Some(1));
+        config.apply(&mut report).unwrap();
+
+        let (key, value) = report.sections.iter().next().unwrap();
+        assert_eq!(key.source_file.to_str(), Some("src/main.rs"));
+        assert!(value.branches.is_empty());
+        assert!(value.functions.is_empty());
+        assert_eq!(value.lines.len(), 1);
+    }
+
+    #[test]
+    fn invalid_value() {
+        let mut config = Config::default();
+        assert!(rc(&["branch_coverage=yes"]).apply(&mut config).is_err());
+        assert!(rc(&["geninfo_adjust_src_path=/build"])
+            .apply(&mut config)
+            .is_err());
+    }
+}
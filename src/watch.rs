@@ -0,0 +1,94 @@
+//! Watching input tracefiles for changes by polling.
+use crate::input::{self, Input};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Modification times and sizes of the tracefiles specified by command line arguments.
+///
+/// The arguments are expanded again on every poll, so files added to the directories or matching
+/// the glob patterns are also detected.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, Option<(SystemTime, u64)>>,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the tracefiles specified by `args`, as [`input::collect`] expands them.
+    ///
+    /// Arguments which currently match no files are ignored. The standard input and the files in
+    /// `ignored`, such as the output of the command, are never watched.
+    pub fn take(args: &[PathBuf], ignored: &[PathBuf]) -> Self {
+        let mut files = BTreeMap::new();
+        for arg in args {
+            // A glob pattern may match no files while the tracefiles are being regenerated.
+            let inputs = input::collect(std::slice::from_ref(arg), false).unwrap_or_default();
+            for input in inputs {
+                if let Input::File(path) = input {
+                    if ignored.iter().any(|ignored| same_file(ignored, &path)) {
+                        continue;
+                    }
+                    let stat = stat(&path);
+                    let _ = files.insert(path, stat);
+                }
+            }
+        }
+        Snapshot { files }
+    }
+
+    /// Blocks until the tracefiles change from `self`, and returns the new snapshot.
+    ///
+    /// The tracefiles are polled every `interval`. A change is reported after the tracefiles stay
+    /// the same for another `interval`, so tracefiles being written are not read halfway.
+    pub fn wait_for_change(
+        &self,
+        args: &[PathBuf],
+        ignored: &[PathBuf],
+        interval: Duration,
+    ) -> Self {
+        let mut last = self.clone();
+        loop {
+            thread::sleep(interval);
+            let current = Snapshot::take(args, ignored);
+            if current == last && current != *self {
+                return current;
+            }
+            last = current;
+        }
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b || matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+fn stat(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snapshot;
+    use std::fs;
+
+    #[test]
+    fn detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = [dir.path().to_owned()];
+        let ignored = [dir.path().join("merged.info")];
+        let empty = Snapshot::take(&args, &ignored);
+
+        fs::write(dir.path().join("a.info"), "TN:\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        fs::write(dir.path().join("merged.info"), "").unwrap();
+        let added = Snapshot::take(&args, &ignored);
+        assert_ne!(added, empty);
+        assert_eq!(added.files.len(), 1);
+
+        fs::write(dir.path().join("a.info"), "TN:\nSF:a.c\n").unwrap();
+        assert_ne!(Snapshot::take(&args, &ignored), added);
+    }
+}
@@ -0,0 +1,103 @@
+//! Shell wildcard patterns with the semantics of `lcov --extract` and `lcov --remove`.
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A shell wildcard pattern matched against the whole path of a source file.
+///
+/// The pattern is interpreted in the same way as the Perl implementation of `lcov`: `*` matches
+/// any sequence of characters including `/`, `?` matches any single character, and all the other
+/// characters, including `[` and `\`, match themselves.
+///
+/// # Examples
+///
+/// ```rust
+/// use lcov_util::wildcard::Wildcard;
+/// use std::path::Path;
+///
+/// let pattern = "*/src/*.c".parse::<Wildcard>().unwrap();
+/// assert!(pattern.matches_path(Path::new("/home/alice/project/src/lib/foo.c")));
+/// assert!(!pattern.matches_path(Path::new("src/foo.c")));
+/// assert!(!pattern.matches_path(Path::new("/project/src/foo.cc")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Wildcard {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Wildcard {
+    /// Returns `true` if the pattern matches the whole path.
+    ///
+    /// Paths which are not valid UTF-8 are matched lossily.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        self.regex.is_match(&path.to_string_lossy())
+    }
+
+    /// Returns the original pattern.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+impl FromStr for Wildcard {
+    type Err = regex::Error;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let mut regex = String::from("^(?s:");
+        let mut buf = [0; 4];
+        for c in pattern.chars() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                _ => regex.push_str(&regex::escape(c.encode_utf8(&mut buf))),
+            }
+        }
+        regex.push_str(")$");
+        Ok(Wildcard {
+            pattern: pattern.into(),
+            regex: Regex::new(&regex)?,
+        })
+    }
+}
+
+impl fmt::Display for Wildcard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Wildcard;
+    use std::path::Path;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        pattern
+            .parse::<Wildcard>()
+            .unwrap()
+            .matches_path(Path::new(path))
+    }
+
+    #[test]
+    fn whole_path() {
+        assert!(matches("/usr/*", "/usr/include/stdio.h"));
+        assert!(matches("*stdio.h", "/usr/include/stdio.h"));
+        assert!(!matches("usr/*", "/usr/include/stdio.h"));
+        assert!(!matches("*/include", "/usr/include/stdio.h"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn literal_characters() {
+        assert!(matches("src/[ab].c", "src/[ab].c"));
+        assert!(!matches("src/[ab].c", "src/a.c"));
+        assert!(matches("src/a.c", "src/a.c"));
+        assert!(!matches("src/a.c", "src/abc"));
+        assert!(matches(r"C:\src\*", r"C:\src\main.c"));
+        assert!(matches("src/a+b(1).c", "src/a+b(1).c"));
+        assert!(matches("src/?.c", "src/a.c"));
+        assert!(!matches("src/?.c", "src/ab.c"));
+    }
+}
@@ -0,0 +1,34 @@
+use std::fs;
+use std::process::Command;
+
+const UNCOVERED: &str = "TN:\nSF:src/main.rs\nDA:1,0\nend_of_record\n";
+
+fn exit_code(bin: &str, args: &[&str]) -> Option<i32> {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("a.info");
+    fs::write(&input, UNCOVERED).unwrap();
+    Command::new(bin)
+        .args(args)
+        .arg(&input)
+        .current_dir(dir.path())
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+#[test]
+fn merge_fail_under() {
+    let bin = env!("CARGO_BIN_EXE_lcov-merge");
+    assert_eq!(exit_code(bin, &["--fail-under-lines", "50"]), Some(3));
+    assert_eq!(exit_code(bin, &["--fail-under-lines", "0"]), Some(0));
+    assert_eq!(exit_code(bin, &["--bogus"]), Some(2));
+}
+
+#[test]
+fn summary_fail_under() {
+    let bin = env!("CARGO_BIN_EXE_lcov-summary");
+    assert_eq!(exit_code(bin, &["--fail-under-lines", "50"]), Some(3));
+    assert_eq!(exit_code(bin, &["--fail-under-lines", "0"]), Some(0));
+    assert_eq!(exit_code(bin, &["--bogus"]), Some(2));
+}